@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bip39::{Language, Mnemonic, MnemonicType};
+use clap::{Parser, Subcommand};
+use futures_util::StreamExt;
+use serde_json::Value as JsonValue;
+use solana_sdk::pubkey::Pubkey;
+use sunshine_core::msg::{GraphId, NodeId, Properties};
+use sunshine_core::store::Datastore;
+use sunshine_indra::store::{DbConfig, DB};
+use sunshine_solana::commands::solana::generate_keypair::generate_keypair;
+use sunshine_solana::commands::solana::{Config, Ctx, SolanaNet};
+use sunshine_solana::{
+    FlowBuilder, FlowContext, Limits, OverlapPolicy, RunEvent, RunOptions, RunState, Schedule,
+    Value,
+};
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Parser)]
+#[clap(name = "sunshine", about = "Import, validate and run sunshine flows")]
+struct Cli {
+    /// Path of the sled store, the same one sunshine_server uses
+    #[clap(long, env = "SUNSHINE_DB", default_value = "sunshine_db")]
+    db: String,
+    /// Json file with the concurrency and rate limits of the runs
+    #[clap(long, env = "SUNSHINE_LIMITS")]
+    limits: Option<PathBuf>,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every graph in the store
+    Graphs,
+    /// Import a flow exported with `export` or defined in yaml/toml, printing its new id
+    Import { file: PathBuf },
+    /// Export a flow as JSON
+    Export {
+        flow_id: GraphId,
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Check a flow for problems without running it
+    Validate { flow_id: GraphId },
+    /// Freeze the current graph of a flow as its next version
+    Publish { flow_id: GraphId },
+    /// Run a flow once, or on a schedule with --every/--cron
+    Run {
+        flow_id: GraphId,
+        /// A flow input as name=json, plain strings don't need quotes
+        #[clap(short, long = "input")]
+        inputs: Vec<String>,
+        #[clap(long)]
+        dry_run: bool,
+        /// Run a version published with `publish` instead of the live graph
+        #[clap(long)]
+        version: Option<u64>,
+        /// Run every given number of seconds until interrupted
+        #[clap(long, conflicts_with = "cron")]
+        every: Option<u64>,
+        /// Run on a cron expression until interrupted
+        #[clap(long)]
+        cron: Option<String>,
+    },
+    /// Print the node logs of a run
+    Logs {
+        run_id: Uuid,
+        /// Keep printing the logs of a run in progress until it finishes, through the server
+        /// which runs it and holds the store
+        #[clap(short, long)]
+        follow: bool,
+        /// Address of the sunshine_server to follow the run through
+        #[clap(long, env = "SUNSHINE_ADDR", default_value = "127.0.0.1:8080")]
+        server: String,
+    },
+    /// Manage the keypairs and pubkeys of a wallet graph
+    Wallet {
+        #[clap(subcommand)]
+        command: WalletCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum WalletCommand {
+    /// Create an empty wallet graph, printing its id
+    Create,
+    List {
+        #[clap(long)]
+        wallet: GraphId,
+    },
+    /// Store a keypair generated from a seed phrase, a new one is generated if it's missing
+    AddKeypair {
+        #[clap(long)]
+        wallet: GraphId,
+        name: String,
+        #[clap(long)]
+        seed_phrase: Option<String>,
+        #[clap(long, default_value = "")]
+        passphrase: String,
+    },
+    AddPubkey {
+        #[clap(long)]
+        wallet: GraphId,
+        name: String,
+        pubkey: String,
+    },
+    Delete {
+        #[clap(long)]
+        wallet: GraphId,
+        node_id: NodeId,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// returns the exit code
+async fn run(cli: Cli) -> Result<i32, Error> {
+    if let Command::Logs {
+        run_id,
+        follow: true,
+        server,
+    } = &cli.command
+    {
+        return follow_run(server, *run_id).await;
+    }
+
+    let db: Arc<dyn Datastore> = Arc::new(DB::new(&DbConfig { db_path: cli.db })?);
+
+    let limits = match cli.limits {
+        Some(path) => serde_json::from_slice(&std::fs::read(path)?)?,
+        None => Limits::default(),
+    };
+
+    let flows = FlowContext::new(db.clone()).with_limits(&limits);
+
+    match cli.command {
+        Command::Graphs => {
+            for (graph_id, properties) in db.list_graphs().await? {
+                println!("{} {}", graph_id, JsonValue::Object(properties));
+            }
+        }
+        Command::Import { file } => {
+            let contents = std::fs::read_to_string(&file)?;
+
+            // yaml and toml files are flow definitions, everything else an exported flow
+            let flow_file = match file.extension().and_then(|e| e.to_str()) {
+                Some("yaml" | "yml") => FlowBuilder::from_yaml(&contents)?.build()?,
+                Some("toml") => FlowBuilder::from_toml(&contents)?.build()?,
+                _ => serde_json::from_str(&contents)?,
+            };
+
+            println!("{}", flows.import_flow(&flow_file).await?);
+        }
+        Command::Export { flow_id, output } => {
+            let file = serde_json::to_string_pretty(&flows.export_flow(flow_id).await?)?;
+
+            match output {
+                Some(path) => std::fs::write(path, file)?,
+                None => println!("{}", file),
+            }
+        }
+        Command::Validate { flow_id } => {
+            let problems = flows.validate_flow(flow_id).await?;
+
+            for problem in problems.iter() {
+                match problem.node_id {
+                    Some(node_id) => println!("node {}: {}", node_id, problem.message),
+                    None => println!("flow: {}", problem.message),
+                }
+            }
+
+            if !problems.is_empty() {
+                return Ok(1);
+            }
+        }
+        Command::Publish { flow_id } => {
+            let published = flows.publish_flow(flow_id).await?;
+
+            println!("version {} {}", published.version, published.graph_id);
+        }
+        Command::Run {
+            flow_id,
+            inputs,
+            dry_run,
+            version,
+            every,
+            cron,
+        } => {
+            let inputs = parse_inputs(&inputs)?;
+
+            let options = RunOptions {
+                dry_run,
+                version,
+                ..Default::default()
+            };
+
+            let schedule = match (every, cron) {
+                (Some(secs), _) => {
+                    Schedule::interval(Duration::from_secs(secs), OverlapPolicy::Skip)
+                }
+                (None, Some(expression)) => Schedule::cron(expression, OverlapPolicy::Skip),
+                (None, None) => return run_once(&flows, flow_id, inputs, options).await,
+            };
+
+            // scheduled runs don't simulate, there's no dry run option on a deployment
+            if dry_run {
+                return Err("--dry-run can't be combined with a schedule".into());
+            }
+
+            let events = flows.subscribe();
+
+            flows
+                .deploy_version(schedule, flow_id, version, inputs)
+                .await?;
+
+            tokio::select! {
+                _ = print_events(events, flow_id, std::future::pending()) => (),
+                _ = tokio::signal::ctrl_c() => (),
+            }
+
+            flows.undeploy_flow(flow_id).await?;
+        }
+        Command::Logs { run_id, .. } => {
+            for event in flows.run_events(run_id).await? {
+                println!("{}", format_event(&event));
+            }
+        }
+        Command::Wallet { command } => return wallet(db, command).await,
+    }
+
+    Ok(0)
+}
+
+async fn run_once(
+    flows: &FlowContext,
+    flow_id: GraphId,
+    inputs: HashMap<String, Value>,
+    options: RunOptions,
+) -> Result<i32, Error> {
+    let events = flows.subscribe();
+
+    let (send_finished, finished) = oneshot::channel::<()>();
+
+    let printer = tokio::spawn(print_events(events, flow_id, async move {
+        finished.await.ok();
+    }));
+
+    let run = flows.run_to_completion(flow_id, inputs, options).await;
+
+    send_finished.send(()).ok();
+    printer.await?;
+
+    let (run_id, outputs) = run?;
+
+    eprintln!("run {}", run_id);
+
+    let outputs = outputs
+        .into_iter()
+        .map(|(name, value)| Ok((name, JsonValue::try_from(value)?)))
+        .collect::<Result<serde_json::Map<_, _>, Error>>()?;
+
+    println!("{}", serde_json::to_string_pretty(&outputs)?);
+
+    Ok(0)
+}
+
+// prints the events of the flow until `finished` completes and then the events which were
+// already sent, every event of a run is sent before the run finishes
+async fn print_events(
+    mut events: broadcast::Receiver<RunEvent>,
+    flow_id: GraphId,
+    finished: impl Future<Output = ()>,
+) {
+    tokio::pin!(finished);
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if event.flow_id == flow_id => eprintln!("{}", format_event(&event)),
+                Ok(_) => (),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            _ = &mut finished => break,
+        }
+    }
+
+    loop {
+        match events.try_recv() {
+            Ok(event) if event.flow_id == flow_id => eprintln!("{}", format_event(&event)),
+            Ok(_) => (),
+            Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                eprintln!("skipped {} events", skipped);
+            }
+            Err(_) => break,
+        }
+    }
+}
+
+// prints the events of the run streamed by the server until it closes the stream once the
+// run finished
+async fn follow_run(server: &str, run_id: Uuid) -> Result<i32, Error> {
+    let url = format!("ws://{}/events?run_id={}", server, run_id);
+
+    let (mut stream, _) = tokio_tungstenite::connect_async(url).await?;
+
+    while let Some(message) = stream.next().await {
+        match message? {
+            Message::Text(text) => {
+                let event: RunEvent = serde_json::from_str(&text)?;
+
+                println!("{}", format_event(&event));
+            }
+            Message::Close(_) => break,
+            _ => (),
+        }
+    }
+
+    Ok(0)
+}
+
+fn format_event(event: &RunEvent) -> String {
+    let state = match &event.state {
+        RunState::Failed(_, error) => format!("Failed: {}", error),
+        state => format!("{:?}", state),
+    };
+
+    format!(
+        "{} {} {} {}",
+        event.at.to_rfc3339(),
+        event.run_id,
+        event.name,
+        state
+    )
+}
+
+// name=json, falling back to a string when the value isn't valid json
+fn parse_inputs(inputs: &[String]) -> Result<HashMap<String, Value>, Error> {
+    inputs
+        .iter()
+        .map(|input| {
+            let (name, value) = input
+                .split_once('=')
+                .ok_or_else(|| format!("input {} isn't name=value", input))?;
+
+            let value =
+                serde_json::from_str(value).unwrap_or_else(|_| JsonValue::String(value.to_owned()));
+
+            Ok((name.to_owned(), Value::try_from(value)?))
+        })
+        .collect()
+}
+
+async fn wallet(db: Arc<dyn Datastore>, command: WalletCommand) -> Result<i32, Error> {
+    // the net is only used to send transactions, which the wallet commands don't do
+    let ctx = |wallet_graph| {
+        Ctx::new(
+            Config {
+                solana_net: SolanaNet::Devnet,
+                wallet_graph,
+                solana_net_variable: None,
+            },
+            db.clone(),
+        )
+    };
+
+    match command {
+        WalletCommand::Create => {
+            let (_, graph_id) = db.create_graph(Properties::default()).await?;
+
+            println!("{}", graph_id);
+        }
+        WalletCommand::List { wallet } => {
+            for entry in ctx(wallet)?.list_wallet().await? {
+                let kind = if entry.has_keypair {
+                    "keypair"
+                } else {
+                    "pubkey"
+                };
+
+                println!("{} {} {} {}", entry.node_id, entry.name, kind, entry.pubkey);
+            }
+        }
+        WalletCommand::AddKeypair {
+            wallet,
+            name,
+            seed_phrase,
+            passphrase,
+        } => {
+            // the seed phrase is the only way to recover the keypair, so a generated one is shown
+            let seed_phrase = seed_phrase.unwrap_or_else(|| {
+                let phrase = Mnemonic::new(MnemonicType::Words12, Language::English)
+                    .phrase()
+                    .to_owned();
+
+                eprintln!("seed phrase: {}", phrase);
+
+                phrase
+            });
+
+            let keypair = generate_keypair(&passphrase, Some(seed_phrase))?;
+
+            let node_id = ctx(wallet)?.insert_keypair(name, &keypair).await?;
+
+            println!("{}", node_id);
+        }
+        WalletCommand::AddPubkey {
+            wallet,
+            name,
+            pubkey,
+        } => {
+            let pubkey = Pubkey::from_str(&pubkey)?;
+
+            let node_id = ctx(wallet)?.insert_pubkey(name, pubkey).await?;
+
+            println!("{}", node_id);
+        }
+        WalletCommand::Delete { wallet, node_id } => {
+            let pubkey = ctx(wallet)?.remove_pubkey(node_id).await?;
+
+            println!("{}", pubkey);
+        }
+    }
+
+    Ok(0)
+}
@@ -250,6 +250,7 @@ impl Datastore for DB {
                     .unwrap()
                     .value
             }
+            0 => return Err(Error::NodeNotFound),
             _ => unreachable!(),
         };
 
@@ -0,0 +1,69 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+
+#[derive(Debug)]
+pub enum ApiError {
+    Store(sunshine_core::Error),
+    Flow(sunshine_solana::Error),
+    BadRequest(String),
+}
+
+impl From<sunshine_core::Error> for ApiError {
+    fn from(error: sunshine_core::Error) -> ApiError {
+        ApiError::Store(error)
+    }
+}
+
+impl From<sunshine_solana::Error> for ApiError {
+    fn from(error: sunshine_solana::Error) -> ApiError {
+        ApiError::Flow(error)
+    }
+}
+
+fn store_status(error: &sunshine_core::Error) -> StatusCode {
+    use sunshine_core::Error;
+
+    match error {
+        Error::GraphNotFound | Error::NodeNotFound => StatusCode::NOT_FOUND,
+        Error::UndoBufferEmpty | Error::RedoBufferEmpty => StatusCode::CONFLICT,
+        Error::InvalidId(_) | Error::JsonError(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn flow_status(error: &sunshine_solana::Error) -> StatusCode {
+    use sunshine_solana::Error;
+
+    match error {
+        Error::Core(error) => store_status(error),
+        Error::FlowDoesntExist
+        | Error::RunDoesntExist
+        | Error::WebhookNotFound(_)
+        | Error::NodeNotPaused(_)
+        | Error::NodeNotAwaitingApproval(_)
+        | Error::VersionDoesntExist(_) => StatusCode::NOT_FOUND,
+        Error::RunInProgress | Error::WebhookPathInUse(_) | Error::SnapshotIsImmutable(_) => {
+            StatusCode::CONFLICT
+        }
+        Error::UnknownFlowVariable(_)
+        | Error::InvalidFlowVariables
+        | Error::NodeNotInFlow(_)
+        | Error::IncompatibleJson(_)
+        | Error::IncompatibleEdge { .. } => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Store(e) => (store_status(&e), e.to_string()),
+            ApiError::Flow(e) => (flow_status(&e), e.to_string()),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+        };
+
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
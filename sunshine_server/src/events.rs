@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Extension, Query};
+use axum::response::Response;
+use serde::Deserialize;
+use sunshine_solana::RunEvent;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use uuid::Uuid;
+
+use crate::State;
+
+// how often a followed run is checked for having finished
+const FINISHED_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Deserialize)]
+pub struct EventFilter {
+    run_id: Option<Uuid>,
+    flow_id: Option<Uuid>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &RunEvent) -> bool {
+        self.run_id.map_or(true, |run_id| run_id == event.run_id)
+            && self
+                .flow_id
+                .map_or(true, |flow_id| flow_id == event.flow_id)
+    }
+}
+
+// streams the state changes of nodes as json text messages. the stream of a single run
+// starts with the state changes recorded so far and is closed once the run finished
+pub async fn stream(
+    ws: WebSocketUpgrade,
+    Extension(state): Extension<State>,
+    Query(filter): Query<EventFilter>,
+) -> Response {
+    ws.on_upgrade(move |socket| forward(socket, state, filter))
+}
+
+async fn forward(mut socket: WebSocket, state: State, filter: EventFilter) {
+    let mut events = state.flows.subscribe();
+
+    // events which were already recorded when the stream started aren't sent twice
+    let mut recorded_until = None;
+
+    if let Some(run_id) = filter.run_id {
+        let recorded = match state.flows.run_events(run_id).await {
+            Ok(recorded) => recorded,
+            Err(e) => {
+                tracing::warn!(%run_id, error = %e, "failed to read events of run");
+                Vec::new()
+            }
+        };
+
+        for event in recorded {
+            recorded_until = Some(event.at);
+
+            if !send(&mut socket, &event).await {
+                return;
+            }
+        }
+    }
+
+    let is_new = |event: &RunEvent| {
+        filter.matches(event) && recorded_until.map_or(true, |until| event.at > until)
+    };
+
+    let mut finished_check = tokio::time::interval(FINISHED_CHECK_INTERVAL);
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "event stream lagged behind");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if is_new(&event) && !send(&mut socket, &event).await {
+                    return;
+                }
+            }
+            _ = finished_check.tick(), if filter.run_id.is_some() => {
+                if filter.run_id.map_or(false, |run_id| state.flows.is_running(run_id)) {
+                    continue;
+                }
+
+                // every event of a finished run was sent before it finished
+                loop {
+                    match events.try_recv() {
+                        Ok(event) => {
+                            if is_new(&event) && !send(&mut socket, &event).await {
+                                return;
+                            }
+                        }
+                        Err(TryRecvError::Lagged(_)) => (),
+                        Err(_) => break,
+                    }
+                }
+
+                break;
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                _ => (),
+            },
+        }
+    }
+
+    socket.send(Message::Close(None)).await.ok();
+}
+
+// returns false once the socket is closed
+async fn send(socket: &mut WebSocket, event: &RunEvent) -> bool {
+    let text = match serde_json::to_string(event) {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to serialize run event");
+            return true;
+        }
+    };
+
+    socket.send(Message::Text(text)).await.is_ok()
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use axum::extract::{Extension, Path};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Map, Value as JsonValue};
+use sunshine_core::msg::{GraphId, NodeId};
+use sunshine_solana::{RunOptions, Schedule, Value};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::State;
+
+// flow inputs and values of the api are plain json, converted without losing precision
+fn to_values(json: Map<String, JsonValue>) -> Result<HashMap<String, Value>, ApiError> {
+    json.into_iter()
+        .map(|(name, value)| Ok((name, Value::try_from(value)?)))
+        .collect()
+}
+
+fn to_json(values: HashMap<String, Value>) -> Result<JsonValue, ApiError> {
+    let json = values
+        .into_iter()
+        .map(|(name, value)| Ok((name, JsonValue::try_from(value)?)))
+        .collect::<Result<Map<_, _>, ApiError>>()?;
+
+    Ok(JsonValue::Object(json))
+}
+
+#[derive(Deserialize)]
+pub struct DeployRequest {
+    #[serde(default = "once")]
+    schedule: Schedule,
+    #[serde(default)]
+    inputs: Map<String, JsonValue>,
+    // a published version, the live graph without one
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+fn once() -> Schedule {
+    Schedule::Once
+}
+
+pub async fn deploy(
+    Extension(state): Extension<State>,
+    Path(flow_id): Path<GraphId>,
+    Json(request): Json<DeployRequest>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let inputs = to_values(request.inputs)?;
+
+    let run_id = state
+        .flows
+        .deploy_version(request.schedule, flow_id, request.version, inputs)
+        .await?;
+
+    Ok(Json(json!({ "run_id": run_id })))
+}
+
+pub async fn undeploy(
+    Extension(state): Extension<State>,
+    Path(flow_id): Path<GraphId>,
+) -> Result<Json<JsonValue>, ApiError> {
+    state.flows.undeploy_flow(flow_id).await?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+pub async fn publish(
+    Extension(state): Extension<State>,
+    Path(flow_id): Path<GraphId>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let version = state.flows.publish_flow(flow_id).await?;
+
+    Ok(Json(json!(version)))
+}
+
+pub async fn list_versions(
+    Extension(state): Extension<State>,
+    Path(flow_id): Path<GraphId>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let versions = state.flows.list_versions(flow_id).await?;
+
+    Ok(Json(json!(versions)))
+}
+
+pub async fn list_deployed(
+    Extension(state): Extension<State>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let deployments = state.flows.list_deployed().await?;
+
+    Ok(Json(json!(deployments)))
+}
+
+#[derive(Deserialize)]
+pub struct RunRequest {
+    #[serde(default)]
+    inputs: Map<String, JsonValue>,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    debug: bool,
+    // responds with the flow outputs once the run finished instead of the run id
+    #[serde(default)]
+    wait: bool,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+pub async fn run(
+    Extension(state): Extension<State>,
+    Path(flow_id): Path<GraphId>,
+    Json(request): Json<RunRequest>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let inputs = to_values(request.inputs)?;
+
+    let options = RunOptions {
+        dry_run: request.dry_run,
+        debug: request.debug,
+        version: request.version,
+    };
+
+    if !request.wait {
+        let run_id = state.flows.start_flow(flow_id, inputs, options).await?;
+
+        return Ok(Json(json!({ "run_id": run_id })));
+    }
+
+    // nobody could continue the breakpoints while the request waits
+    if request.debug {
+        return Err(ApiError::BadRequest(
+            "a debug run can't be waited for".to_owned(),
+        ));
+    }
+
+    let (run_id, outputs) = state
+        .flows
+        .run_to_completion(flow_id, inputs, options)
+        .await?;
+
+    Ok(Json(
+        json!({ "run_id": run_id, "outputs": to_json(outputs)? }),
+    ))
+}
+
+pub async fn get_run(
+    Extension(state): Extension<State>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let records = state.flows.get_run(run_id).await?;
+
+    Ok(Json(json!(records)))
+}
+
+pub async fn cancel_run(
+    Extension(state): Extension<State>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<JsonValue>, ApiError> {
+    state.flows.cancel_run(run_id)?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+pub async fn resume_run(
+    Extension(state): Extension<State>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<JsonValue>, ApiError> {
+    state.flows.resume_run(run_id).await?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+pub async fn paused_nodes(
+    Extension(state): Extension<State>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let paused = state.flows.paused_nodes(run_id)?;
+
+    Ok(Json(json!(paused)))
+}
+
+pub async fn continue_node(
+    Extension(state): Extension<State>,
+    Path((run_id, node_id)): Path<(Uuid, NodeId)>,
+    Json(overrides): Json<Map<String, JsonValue>>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let overrides = to_values(overrides)?;
+
+    state.flows.continue_node(run_id, node_id, overrides)?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+pub async fn skip_node(
+    Extension(state): Extension<State>,
+    Path((run_id, node_id)): Path<(Uuid, NodeId)>,
+) -> Result<Json<JsonValue>, ApiError> {
+    state.flows.skip_node(run_id, node_id)?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+pub async fn pending_approvals(Extension(state): Extension<State>) -> Json<JsonValue> {
+    Json(json!(state.flows.pending_approvals()))
+}
+
+pub async fn approve_node(
+    Extension(state): Extension<State>,
+    Path((run_id, node_id)): Path<(Uuid, NodeId)>,
+) -> Result<Json<JsonValue>, ApiError> {
+    state.flows.approve(run_id, node_id)?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+pub async fn reject_node(
+    Extension(state): Extension<State>,
+    Path((run_id, node_id)): Path<(Uuid, NodeId)>,
+) -> Result<Json<JsonValue>, ApiError> {
+    state.flows.reject(run_id, node_id)?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+pub async fn enable_triggers(
+    Extension(state): Extension<State>,
+    Path(flow_id): Path<GraphId>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let triggers = state.flows.enable_triggers(flow_id).await?;
+
+    Ok(Json(json!({ "triggers": triggers })))
+}
+
+pub async fn disable_triggers(
+    Extension(state): Extension<State>,
+    Path(flow_id): Path<GraphId>,
+) -> Result<Json<JsonValue>, ApiError> {
+    state.flows.disable_triggers(flow_id)?;
+
+    Ok(Json(JsonValue::Null))
+}
+
+// webhook paths of triggers are matched without the leading slash,
+// e.g. POST /webhooks/orders/new runs the flow bound to "orders/new"
+pub async fn webhook(
+    Extension(state): Extension<State>,
+    Path(path): Path<String>,
+    Json(payload): Json<JsonValue>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let path = path.trim_start_matches('/');
+
+    let run_id = state.flows.handle_webhook(path, payload).await?;
+
+    Ok(Json(json!({ "run_id": run_id })))
+}
+
+pub async fn metrics(Extension(state): Extension<State>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
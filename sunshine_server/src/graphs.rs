@@ -0,0 +1,211 @@
+use axum::extract::{Extension, Path, Query};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use sunshine_core::msg::{
+    Action, CreateEdge, Edge, EdgeId, GraphId, MutateKind, NodeId, Properties, QueryKind, Reply,
+};
+use sunshine_solana::SNAPSHOT_MARKER;
+
+use crate::error::ApiError;
+use crate::State;
+
+// every route goes through Datastore::execute so that it can be undone
+async fn execute(state: &State, action: Action) -> Result<Json<JsonValue>, ApiError> {
+    // published versions of a flow are never edited, not even by undo or redo
+    if let Some(graph_id) = edited_graph(state, &action) {
+        let root = state.db.read_node(graph_id).await?;
+
+        if root.properties.contains_key(SNAPSHOT_MARKER) {
+            return Err(sunshine_solana::Error::SnapshotIsImmutable(graph_id).into());
+        }
+    }
+
+    let reply = state.db.execute(action).await?;
+
+    Ok(Json(reply_to_json(reply)?))
+}
+
+// the graph the action edits, undo and redo edit the graph of the entry they replay
+fn edited_graph(state: &State, action: &Action) -> Option<GraphId> {
+    let replayed = match action {
+        Action::Undo => state.db.undo_buf().lock().unwrap().last().cloned(),
+        Action::Redo => state.db.redo_buf().lock().unwrap().last().cloned(),
+        _ => None,
+    };
+
+    match replayed.as_ref().unwrap_or(action) {
+        Action::Mutate(graph_id, _) | Action::DeleteGraph(graph_id) => Some(*graph_id),
+        _ => None,
+    }
+}
+
+fn reply_to_json(reply: Reply) -> Result<JsonValue, ApiError> {
+    let json = match reply {
+        Reply::Id(id) => json!({ "id": id }),
+        Reply::NodeList(nodes) => JsonValue::Array(
+            nodes
+                .into_iter()
+                .map(|(id, properties)| json!({ "id": id, "properties": properties }))
+                .collect(),
+        ),
+        Reply::Node(node) => serde_json::to_value(node).map_err(sunshine_core::Error::JsonError)?,
+        Reply::Edge(edge) => serde_json::to_value(edge).map_err(sunshine_core::Error::JsonError)?,
+        Reply::Graph(graph) => {
+            serde_json::to_value(graph).map_err(sunshine_core::Error::JsonError)?
+        }
+        Reply::Properties(properties) => JsonValue::Object(properties),
+        Reply::Empty => JsonValue::Null,
+    };
+
+    Ok(json)
+}
+
+pub async fn list_graphs(Extension(state): Extension<State>) -> Result<Json<JsonValue>, ApiError> {
+    execute(&state, Action::Query(QueryKind::ListGraphs)).await
+}
+
+pub async fn create_graph(
+    Extension(state): Extension<State>,
+    Json(properties): Json<Properties>,
+) -> Result<Json<JsonValue>, ApiError> {
+    execute(&state, Action::CreateGraph(properties)).await
+}
+
+pub async fn read_graph(
+    Extension(state): Extension<State>,
+    Path(graph_id): Path<GraphId>,
+) -> Result<Json<JsonValue>, ApiError> {
+    execute(&state, Action::Query(QueryKind::ReadGraph(graph_id))).await
+}
+
+pub async fn create_node(
+    Extension(state): Extension<State>,
+    Path(graph_id): Path<GraphId>,
+    Json(properties): Json<Properties>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let action = Action::Mutate(graph_id, MutateKind::CreateNode(properties));
+
+    execute(&state, action).await
+}
+
+pub async fn read_node(
+    Extension(state): Extension<State>,
+    Path(node_id): Path<NodeId>,
+) -> Result<Json<JsonValue>, ApiError> {
+    execute(&state, Action::Query(QueryKind::ReadNode(node_id))).await
+}
+
+pub async fn update_node(
+    Extension(state): Extension<State>,
+    Path((graph_id, node_id)): Path<(GraphId, NodeId)>,
+    Json(properties): Json<Properties>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let action = Action::Mutate(graph_id, MutateKind::UpdateNode((node_id, properties)));
+
+    execute(&state, action).await
+}
+
+pub async fn delete_node(
+    Extension(state): Extension<State>,
+    Path((graph_id, node_id)): Path<(GraphId, NodeId)>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let action = Action::Mutate(graph_id, MutateKind::DeleteNode(node_id));
+
+    execute(&state, action).await
+}
+
+#[derive(Deserialize)]
+pub struct NewEdge {
+    from: NodeId,
+    to: NodeId,
+    #[serde(default)]
+    properties: Properties,
+}
+
+pub async fn create_edge(
+    Extension(state): Extension<State>,
+    Path(graph_id): Path<GraphId>,
+    Json(mut edge): Json<NewEdge>,
+) -> Result<Json<JsonValue>, ApiError> {
+    // edges between the ports of two commands are rejected when the kinds don't convert
+    state
+        .flows
+        .type_edge(edge.from, edge.to, &mut edge.properties)
+        .await?;
+
+    let action = Action::Mutate(
+        graph_id,
+        MutateKind::CreateEdge(CreateEdge {
+            from: edge.from,
+            to: edge.to,
+            properties: edge.properties,
+        }),
+    );
+
+    execute(&state, action).await
+}
+
+// an edge is only identified together with the nodes it connects
+#[derive(Deserialize)]
+pub struct EdgeEnds {
+    from: NodeId,
+    to: NodeId,
+}
+
+pub async fn read_edge(
+    Extension(state): Extension<State>,
+    Path(edge_id): Path<EdgeId>,
+    Query(ends): Query<EdgeEnds>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let edge = Edge {
+        id: edge_id,
+        from: ends.from,
+        to: ends.to,
+    };
+
+    execute(&state, Action::Query(QueryKind::ReadEdgeProperties(edge))).await
+}
+
+pub async fn update_edge(
+    Extension(state): Extension<State>,
+    Path((graph_id, edge_id)): Path<(GraphId, EdgeId)>,
+    Query(ends): Query<EdgeEnds>,
+    Json(properties): Json<Properties>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let edge = Edge {
+        id: edge_id,
+        from: ends.from,
+        to: ends.to,
+    };
+
+    let action = Action::Mutate(graph_id, MutateKind::UpdateEdge((edge, properties)));
+
+    execute(&state, action).await
+}
+
+pub async fn delete_edge(
+    Extension(state): Extension<State>,
+    Path((graph_id, edge_id)): Path<(GraphId, EdgeId)>,
+    Query(ends): Query<EdgeEnds>,
+) -> Result<Json<JsonValue>, ApiError> {
+    let edge = Edge {
+        id: edge_id,
+        from: ends.from,
+        to: ends.to,
+    };
+
+    execute(
+        &state,
+        Action::Mutate(graph_id, MutateKind::DeleteEdge(edge)),
+    )
+    .await
+}
+
+pub async fn undo(Extension(state): Extension<State>) -> Result<Json<JsonValue>, ApiError> {
+    execute(&state, Action::Undo).await
+}
+
+pub async fn redo(Extension(state): Extension<State>) -> Result<Json<JsonValue>, ApiError> {
+    execute(&state, Action::Redo).await
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use axum::routing::{get, post, put};
+use axum::{Extension, Router};
+use sunshine_core::store::Datastore;
+use sunshine_solana::{FlowContext, Limits, PrometheusMetrics};
+
+mod error;
+mod events;
+mod flows;
+mod graphs;
+
+pub use error::ApiError;
+
+#[derive(Clone)]
+pub struct State {
+    pub db: Arc<dyn Datastore>,
+    pub flows: Arc<FlowContext>,
+    pub metrics: Arc<PrometheusMetrics>,
+}
+
+impl State {
+    // the flow context reports to `metrics`, which are served under /metrics
+    pub fn new(db: Arc<dyn Datastore>) -> State {
+        State::with_limits(db, &Limits::default())
+    }
+
+    pub fn with_limits(db: Arc<dyn Datastore>, limits: &Limits) -> State {
+        let metrics = Arc::new(PrometheusMetrics::new());
+
+        let flows = FlowContext::with_metrics(db.clone(), metrics.clone()).with_limits(limits);
+
+        State {
+            db,
+            flows: Arc::new(flows),
+            metrics,
+        }
+    }
+}
+
+pub fn router(state: State) -> Router {
+    Router::new()
+        .route(
+            "/graphs",
+            get(graphs::list_graphs).post(graphs::create_graph),
+        )
+        .route("/graphs/:graph_id", get(graphs::read_graph))
+        .route("/graphs/:graph_id/nodes", post(graphs::create_node))
+        .route(
+            "/graphs/:graph_id/nodes/:node_id",
+            put(graphs::update_node).delete(graphs::delete_node),
+        )
+        .route("/graphs/:graph_id/edges", post(graphs::create_edge))
+        .route(
+            "/graphs/:graph_id/edges/:edge_id",
+            put(graphs::update_edge).delete(graphs::delete_edge),
+        )
+        .route("/nodes/:node_id", get(graphs::read_node))
+        .route("/edges/:edge_id", get(graphs::read_edge))
+        .route("/undo", post(graphs::undo))
+        .route("/redo", post(graphs::redo))
+        .route("/deployments", get(flows::list_deployed))
+        .route(
+            "/flows/:flow_id/deploy",
+            post(flows::deploy).delete(flows::undeploy),
+        )
+        .route("/flows/:flow_id/run", post(flows::run))
+        .route(
+            "/flows/:flow_id/versions",
+            get(flows::list_versions).post(flows::publish),
+        )
+        .route(
+            "/flows/:flow_id/triggers",
+            post(flows::enable_triggers).delete(flows::disable_triggers),
+        )
+        .route(
+            "/runs/:run_id",
+            get(flows::get_run).delete(flows::cancel_run),
+        )
+        .route("/runs/:run_id/resume", post(flows::resume_run))
+        .route("/runs/:run_id/paused", get(flows::paused_nodes))
+        .route(
+            "/runs/:run_id/nodes/:node_id/continue",
+            post(flows::continue_node),
+        )
+        .route("/runs/:run_id/nodes/:node_id/skip", post(flows::skip_node))
+        .route("/approvals", get(flows::pending_approvals))
+        .route(
+            "/runs/:run_id/nodes/:node_id/approve",
+            post(flows::approve_node),
+        )
+        .route(
+            "/runs/:run_id/nodes/:node_id/reject",
+            post(flows::reject_node),
+        )
+        .route("/webhooks/*path", post(flows::webhook))
+        .route("/events", get(events::stream))
+        .route("/metrics", get(flows::metrics))
+        .layer(Extension(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use serde_json::{json, Value as JsonValue};
+    use sunshine_core::msg::{Action, MutateKind, Properties};
+    use sunshine_indra::store::{DbConfig, DB};
+    use tower::ServiceExt;
+
+    fn test_router(dir: &tempfile::TempDir) -> Router {
+        let db = DB::new(&DbConfig {
+            db_path: dir.path().join("db").to_str().unwrap().to_owned(),
+        })
+        .unwrap();
+
+        router(State::new(Arc::new(db)))
+    }
+
+    async fn request(
+        router: &Router,
+        method: Method,
+        uri: &str,
+        body: JsonValue,
+    ) -> (StatusCode, JsonValue) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = serde_json::from_slice(&body).unwrap_or(JsonValue::Null);
+
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn creates_nodes_and_undoes_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = test_router(&dir);
+
+        let (status, graph) = request(&router, Method::POST, "/graphs", json!({})).await;
+        assert_eq!(status, StatusCode::OK);
+        let graph_id = graph["id"].as_str().unwrap().to_owned();
+
+        let nodes = format!("/graphs/{}/nodes", graph_id);
+        let (status, _) = request(&router, Method::POST, &nodes, json!({ "name": "a" })).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let graph_uri = format!("/graphs/{}", graph_id);
+        let (_, graph) = request(&router, Method::GET, &graph_uri, JsonValue::Null).await;
+        assert_eq!(graph["nodes"].as_array().unwrap().len(), 1);
+
+        let (status, _) = request(&router, Method::POST, "/undo", JsonValue::Null).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, graph) = request(&router, Method::GET, &graph_uri, JsonValue::Null).await;
+        assert!(graph["nodes"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn snapshots_cant_be_edited_by_undo() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::new(&DbConfig {
+            db_path: dir.path().join("db").to_str().unwrap().to_owned(),
+        })
+        .unwrap();
+        let state = State::new(Arc::new(db));
+        let router = router(state.clone());
+
+        let (_, graph) = request(&router, Method::POST, "/graphs", json!({})).await;
+        let graph_id = graph["id"].as_str().unwrap().to_owned();
+
+        let versions = format!("/flows/{}/versions", graph_id);
+        let (status, version) = request(&router, Method::POST, &versions, JsonValue::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let snapshot_id = serde_json::from_value(version["graph_id"].clone()).unwrap();
+
+        // an entry replaying an edit of the snapshot
+        state.db.undo_buf().lock().unwrap().push(Action::Mutate(
+            snapshot_id,
+            MutateKind::CreateNode(Properties::new()),
+        ));
+
+        let (status, _) = request(&router, Method::POST, "/undo", JsonValue::Null).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn incompatible_edges_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = test_router(&dir);
+
+        let (_, graph) = request(&router, Method::POST, "/graphs", json!({})).await;
+        let graph_id = graph["id"].as_str().unwrap().to_owned();
+
+        let nodes = format!("/graphs/{}/nodes", graph_id);
+        let command = |cmd: JsonValue| json!({ "COMMAND_MARKER": { "Solana": cmd } });
+
+        let (_, transfer) = request(
+            &router,
+            Method::POST,
+            &nodes,
+            command(json!({
+                "TransferSolana": { "sender": null, "recipient": null, "amount": null }
+            })),
+        )
+        .await;
+        let (_, balance) = request(
+            &router,
+            Method::POST,
+            &nodes,
+            command(json!({ "GetBalance": { "pubkey": null } })),
+        )
+        .await;
+
+        let edges = format!("/graphs/{}/edges", graph_id);
+        let edge = |output: &str| {
+            json!({
+                "from": transfer["id"],
+                "to": balance["id"],
+                "properties": {
+                    "OUTPUT_ARG_NAME_MARKER": output,
+                    "INPUT_ARG_NAME_MARKER": "pubkey",
+                },
+            })
+        };
+
+        let (status, body) = request(&router, Method::POST, &edges, edge("signature")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("recipient"));
+
+        let (status, _) = request(&router, Method::POST, &edges, edge("sender")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn unknown_runs_are_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = test_router(&dir);
+
+        let uri = format!("/runs/{}/paused", uuid::Uuid::new_v4());
+        let (status, body) = request(&router, Method::GET, &uri, JsonValue::Null).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn approvals_of_unknown_runs_are_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = test_router(&dir);
+
+        let (status, body) = request(&router, Method::GET, "/approvals", JsonValue::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!([]));
+
+        let uri = format!(
+            "/runs/{}/nodes/{}/approve",
+            uuid::Uuid::new_v4(),
+            uuid::Uuid::new_v4()
+        );
+        let (status, _) = request(&router, Method::POST, &uri, JsonValue::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
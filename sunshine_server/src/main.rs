@@ -0,0 +1,48 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use sunshine_indra::store::{DbConfig, DB};
+use sunshine_server::{router, State};
+use sunshine_solana::Limits;
+use tracing_subscriber::EnvFilter;
+
+// SUNSHINE_DB is the path of the sled store, SUNSHINE_ADDR the address to listen on and
+// SUNSHINE_LIMITS an optional json file with the Limits of the flow context
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    let db_path = std::env::var("SUNSHINE_DB").unwrap_or_else(|_| "sunshine_db".to_owned());
+
+    let addr: SocketAddr = std::env::var("SUNSHINE_ADDR")
+        .unwrap_or_else(|_| "127.0.0.1:8080".to_owned())
+        .parse()
+        .expect("SUNSHINE_ADDR isn't a socket address");
+
+    let db = DB::new(&DbConfig { db_path }).expect("failed to open the store");
+
+    let limits = match std::env::var("SUNSHINE_LIMITS") {
+        Ok(path) => {
+            let limits = std::fs::read(path).expect("failed to read SUNSHINE_LIMITS");
+
+            serde_json::from_slice(&limits).expect("SUNSHINE_LIMITS isn't valid limits")
+        }
+        Err(_) => Limits::default(),
+    };
+
+    let state = State::with_limits(Arc::new(db), &limits);
+
+    match state.flows.restore().await {
+        Ok(restored) => tracing::info!(count = restored.len(), "restored deployments"),
+        Err(e) => tracing::error!(error = %e, "failed to restore deployments"),
+    }
+
+    tracing::info!(%addr, "listening");
+
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .await
+        .expect("server failed");
+}
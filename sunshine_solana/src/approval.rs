@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sunshine_core::msg::NodeId;
+use tokio::sync::oneshot;
+
+use crate::{FlowId, RunId};
+
+// Approval node of a run waiting until someone approves or rejects it. only what the
+// approver needs to decide is exposed, not the inputs of the node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub run_id: RunId,
+    pub flow_id: FlowId,
+    pub node_id: NodeId,
+    pub name: String,
+    pub message: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    // it's rejected when nobody decides until then
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum Decision {
+    Approve,
+    Reject,
+}
+
+pub(crate) struct Request {
+    pub pending: PendingApproval,
+    pub decide: oneshot::Sender<Decision>,
+}
+
+pub(crate) type Approvals = Arc<DashMap<NodeId, Request>>;
+
+// waits until the approval is decided or times out, which rejects it
+pub(crate) async fn request(
+    approvals: &Approvals,
+    pending: PendingApproval,
+    timeout: Option<Duration>,
+) -> Decision {
+    let (tx, rx) = oneshot::channel();
+
+    let node_id = pending.node_id;
+
+    approvals.insert(
+        node_id,
+        Request {
+            pending,
+            decide: tx,
+        },
+    );
+
+    let decision = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+            Ok(decision) => decision,
+            Err(_) => {
+                tracing::info!(%node_id, "approval timed out");
+                approvals.remove(&node_id);
+                return Decision::Reject;
+            }
+        },
+        None => rx.await,
+    };
+
+    // a dropped sender means the request was discarded with the run
+    decision.unwrap_or(Decision::Reject)
+}
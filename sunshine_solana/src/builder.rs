@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sunshine_core::msg::{NodeId, Properties};
+use sunshine_core::store::Datastore;
+use uuid::Uuid;
+
+use crate::error::Error;
+use crate::flow_file::{self, FlowFile, FlowFileEdge, FlowFileNode};
+use crate::input::{MergeStrategy, INPUT_MERGE_MARKER};
+use crate::ports;
+use crate::{
+    commands, ContextConfig, FlowId, COMMAND_MARKER, COMMAND_NAME_MARKER, CTX_EDGE_MARKER,
+    CTX_MARKER, INPUT_ARG_NAME_MARKER, OUTPUT_ARG_NAME_MARKER, START_NODE_MARKER,
+};
+
+// a flow defined by node names instead of ids, built in rust or read from yaml/toml:
+//
+// FlowBuilder::new()
+//     .context("ctx", ContextConfig { .. })
+//     .start_node("mint", CreateMintAccount { .. })
+//     .node("meta", CreateMetadataAccounts { .. })
+//     .connect("mint.mint_account", "meta.mint_account")
+//     .create(&db)
+//
+// solana commands are connected to the context they name, or the only context of the flow
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlowBuilder {
+    // properties of the flow root, e.g. variables and triggers
+    #[serde(default)]
+    pub properties: Properties,
+    #[serde(default)]
+    pub contexts: BTreeMap<String, ContextConfig>,
+    #[serde(default)]
+    pub nodes: BTreeMap<String, NodeSpec>,
+    #[serde(default)]
+    pub edges: Vec<EdgeSpec>,
+}
+
+// plain values come before the command, toml can't have them after a table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeSpec {
+    #[serde(default)]
+    pub start: bool,
+    #[serde(default)]
+    pub context: Option<String>,
+    pub command: commands::Config,
+    #[serde(default)]
+    pub merge: HashMap<String, MergeStrategy>,
+    // any other node properties, e.g. a breakpoint or flow outputs
+    #[serde(default)]
+    pub properties: Properties,
+}
+
+// both ends are "node.port"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeSpec {
+    pub from: String,
+    pub to: String,
+}
+
+impl FlowBuilder {
+    pub fn new() -> FlowBuilder {
+        FlowBuilder::default()
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<FlowBuilder, Error> {
+        serde_yaml::from_str(yaml).map_err(|e| Error::InvalidFlowDefinition(e.to_string()))
+    }
+
+    pub fn from_toml(toml: &str) -> Result<FlowBuilder, Error> {
+        toml::from_str(toml).map_err(|e| Error::InvalidFlowDefinition(e.to_string()))
+    }
+
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        serde_yaml::to_string(self).map_err(|e| Error::InvalidFlowDefinition(e.to_string()))
+    }
+
+    pub fn to_toml(&self) -> Result<String, Error> {
+        toml::to_string(self).map_err(|e| Error::InvalidFlowDefinition(e.to_string()))
+    }
+
+    pub fn property(mut self, name: impl Into<String>, value: impl Into<JsonValue>) -> Self {
+        self.properties.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn context(mut self, name: impl Into<String>, cfg: ContextConfig) -> Self {
+        self.contexts.insert(name.into(), cfg);
+        self
+    }
+
+    pub fn node(self, name: impl Into<String>, command: impl Into<commands::Config>) -> Self {
+        self.add_node(name.into(), command.into(), false)
+    }
+
+    // a node which runs as soon as the flow starts
+    pub fn start_node(self, name: impl Into<String>, command: impl Into<commands::Config>) -> Self {
+        self.add_node(name.into(), command.into(), true)
+    }
+
+    fn add_node(mut self, name: String, command: commands::Config, start: bool) -> Self {
+        self.nodes.insert(
+            name,
+            NodeSpec {
+                start,
+                context: None,
+                command,
+                merge: HashMap::new(),
+                properties: Properties::new(),
+            },
+        );
+        self
+    }
+
+    // panics if the node wasn't added before
+    fn node_mut(&mut self, name: &str) -> &mut NodeSpec {
+        self.nodes
+            .get_mut(name)
+            .unwrap_or_else(|| panic!("node {} isn't part of the flow", name))
+    }
+
+    pub fn with_context(mut self, node: &str, context: impl Into<String>) -> Self {
+        self.node_mut(node).context = Some(context.into());
+        self
+    }
+
+    pub fn node_property(
+        mut self,
+        node: &str,
+        name: impl Into<String>,
+        value: impl Into<JsonValue>,
+    ) -> Self {
+        self.node_mut(node)
+            .properties
+            .insert(name.into(), value.into());
+        self
+    }
+
+    // `input` is "node.input"
+    pub fn merge(mut self, input: &str, strategy: MergeStrategy) -> Self {
+        let (node, input) = input
+            .split_once('.')
+            .unwrap_or_else(|| panic!("{} isn't node.input", input));
+
+        self.node_mut(node).merge.insert(input.to_owned(), strategy);
+        self
+    }
+
+    // `from` is "node.output", `to` is "node.input"
+    pub fn connect(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.edges.push(EdgeSpec {
+            from: from.into(),
+            to: to.into(),
+        });
+        self
+    }
+
+    // assigns the node ids and turns the names into the markers read_flow expects
+    pub fn build(&self) -> Result<FlowFile, Error> {
+        let invalid = Error::InvalidFlowDefinition;
+
+        let mut ids = HashMap::new();
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+
+        for (name, cfg) in self.contexts.iter() {
+            let id = Uuid::new_v4();
+
+            let mut properties = Properties::new();
+            properties.insert(CTX_MARKER.to_owned(), serde_json::to_value(cfg)?);
+
+            ids.insert(name.as_str(), id);
+            nodes.push(FlowFileNode { id, properties });
+        }
+
+        for (name, node) in self.nodes.iter() {
+            if self.contexts.contains_key(name) {
+                return Err(invalid(format!("{} is both a context and a node", name)));
+            }
+
+            let id = Uuid::new_v4();
+
+            let mut properties = node.properties.clone();
+
+            properties.insert(
+                COMMAND_MARKER.to_owned(),
+                serde_json::to_value(&node.command)?,
+            );
+            properties.insert(
+                COMMAND_NAME_MARKER.to_owned(),
+                JsonValue::from(name.as_str()),
+            );
+
+            if node.start {
+                properties.insert(START_NODE_MARKER.to_owned(), JsonValue::Bool(true));
+            }
+
+            if !node.merge.is_empty() {
+                properties.insert(
+                    INPUT_MERGE_MARKER.to_owned(),
+                    serde_json::to_value(&node.merge)?,
+                );
+            }
+
+            ids.insert(name.as_str(), id);
+            nodes.push(FlowFileNode { id, properties });
+        }
+
+        for (name, node) in self.nodes.iter() {
+            if !matches!(node.command, commands::Config::Solana(_)) {
+                continue;
+            }
+
+            let context = match (&node.context, self.contexts.len()) {
+                (Some(context), _) => context.as_str(),
+                (None, 1) => self.contexts.keys().next().unwrap().as_str(),
+                (None, _) => {
+                    return Err(invalid(format!(
+                        "solana command {} has to name one of the contexts",
+                        name
+                    )))
+                }
+            };
+
+            if !self.contexts.contains_key(context) {
+                return Err(invalid(format!(
+                    "{} uses unknown context {}",
+                    name, context
+                )));
+            }
+
+            let mut properties = Properties::new();
+            properties.insert(CTX_EDGE_MARKER.to_owned(), JsonValue::Bool(true));
+
+            edges.push(FlowFileEdge {
+                from: ids[context],
+                to: ids[name.as_str()],
+                properties,
+            });
+        }
+
+        let port = |end: &str| -> Result<(&NodeSpec, NodeId, String), Error> {
+            let (node, port) = end
+                .split_once('.')
+                .ok_or_else(|| invalid(format!("{} isn't node.port", end)))?;
+
+            match self.nodes.get(node) {
+                Some(spec) => Ok((spec, ids[node], port.to_owned())),
+                None => Err(invalid(format!("{} refers to unknown node {}", end, node))),
+            }
+        };
+
+        for edge in self.edges.iter() {
+            let (from_spec, from, output) = port(&edge.from)?;
+            let (to_spec, to, input) = port(&edge.to)?;
+
+            let mut properties = Properties::new();
+
+            let check = ports::check_edge(&from_spec.command, &output, &to_spec.command, &input);
+            ports::type_edge(&check, &output, &input, &mut properties)?;
+
+            properties.insert(OUTPUT_ARG_NAME_MARKER.to_owned(), JsonValue::String(output));
+            properties.insert(INPUT_ARG_NAME_MARKER.to_owned(), JsonValue::String(input));
+
+            edges.push(FlowFileEdge {
+                from,
+                to,
+                properties,
+            });
+        }
+
+        Ok(FlowFile {
+            properties: self.properties.clone(),
+            nodes,
+            edges,
+        })
+    }
+
+    // builds the flow as a new graph in the datastore
+    pub async fn create(&self, db: &Arc<dyn Datastore>) -> Result<FlowId, Error> {
+        flow_file::import_flow(db, &self.build()?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::simple::Command as SimpleCommand;
+    use crate::commands::solana::get_balance::GetBalance;
+    use crate::commands::solana::SolanaNet;
+    use crate::Value;
+
+    fn flow() -> FlowBuilder {
+        FlowBuilder::new()
+            .context(
+                "ctx",
+                ContextConfig {
+                    solana_net: SolanaNet::Devnet,
+                    wallet_graph: Uuid::nil(),
+                    solana_net_variable: None,
+                },
+            )
+            .start_node("amount", SimpleCommand::Const(Value::U64(3)))
+            .node("balance", GetBalance { pubkey: None })
+            .node("print", SimpleCommand::Print)
+            .merge("print.print", MergeStrategy::FirstArrival)
+            .connect("amount.output", "print.print")
+            .connect("balance.balance", "print.print")
+    }
+
+    #[test]
+    fn names_become_markers() {
+        let file = flow().build().unwrap();
+
+        let named = |name: &str| {
+            file.nodes
+                .iter()
+                .find(|node| node.properties.get(COMMAND_NAME_MARKER) == Some(&name.into()))
+                .unwrap()
+        };
+
+        assert_eq!(file.nodes.len(), 4);
+        assert!(named("amount").properties.contains_key(START_NODE_MARKER));
+        assert!(!named("print").properties.contains_key(START_NODE_MARKER));
+        assert!(named("print").properties.contains_key(INPUT_MERGE_MARKER));
+
+        let ctx_edges: Vec<_> = file
+            .edges
+            .iter()
+            .filter(|edge| edge.properties.contains_key(CTX_EDGE_MARKER))
+            .collect();
+
+        assert_eq!(ctx_edges.len(), 1);
+        assert_eq!(ctx_edges[0].to, named("balance").id);
+
+        let edge = file
+            .edges
+            .iter()
+            .find(|edge| edge.from == named("amount").id)
+            .unwrap();
+
+        assert_eq!(edge.to, named("print").id);
+        assert_eq!(edge.properties[OUTPUT_ARG_NAME_MARKER], "output");
+        assert_eq!(edge.properties[INPUT_ARG_NAME_MARKER], "print");
+    }
+
+    #[test]
+    fn yaml_round_trip() {
+        let yaml = flow().to_yaml().unwrap();
+
+        let file = FlowBuilder::from_yaml(&yaml).unwrap().build().unwrap();
+
+        assert_eq!(file.nodes.len(), 4);
+        assert_eq!(file.edges.len(), 3);
+    }
+
+    #[test]
+    fn unknown_node_is_rejected() {
+        let result = flow().connect("amount.output", "missing.input").build();
+
+        assert!(matches!(result, Err(Error::InvalidFlowDefinition(_))));
+    }
+}
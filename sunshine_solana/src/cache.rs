@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sunshine_core::msg::{GraphId, NodeId, Properties};
+use sunshine_core::store::Datastore;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use crate::error::Error;
+use crate::Value;
+
+// opt-in per node, e.g. { "ttl_secs": 3600 }. a run reuses the outputs of an earlier run of
+// the node when the command and its inputs are the same, instead of running the command
+pub const CACHE_MARKER: &str = "CACHE_MARKER";
+// the graph holding the cached outputs of every flow
+pub const CACHE_GRAPH_MARKER: &str = "CACHE_GRAPH_MARKER";
+pub const CACHE_ENTRY_MARKER: &str = "CACHE_ENTRY_MARKER";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct CachePolicy {
+    // outputs are reused until they're replaced without one
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+pub(crate) fn read_policy(props: &Properties) -> Result<Option<CachePolicy>, Error> {
+    match props.get(CACHE_MARKER) {
+        Some(policy) => Ok(Some(serde_json::from_value(policy.clone())?)),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    outputs: HashMap<String, Value>,
+    cached_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+// a cached node of a flow
+pub(crate) struct NodeCache {
+    pub policy: CachePolicy,
+    // the config of the command, part of the key
+    pub command: JsonValue,
+}
+
+impl NodeCache {
+    // the same command on another cluster doesn't share outputs, so the rpc url is part of
+    // the key as well
+    pub fn key(
+        &self,
+        rpc_url: Option<&str>,
+        inputs: &HashMap<String, Value>,
+    ) -> Result<String, Error> {
+        // maps inside the values are sorted once they're json
+        let inputs = inputs
+            .iter()
+            .map(|(name, value)| Ok((name, serde_json::to_value(value)?)))
+            .collect::<Result<BTreeMap<_, _>, Error>>()?;
+
+        let keyed = serde_json::to_vec(&(&self.command, rpc_url, inputs))?;
+
+        let digest = ring::digest::digest(&ring::digest::SHA256, &keyed);
+
+        Ok(digest
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+}
+
+// entries are nodes whose id is derived from the key, so they're found without an index
+#[derive(Clone, Default)]
+pub(crate) struct Cache {
+    graph: Arc<OnceCell<GraphId>>,
+}
+
+fn entry_id(key: &str) -> NodeId {
+    let mut bytes = [0; 16];
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&key[i * 2..i * 2 + 2], 16).unwrap_or_default();
+    }
+
+    Uuid::from_bytes(bytes)
+}
+
+impl Cache {
+    async fn graph(&self, db: &Arc<dyn Datastore>) -> Result<GraphId, Error> {
+        self.graph
+            .get_or_try_init(|| async {
+                let graph = db
+                    .list_graphs()
+                    .await?
+                    .into_iter()
+                    .find(|(_, props)| props.contains_key(CACHE_GRAPH_MARKER));
+
+                if let Some((graph_id, _)) = graph {
+                    return Ok(graph_id);
+                }
+
+                let mut props = Properties::new();
+                props.insert(CACHE_GRAPH_MARKER.to_owned(), JsonValue::Bool(true));
+
+                let (_, graph_id) = db.create_graph(props).await?;
+
+                Ok(graph_id)
+            })
+            .await
+            .map(|graph_id| *graph_id)
+    }
+
+    // None when nothing is cached for the key or the outputs expired
+    pub async fn get(
+        &self,
+        db: &Arc<dyn Datastore>,
+        key: &str,
+    ) -> Result<Option<HashMap<String, Value>>, Error> {
+        let props = match db.read_node(entry_id(key)).await {
+            Ok(node) => node.properties,
+            Err(sunshine_core::Error::NodeNotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let entry: CacheEntry = match props.get(CACHE_ENTRY_MARKER) {
+            Some(entry) => serde_json::from_value(entry.clone())?,
+            None => return Ok(None),
+        };
+
+        match entry.expires_at {
+            Some(expires_at) if expires_at <= Utc::now() => Ok(None),
+            _ => Ok(Some(entry.outputs)),
+        }
+    }
+
+    pub async fn put(
+        &self,
+        db: &Arc<dyn Datastore>,
+        key: &str,
+        policy: CachePolicy,
+        outputs: &HashMap<String, Value>,
+    ) -> Result<(), Error> {
+        let graph_id = self.graph(db).await?;
+
+        let cached_at = Utc::now();
+
+        let entry = CacheEntry {
+            outputs: outputs.clone(),
+            cached_at,
+            expires_at: policy
+                .ttl_secs
+                .map(|ttl| cached_at + Duration::seconds(ttl as i64)),
+        };
+
+        let mut props = Properties::new();
+        props.insert(CACHE_ENTRY_MARKER.to_owned(), serde_json::to_value(entry)?);
+
+        let node_id = entry_id(key);
+
+        match db.read_node(node_id).await {
+            Ok(_) => {
+                db.update_node((node_id, props), graph_id).await?;
+            }
+            Err(sunshine_core::Error::NodeNotFound) => {
+                db.create_node_with_id(node_id, (graph_id, props)).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_depends_on_command_and_inputs() {
+        let cache = NodeCache {
+            policy: CachePolicy::default(),
+            command: serde_json::json!({ "Simple": "Print" }),
+        };
+
+        let inputs = |value: &str| {
+            let mut inputs = HashMap::new();
+            inputs.insert("print".to_owned(), Value::String(value.to_owned()));
+            inputs.insert("other".to_owned(), Value::U64(1));
+            inputs
+        };
+
+        let key = cache.key(None, &inputs("a")).unwrap();
+
+        assert_eq!(key, cache.key(None, &inputs("a")).unwrap());
+        assert_ne!(key, cache.key(None, &inputs("b")).unwrap());
+        assert_ne!(
+            key,
+            cache
+                .key(Some("https://api.devnet.solana.com"), &inputs("a"))
+                .unwrap()
+        );
+        assert_eq!(entry_id(&key), entry_id(&key));
+    }
+}
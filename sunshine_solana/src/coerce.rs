@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use uuid::Uuid;
+
+use crate::commands::simple::branch::Operator;
+use crate::commands::solana::nft::create_metadata_accounts::NftUses;
+use crate::commands::solana::nft::update_metadata_accounts::MetadataAccountData;
+use crate::error::Error;
+use crate::{NftCreator, NftMetadata, Value, ValueKind};
+
+// numbers are converted through this, so a conversion only succeeds when it's lossless
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+// integers above this can't be represented exactly as f64
+const MAX_SAFE_F64_INT: i128 = 1 << 53;
+const MAX_SAFE_F32_INT: i128 = 1 << 24;
+
+impl Value {
+    // converts the value to `kind` when it can be done without losing information,
+    // e.g. U8 -> U64, integral F64 -> U64, "12.5" -> F64, base58 String -> Pubkey
+    pub fn coerce_to(self, kind: ValueKind) -> Result<Value, Error> {
+        if self.kind() == kind {
+            return Ok(self);
+        }
+
+        let from = self.kind();
+        let cant_coerce = || Error::CantCoerce { from, to: kind };
+
+        let res = match kind {
+            ValueKind::Integer
+            | ValueKind::U8
+            | ValueKind::U16
+            | ValueKind::U64
+            | ValueKind::Balance => {
+                let int = match self.number().ok_or_else(cant_coerce)? {
+                    Number::Int(i) => i,
+                    Number::Float(f) if f.fract() == 0.0 && f.abs() < 1e38 => f as i128,
+                    Number::Float(_) => return Err(cant_coerce()),
+                };
+
+                match kind {
+                    ValueKind::Integer => Value::I64(int.try_into().map_err(|_| cant_coerce())?),
+                    ValueKind::U8 => Value::U8(int.try_into().map_err(|_| cant_coerce())?),
+                    ValueKind::U16 => Value::U16(int.try_into().map_err(|_| cant_coerce())?),
+                    ValueKind::U64 => Value::U64(int.try_into().map_err(|_| cant_coerce())?),
+                    _ => Value::Balance(int.try_into().map_err(|_| cant_coerce())?),
+                }
+            }
+            // every f32 is an f64, including the ones which aren't finite
+            ValueKind::F64 if from == ValueKind::F32 => match self {
+                Value::F32(f) => Value::F64(f as f64),
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::F64 => match self.number().ok_or_else(cant_coerce)? {
+                Number::Int(i) if i.abs() <= MAX_SAFE_F64_INT => Value::F64(i as f64),
+                Number::Float(f) => Value::F64(f),
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::F32 => match self.number().ok_or_else(cant_coerce)? {
+                Number::Int(i) if i.abs() <= MAX_SAFE_F32_INT => Value::F32(i as f32),
+                Number::Float(f) if (f as f32) as f64 == f => Value::F32(f as f32),
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::Bool => match self {
+                Value::String(s) => Value::Bool(s.parse().map_err(|_| cant_coerce())?),
+                Value::Json(json) => match JsonValue::from(json) {
+                    JsonValue::Bool(b) => Value::Bool(b),
+                    _ => return Err(cant_coerce()),
+                },
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::String => match self {
+                Value::StringOpt(Some(s)) => Value::String(s),
+                Value::Pubkey(p) => Value::String(p.to_string()),
+                Value::NodeId(id) => Value::String(id.to_string()),
+                Value::Success(s) => Value::String(s.to_string()),
+                Value::Json(json) => match JsonValue::from(json) {
+                    JsonValue::String(s) => Value::String(s),
+                    _ => return Err(cant_coerce()),
+                },
+                v if v.number().is_some() => Value::String(v.to_string()),
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::StringOpt => match self {
+                Value::Empty => Value::StringOpt(None),
+                v => match v.coerce_to(ValueKind::String) {
+                    Ok(Value::String(s)) => Value::StringOpt(Some(s)),
+                    _ => return Err(cant_coerce()),
+                },
+            },
+            ValueKind::Pubkey => match self {
+                Value::Keypair(keypair) => Value::Pubkey(Keypair::from(keypair).pubkey().into()),
+                Value::String(s) => {
+                    Value::Pubkey(Pubkey::from_str(&s).map_err(|_| cant_coerce())?.into())
+                }
+                Value::Json(json) => match JsonValue::from(json) {
+                    JsonValue::String(s) => Value::String(s).coerce_to(kind)?,
+                    _ => return Err(cant_coerce()),
+                },
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::NodeId => match self {
+                Value::String(s) => Value::NodeId(Uuid::from_str(&s).map_err(|_| cant_coerce())?),
+                Value::NodeIdOpt(Some(id)) => Value::NodeId(id),
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::NodeIdOpt => match self {
+                Value::Empty => Value::NodeIdOpt(None),
+                v => match v.coerce_to(ValueKind::NodeId) {
+                    Ok(Value::NodeId(id)) => Value::NodeIdOpt(Some(id)),
+                    _ => return Err(cant_coerce()),
+                },
+            },
+            ValueKind::Json => Value::Json(JsonValue::try_from(self)?.into()),
+            ValueKind::Array => match self {
+                Value::Json(json) => match JsonValue::from(json) {
+                    json @ JsonValue::Array(_) => Value::try_from(json)?,
+                    _ => return Err(cant_coerce()),
+                },
+                Value::NftCreators(creators) => Value::Array(
+                    creators
+                        .into_iter()
+                        .map(|c| Value::try_from(serde_json::to_value(c)?))
+                        .collect::<Result<_, Error>>()?,
+                ),
+                _ => return Err(cant_coerce()),
+            },
+            ValueKind::Map => match self {
+                Value::Json(json) => match JsonValue::from(json) {
+                    json @ JsonValue::Object(_) => Value::try_from(json)?,
+                    _ => return Err(cant_coerce()),
+                },
+                _ => return Err(cant_coerce()),
+            },
+            // structured values are deserialized from their json representation
+            ValueKind::NftCreators
+            | ValueKind::MetadataAccountData
+            | ValueKind::NftUses
+            | ValueKind::NftMetadata => {
+                let json = JsonValue::try_from(self).map_err(|_| cant_coerce())?;
+
+                match kind {
+                    ValueKind::NftCreators => Value::NftCreators(serde_json::from_value(json)?),
+                    ValueKind::MetadataAccountData => {
+                        Value::MetadataAccountData(serde_json::from_value(json)?)
+                    }
+                    ValueKind::NftUses => Value::Uses(serde_json::from_value(json)?),
+                    _ => Value::NftMetadata(serde_json::from_value(json)?),
+                }
+            }
+            _ => return Err(cant_coerce()),
+        };
+
+        Ok(res)
+    }
+
+    // coerces an argument of a command, naming the port in the error
+    pub(crate) fn coerce_arg<T>(self, port: &str) -> Result<T, Error>
+    where
+        T: TryFrom<Value, Error = Error>,
+    {
+        T::try_from(self).map_err(|e| Error::InvalidArgument {
+            port: port.to_owned(),
+            source: Box::new(e),
+        })
+    }
+
+    fn number(&self) -> Option<Number> {
+        let n = match self {
+            Value::I64(v) => Number::Int(*v as i128),
+            Value::Balance(v) | Value::U64(v) => Number::Int(*v as i128),
+            Value::U8(v) => Number::Int(*v as i128),
+            Value::U16(v) => Number::Int(*v as i128),
+            Value::F32(v) => Number::Float(*v as f64),
+            Value::F64(v) => Number::Float(*v),
+            Value::String(s) => match s.trim().parse::<i128>() {
+                Ok(i) => Number::Int(i),
+                Err(_) => Number::Float(s.trim().parse().ok()?),
+            },
+            Value::Json(json) => match JsonValue::from(json.clone()) {
+                JsonValue::Number(n) => match (n.as_u64(), n.as_i64()) {
+                    (Some(v), _) => Number::Int(v as i128),
+                    (_, Some(v)) => Number::Int(v as i128),
+                    _ => Number::Float(n.as_f64()?),
+                },
+                JsonValue::String(s) => return Value::String(s).number(),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        match n {
+            Number::Float(f) if !f.is_finite() => None,
+            n => Some(n),
+        }
+    }
+}
+
+// how coerce_to treats every value of one kind, used to type the edges of a flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Conversion {
+    Same,
+    // always succeeds
+    Lossless,
+    // succeeds for some values, e.g. a string which is a pubkey
+    Checked,
+    // doesn't convert
+    Incompatible,
+}
+
+impl ValueKind {
+    pub fn conversion_to(self, to: ValueKind) -> Conversion {
+        use ValueKind as K;
+
+        if self == to {
+            return Conversion::Same;
+        }
+
+        let lossless = match (self, to) {
+            (K::U8, K::U16 | K::U64 | K::Balance | K::Integer | K::F32 | K::F64) => true,
+            (K::U16, K::U64 | K::Balance | K::Integer | K::F32 | K::F64) => true,
+            (K::U64, K::Balance) | (K::Balance, K::U64) => true,
+            (K::F32, K::F64) => true,
+            (K::U8 | K::U16 | K::U64 | K::Balance | K::Integer, K::String | K::StringOpt) => true,
+            (K::Pubkey | K::NodeId | K::Success, K::String | K::StringOpt) => true,
+            (K::String | K::Empty, K::StringOpt) => true,
+            (K::NodeId | K::Empty, K::NodeIdOpt) => true,
+            (K::Keypair, K::Pubkey) => true,
+            (K::NftCreators, K::Array) => true,
+            // floats which aren't finite have no json number and aren't written as strings
+            (K::F32 | K::F64, K::String | K::StringOpt) => false,
+            (K::F32 | K::F64 | K::Array | K::Map | K::Cancel, K::Json) => false,
+            (_, K::Json) => true,
+            _ => false,
+        };
+
+        if lossless {
+            return Conversion::Lossless;
+        }
+
+        let checked = match (self, to) {
+            (K::F32 | K::F64 | K::Array | K::Map, K::Json) => true,
+            (K::F32 | K::F64, K::String | K::StringOpt) => true,
+            (
+                K::U8 | K::U16 | K::U64 | K::Balance | K::Integer | K::F32 | K::F64,
+                K::U8 | K::U16 | K::U64 | K::Balance | K::Integer | K::F32 | K::F64,
+            ) => true,
+            // strings and json are parsed
+            (K::String | K::Json, K::U8 | K::U16 | K::U64 | K::Balance | K::Integer)
+            | (K::String | K::Json, K::F32 | K::F64 | K::Bool | K::Pubkey | K::StringOpt) => true,
+            (K::Json, K::String | K::Array | K::Map) => true,
+            (K::StringOpt, K::String) => true,
+            (K::String | K::NodeIdOpt, K::NodeId) | (K::String, K::NodeIdOpt) => true,
+            (K::Json | K::Array, K::NftCreators) => true,
+            (K::Json | K::Map, K::MetadataAccountData | K::NftUses | K::NftMetadata) => true,
+            _ => false,
+        };
+
+        match checked {
+            true => Conversion::Checked,
+            false => Conversion::Incompatible,
+        }
+    }
+}
+
+macro_rules! impl_try_from_value {
+    ($($ty:ty => $kind:ident($variant:ident)),* $(,)?) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<$ty, Error> {
+                    match value.coerce_to(ValueKind::$kind)? {
+                        Value::$variant(v) => Ok(v),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_value! {
+    i64 => Integer(I64),
+    u8 => U8(U8),
+    u16 => U16(U16),
+    u64 => U64(U64),
+    f32 => F32(F32),
+    f64 => F64(F64),
+    bool => Bool(Bool),
+    String => String(String),
+    Vec<Value> => Array(Array),
+    HashMap<String, Value> => Map(Map),
+    Vec<NftCreator> => NftCreators(NftCreators),
+    MetadataAccountData => MetadataAccountData(MetadataAccountData),
+    NftUses => NftUses(Uses),
+    NftMetadata => NftMetadata(NftMetadata),
+    Operator => Operator(Operator),
+    Uuid => NodeId(NodeId),
+}
+
+impl TryFrom<Value> for Pubkey {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Pubkey, Error> {
+        match value.coerce_to(ValueKind::Pubkey)? {
+            Value::Pubkey(pubkey) => Ok(pubkey.into()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn numeric_coercion_is_lossless() {
+        let amount: f64 = Value::U64(5).coerce_arg("amount").unwrap();
+        assert_eq!(amount, 5.0);
+
+        let price: u64 = Value::F64(12.0).coerce_arg("sale_price").unwrap();
+        assert_eq!(price, 12);
+
+        let decimals: u8 = Value::String("9".to_owned())
+            .coerce_arg("decimals")
+            .unwrap();
+        assert_eq!(decimals, 9);
+
+        assert!(Value::F64(12.5).coerce_to(ValueKind::U64).is_err());
+        assert!(Value::U64(256).coerce_to(ValueKind::U8).is_err());
+        assert!(Value::I64(-1).coerce_to(ValueKind::U64).is_err());
+        assert!(Value::U64(u64::MAX).coerce_to(ValueKind::F64).is_err());
+        assert!(Value::F64(f64::NAN).coerce_to(ValueKind::String).is_err());
+        assert!(Value::F32(f32::INFINITY)
+            .coerce_to(ValueKind::StringOpt)
+            .is_err());
+        assert_eq!(
+            ValueKind::F64.conversion_to(ValueKind::String),
+            Conversion::Checked
+        );
+
+        match Value::U16(300).coerce_arg::<u8>("decimals") {
+            Err(Error::InvalidArgument { port, .. }) => assert_eq!(port, "decimals"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn conversions_match_coerce_to() {
+        use ValueKind as K;
+
+        let values = vec![
+            Value::U8(7),
+            Value::U16(300),
+            Value::U64(5),
+            Value::Balance(5),
+            Value::I64(-3),
+            Value::F32(1.5),
+            Value::F64(2.5),
+            Value::F32(f32::INFINITY),
+            Value::F64(f64::NAN),
+            Value::F64(f64::NEG_INFINITY),
+            Value::Bool(true),
+            Value::String("abc".to_owned()),
+            Value::StringOpt(None),
+            Value::Empty,
+            Value::NodeId(Uuid::new_v4()),
+            Value::Pubkey(Pubkey::new_unique().into()),
+            Value::Keypair(Keypair::new().into()),
+            Value::Success(Default::default()),
+        ];
+
+        let kinds = [
+            K::Integer,
+            K::Keypair,
+            K::String,
+            K::NodeId,
+            K::DeletedNode,
+            K::Pubkey,
+            K::Success,
+            K::Balance,
+            K::U8,
+            K::U16,
+            K::U64,
+            K::F32,
+            K::F64,
+            K::Bool,
+            K::StringOpt,
+            K::Empty,
+            K::NodeIdOpt,
+            K::NftCreators,
+            K::MetadataAccountData,
+            K::NftUses,
+            K::NftMetadata,
+            K::Operator,
+            K::Json,
+            K::Array,
+            K::Map,
+            K::Cancel,
+        ];
+
+        for value in values.iter() {
+            for kind in kinds {
+                let res = value.clone().coerce_to(kind);
+
+                match value.kind().conversion_to(kind) {
+                    Conversion::Same | Conversion::Lossless => {
+                        assert!(res.is_ok(), "{:?} -> {}", value, kind)
+                    }
+                    Conversion::Incompatible => assert!(res.is_err(), "{:?} -> {}", value, kind),
+                    Conversion::Checked => (),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn json_converts_to_arrays_and_maps() {
+        let value = Value::try_from(json!({ "uses": [1, -2, 2.5], "name": "nft" })).unwrap();
+
+        let mut map: HashMap<String, Value> = value.coerce_arg("metadata").unwrap();
+        let uses: Vec<Value> = map.remove("uses").unwrap().coerce_arg("uses").unwrap();
+
+        assert!(matches!(
+            uses.as_slice(),
+            [Value::U64(1), Value::I64(-2), Value::F64(_)]
+        ));
+        assert!(matches!(map.remove("name"), Some(Value::String(s)) if s == "nft"));
+    }
+}
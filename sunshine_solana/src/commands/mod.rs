@@ -1,8 +1,22 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
+use tokio::sync::OwnedSemaphorePermit;
+use url::Url;
+
+use sunshine_core::msg::NodeId;
+
+use crate::approval::Approvals;
+use crate::record::NodeLog;
+use crate::{Engine, Error, FlowContext, FlowId, RunId, RunOptions, RunState, Value};
+
+use solana::instructions::Simulation;
 
 pub mod simple;
 pub mod solana;
-//mod util;
+mod util;
 
 pub enum Command {
     Simple(simple::Command),
@@ -18,6 +32,74 @@ impl Command {
     }
 }
 
+// state of the run provided by the engine to every command
+#[derive(Clone)]
+pub struct Env {
+    pub run_id: RunId,
+    pub flow_id: FlowId,
+    // flow variables after the overrides of the deployment were applied
+    pub variables: HashMap<String, Value>,
+    // transactions are simulated instead of sent
+    pub dry_run: bool,
+    // the run pauses at breakpoints
+    pub debug: bool,
+    // simulations of the node the env was handed to, for its log node
+    pub(crate) simulations: Arc<Mutex<Vec<Simulation>>>,
+    // the rpc of the solana context of the node, its rpc calls are limited by it
+    pub(crate) rpc_url: Option<String>,
+    pub(crate) engine: Engine,
+    // log of the node the env was handed to, None for the env of the flow
+    pub(crate) log: Option<Arc<tokio::sync::Mutex<NodeLog>>>,
+    // id and name of the node the env was handed to, None for the env of the flow
+    pub(crate) node: Option<(NodeId, String)>,
+    // Approval nodes of the run waiting for a decision
+    pub(crate) approvals: Approvals,
+}
+
+impl Env {
+    // runs another flow and waits for its flow outputs, in a dry run the other flow is
+    // dry run too.
+    // boxed because the run of the other flow contains this future again
+    pub(crate) fn call_flow(
+        &self,
+        flow_id: FlowId,
+        inputs: HashMap<String, Value>,
+    ) -> BoxFuture<'static, Result<HashMap<String, Value>, Error>> {
+        let engine = self.engine.clone();
+        // the other flow doesn't stop at breakpoints and runs its live graph
+        let options = RunOptions {
+            dry_run: self.dry_run,
+            ..Default::default()
+        };
+
+        Box::pin(async move {
+            let (_, run) =
+                FlowContext::run_flow(engine, flow_id, HashMap::new(), inputs, options).await?;
+
+            run.await.map_err(|_| Error::RunAborted)
+        })
+    }
+
+    // shows what a running command waits for in the log of its node
+    pub(crate) async fn set_state(&self, state: RunState) {
+        if let Some(log) = &self.log {
+            log.lock().await.set_state(state).await;
+        }
+    }
+
+    // held while requests are sent to the host of the url, which is limited by the flow
+    // context. the rate limit is applied once per permit
+    pub(crate) async fn http_permit(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        let host = Url::parse(url).ok()?.host_str()?.to_owned();
+
+        let permit = self.engine.limiter.concurrency(&host).await;
+
+        self.engine.limiter.throttle(&host).await;
+
+        permit
+    }
+}
+
 #[derive(Debug)]
 pub enum CommandKind {
     Simple(simple::CommandKind),
@@ -38,3 +120,92 @@ impl Config {
         }
     }
 }
+
+impl From<simple::Command> for Config {
+    fn from(command: simple::Command) -> Config {
+        Config::Simple(command)
+    }
+}
+
+impl From<solana::Kind> for Config {
+    fn from(kind: solana::Kind) -> Config {
+        Config::Solana(kind)
+    }
+}
+
+impl From<solana::nft::Command> for Config {
+    fn from(command: solana::nft::Command) -> Config {
+        Config::Solana(solana::Kind::Nft(command))
+    }
+}
+
+// lets FlowBuilder take the command structs themselves
+macro_rules! config_from {
+    ($($variant:path => $command:ty),* $(,)?) => {
+        $(
+            impl From<$command> for Config {
+                fn from(command: $command) -> Config {
+                    $variant(command).into()
+                }
+            }
+        )*
+    };
+}
+
+config_from! {
+    simple::Command::HttpRequest => simple::http_request::HttpRequest,
+    simple::Command::JsonExtract => simple::json_extract::JsonExtract,
+    simple::Command::IpfsUpload => simple::ipfs_upload::IpfsUpload,
+    simple::Command::IpfsNftUpload => simple::ipfs_nft_upload::IpfsNftUpload,
+    simple::Command::Branch => simple::branch::Branch,
+    simple::Command::JsonInsert => simple::json_insert::JsonInsert,
+    simple::Command::Switch => simple::switch::Switch,
+    simple::Command::Expr => simple::expr::Expr,
+    simple::Command::CallFlow => simple::call_flow::CallFlow,
+    simple::Command::Approval => simple::approval::Approval,
+    simple::Command::Delay => simple::timer::Delay,
+    simple::Command::WaitUntil => simple::timer::WaitUntil,
+    simple::Command::Variable => simple::variable::Variable,
+    solana::Kind::GenerateKeypair => solana::generate_keypair::GenerateKeypair,
+    solana::Kind::DeleteKeypair => solana::delete_keypair::DeleteKeypair,
+    solana::Kind::AddPubkey => solana::add_pubkey::AddPubkey,
+    solana::Kind::DeletePubkey => solana::delete_pubkey::DeletePubkey,
+    solana::Kind::CreateTokenAccount => solana::create_token_account::CreateTokenAccount,
+    solana::Kind::GetBalance => solana::get_balance::GetBalance,
+    solana::Kind::CreateMintAccount => solana::create_mint_account::CreateMintAccount,
+    solana::Kind::RequestAirdrop => solana::request_airdrop::RequestAirdrop,
+    solana::Kind::MintToken => solana::mint_token::MintToken,
+    solana::Kind::TransferToken => solana::transfer_token::TransferToken,
+    solana::Kind::TransferSolana => solana::transfer_solana::TransferSolana,
+    solana::Kind::WaitForSlot => solana::wait_for_slot::WaitForSlot,
+    solana::nft::Command::CreateMetadataAccounts => solana::nft::create_metadata_accounts::CreateMetadataAccounts,
+    solana::nft::Command::CreateMasterEdition => solana::nft::create_master_edition::CreateMasterEdition,
+    solana::nft::Command::UpdateMetadataAccounts => solana::nft::update_metadata_accounts::UpdateMetadataAccounts,
+    solana::nft::Command::Utilize => solana::nft::utilize::Utilize,
+    solana::nft::Command::ApproveUseAuthority => solana::nft::approve_use_authority::ApproveUseAuthority,
+    solana::nft::Command::GetLeftUses => solana::nft::get_left_uses::GetLeftUses,
+    solana::nft::Command::ArweaveNftUpload => solana::nft::arweave_nft_upload::ArweaveNftUpload,
+    solana::nft::Command::ArweaveFileUpload => solana::nft::arweave_file_upload::ArweaveFileUpload,
+    solana::nft::Command::VerifyCollection => solana::nft::verify_collection::VerifyCollection,
+    solana::nft::Command::SignMetadata => solana::nft::sign_metadata::SignMetadata,
+    solana::nft::Command::ApproveCollectionAuthority => solana::nft::approve_collection_authority::ApproveCollectionAuthority,
+    solana::nft::Command::CreateAuctionHouse => solana::nft::create_auction_house::CreateAuctionHouse,
+    solana::nft::Command::AuctionHouseSell => solana::nft::auction_house_sell::AuctionHouseSell,
+}
+
+// outputs for a command which picks one of several paths: the taken output gets `value`,
+// every other one gets Cancel, which cancels the nodes connected to it
+pub(crate) fn route<'a>(
+    taken: &str,
+    value: Value,
+    outputs: impl IntoIterator<Item = &'a str>,
+) -> HashMap<String, Value> {
+    let mut routed: HashMap<String, Value> = outputs
+        .into_iter()
+        .map(|output| (output.to_owned(), Value::Cancel))
+        .collect();
+
+    routed.insert(taken.to_owned(), value);
+
+    routed
+}
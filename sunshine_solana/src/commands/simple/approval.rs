@@ -0,0 +1,73 @@
+use crate::approval::{self, Decision, PendingApproval};
+use crate::commands::route;
+use crate::commands::{util::Args, Env};
+use crate::{Error, RunState, Value};
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// the run waits in AwaitingApproval until the node is approved or rejected through the
+// flow context. `value` is passed to the "approved" or the "rejected" output, the other
+// one is canceled
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Approval {
+    // shown to whoever decides the approval
+    pub message: Option<String>,
+    // the approval is rejected when nobody decides in time
+    pub timeout_secs: Option<u64>,
+}
+
+impl Approval {
+    pub(crate) async fn run(
+        &self,
+        inputs: HashMap<String, Value>,
+        env: &Env,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let value = args.take("value").unwrap_or(Value::Empty);
+
+        let decision = match &env.node {
+            Some((node_id, name)) => {
+                let timeout = self.timeout_secs.map(Duration::from_secs);
+
+                let requested_at = Utc::now();
+
+                // the inputs aren't part of the request, they can hold keypairs
+                let pending = PendingApproval {
+                    run_id: env.run_id,
+                    flow_id: env.flow_id,
+                    node_id: *node_id,
+                    name: name.clone(),
+                    message: self.message.clone(),
+                    requested_at,
+                    expires_at: timeout
+                        .and_then(|timeout| chrono::Duration::from_std(timeout).ok())
+                        .and_then(|timeout| requested_at.checked_add_signed(timeout)),
+                };
+
+                env.set_state(RunState::AwaitingApproval).await;
+
+                tracing::info!("awaiting approval");
+
+                let decision = approval::request(&env.approvals, pending, timeout).await;
+
+                tracing::info!(?decision, "approval decided");
+
+                decision
+            }
+            // the env of the flow isn't handed to a node, nobody could approve it
+            None => Decision::Reject,
+        };
+
+        let taken = match decision {
+            Decision::Approve => "approved",
+            Decision::Reject => "rejected",
+        };
+
+        Ok(route(taken, value, ["approved", "rejected"]))
+    }
+}
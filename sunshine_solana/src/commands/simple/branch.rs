@@ -1,3 +1,5 @@
+use crate::commands::route;
+use crate::commands::util::Args;
 use crate::{Error, Value};
 
 use std::collections::HashMap;
@@ -24,63 +26,55 @@ pub enum Operator {
 impl Branch {
     pub(crate) async fn run(
         &self,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
-        let operator = match &self.operator {
-            Some(s) => s.clone(),
-            None => match inputs.remove("operator") {
-                Some(Value::Operator(p)) => p,
-                _ => return Err(Error::ArgumentNotFound("operator".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let a = match &self.a {
-            Some(s) => s.clone(),
-            None => match inputs.remove("a") {
-                Some(v) => v,
-                _ => return Err(Error::ArgumentNotFound("a".to_string())),
-            },
-        };
+        let operator = args.required(self.operator, "operator")?;
 
-        let b = match &self.b {
-            Some(s) => s.clone(),
-            None => match inputs.remove("b") {
-                Some(v) => v,
-                _ => return Err(Error::ArgumentNotFound("b".to_string())),
-            },
-        };
+        let a = args.value(self.a.clone(), "a")?;
 
-        let mut outputs = HashMap::new();
+        let b = args.value(self.b.clone(), "b")?;
 
-        let res = match (a, operator, b) {
-            (Value::U64(a), Operator::Eq, Value::U64(b)) => a == b,
-            (Value::U64(a), Operator::NotEq, Value::U64(b)) => a != b,
-            (Value::U64(a), Operator::Greater, Value::U64(b)) => a > b,
-            (Value::U64(a), Operator::Less, Value::U64(b)) => a < b,
-            (Value::U64(a), Operator::GreaterEq, Value::U64(b)) => a >= b,
-            (Value::U64(a), Operator::LessEq, Value::U64(b)) => a <= b,
-            //
-            (Value::F64(a), Operator::Eq, Value::F64(b)) => a == b,
-            (Value::F64(a), Operator::NotEq, Value::F64(b)) => a != b,
-            (Value::F64(a), Operator::Greater, Value::F64(b)) => a > b,
-            (Value::F64(a), Operator::Less, Value::F64(b)) => a < b,
-            (Value::F64(a), Operator::GreaterEq, Value::F64(b)) => a >= b,
-            (Value::F64(a), Operator::LessEq, Value::F64(b)) => a <= b,
-            (a, operator, b) => {
-                return Err(Error::ComparisonError {
-                    a: a.kind(),
-                    operator,
-                    b: b.kind(),
-                })
-            }
-        };
+        let res = compare(a, operator, b)?;
 
-        if res {
-            outputs.insert("__true_branch".to_owned(), Value::Empty);
+        let taken = if res {
+            "__true_branch"
         } else {
-            outputs.insert("__false_branch".to_owned(), Value::Empty);
-        }
+            "__false_branch"
+        };
 
-        Ok(outputs)
+        Ok(route(
+            taken,
+            Value::Empty,
+            ["__true_branch", "__false_branch"],
+        ))
     }
 }
+
+pub(crate) fn compare(a: Value, operator: Operator, b: Value) -> Result<bool, Error> {
+    let res = match (a, operator, b) {
+        (Value::U64(a), Operator::Eq, Value::U64(b)) => a == b,
+        (Value::U64(a), Operator::NotEq, Value::U64(b)) => a != b,
+        (Value::U64(a), Operator::Greater, Value::U64(b)) => a > b,
+        (Value::U64(a), Operator::Less, Value::U64(b)) => a < b,
+        (Value::U64(a), Operator::GreaterEq, Value::U64(b)) => a >= b,
+        (Value::U64(a), Operator::LessEq, Value::U64(b)) => a <= b,
+        //
+        (Value::F64(a), Operator::Eq, Value::F64(b)) => a == b,
+        (Value::F64(a), Operator::NotEq, Value::F64(b)) => a != b,
+        (Value::F64(a), Operator::Greater, Value::F64(b)) => a > b,
+        (Value::F64(a), Operator::Less, Value::F64(b)) => a < b,
+        (Value::F64(a), Operator::GreaterEq, Value::F64(b)) => a >= b,
+        (Value::F64(a), Operator::LessEq, Value::F64(b)) => a <= b,
+        (a, operator, b) => {
+            return Err(Error::ComparisonError {
+                a: a.kind(),
+                operator,
+                b: b.kind(),
+            })
+        }
+    };
+
+    Ok(res)
+}
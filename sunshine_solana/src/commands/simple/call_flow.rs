@@ -0,0 +1,27 @@
+use crate::commands::{util::Args, Env};
+use crate::{Error, FlowId, Value};
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+// runs another flow like a function: every other input is passed as a flow input
+// and the flow outputs of the run become the outputs of the node
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CallFlow {
+    pub flow_id: Option<FlowId>,
+}
+
+impl CallFlow {
+    pub(crate) async fn run(
+        &self,
+        inputs: HashMap<String, Value>,
+        env: &Env,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let flow_id = args.required(self.flow_id, "flow_id")?;
+
+        env.call_flow(flow_id, args.into_inputs()).await
+    }
+}
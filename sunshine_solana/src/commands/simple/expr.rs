@@ -0,0 +1,615 @@
+use crate::commands::{util::Args, Env};
+use crate::{Error, Value};
+
+use maplit::hashmap;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+// evaluates an expression over the inputs of the node, e.g.
+// `amount * 1.05`, `lamports / 1000000000 >= 2` or `"NFT #{index}: {meta.name}"`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Expr {
+    pub expression: Option<String>,
+}
+
+impl Expr {
+    pub(crate) async fn run(
+        &self,
+        inputs: HashMap<String, Value>,
+        env: &Env,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let expression: String = args.required(self.expression.clone(), "expression")?;
+
+        // every other input is a variable, numeric variants all become json numbers.
+        // flow variables are in scope too, an input with the same name shadows them
+        let mut vars = HashMap::new();
+
+        for (name, value) in env.variables.clone().into_iter().chain(args.into_inputs()) {
+            vars.insert(name, JsonValue::try_from(value)?);
+        }
+
+        let result = eval(&expression, &vars)?;
+
+        let outputs = hashmap! {
+            "value".to_owned() => Value::try_from(result)?,
+        };
+
+        Ok(outputs)
+    }
+}
+
+pub(crate) fn eval(
+    expression: &str,
+    vars: &HashMap<String, JsonValue>,
+) -> Result<JsonValue, Error> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        pos: 0,
+    };
+
+    let node = parser.expr()?;
+
+    match parser.peek() {
+        Token::End => node.eval(vars),
+        token => Err(expr_error(format!("unexpected {:?}", token))),
+    }
+}
+
+fn expr_error(msg: impl Into<String>) -> Error {
+    Error::Expression(msg.into())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    End,
+}
+
+const OPERATORS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", "[", "]",
+    ".",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            // digits after an accessor are an index, `items.0.1` isn't `items` `.` `0.1`
+            let index = tokens.last() == Some(&Token::Op("."));
+            if !index
+                && chars.get(i) == Some(&'.')
+                && chars.get(i + 1).map_or(false, char::is_ascii_digit)
+            {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let num: String = chars[start..i].iter().collect();
+
+            let token = match num.parse::<i64>() {
+                Ok(n) => Token::Int(n),
+                Err(_) => Token::Float(
+                    num.parse()
+                        .map_err(|_| expr_error(format!("invalid number {}", num)))?,
+                ),
+            };
+            tokens.push(token);
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let mut lit = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some('\\') => {
+                        match chars.get(i + 1) {
+                            Some('n') => lit.push('\n'),
+                            Some(&c) => lit.push(c),
+                            None => return Err(expr_error("unterminated string")),
+                        }
+                        i += 2;
+                    }
+                    Some(&q) if q == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&c) => {
+                        lit.push(c);
+                        i += 1;
+                    }
+                    None => return Err(expr_error("unterminated string")),
+                }
+            }
+            tokens.push(Token::Str(lit));
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| expr_error(format!("unexpected character {}", c)))?;
+            i += op.len();
+            tokens.push(Token::Op(*op));
+        }
+    }
+
+    tokens.push(Token::End);
+
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Node {
+    Literal(JsonValue),
+    // string literal with `{expr}` parts, `{{` and `}}` escape braces
+    Template(Vec<TemplatePart>),
+    Var(String),
+    Index(Box<Node>, Box<Node>),
+    Unary(&'static str, Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug)]
+enum TemplatePart {
+    Text(String),
+    Expr(Node),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Token {
+        self.tokens[self.pos].clone()
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek();
+        if token != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Token::Op(op) if ops.contains(&op) => {
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &'static str) -> Result<(), Error> {
+        self.eat(&[op])
+            .map(|_| ())
+            .ok_or_else(|| expr_error(format!("expected {}", op)))
+    }
+
+    fn expr(&mut self) -> Result<Node, Error> {
+        self.binary(0)
+    }
+
+    // operators from the lowest to the highest precedence
+    fn binary(&mut self, level: usize) -> Result<Node, Error> {
+        const LEVELS: &[&[&str]] = &[
+            &["||"],
+            &["&&"],
+            &["==", "!=", "<=", ">=", "<", ">"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut lhs = self.binary(level + 1)?;
+
+        while let Some(op) = self.eat(LEVELS[level]) {
+            let rhs = self.binary(level + 1)?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Node, Error> {
+        match self.eat(&["-", "!"]) {
+            Some(op) => Ok(Node::Unary(op, Box::new(self.unary()?))),
+            None => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Node, Error> {
+        let mut node = self.primary()?;
+
+        loop {
+            if self.eat(&["."]).is_some() {
+                let key = match self.next() {
+                    Token::Ident(key) => JsonValue::String(key),
+                    Token::Int(i) => JsonValue::from(i),
+                    token => return Err(expr_error(format!("unexpected {:?} after .", token))),
+                };
+                node = Node::Index(Box::new(node), Box::new(Node::Literal(key)));
+            } else if self.eat(&["["]).is_some() {
+                let index = self.expr()?;
+                self.expect("]")?;
+                node = Node::Index(Box::new(node), Box::new(index));
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, Error> {
+        let node = match self.next() {
+            Token::Int(i) => Node::Literal(JsonValue::from(i)),
+            Token::Float(f) => Node::Literal(JsonValue::from(f)),
+            Token::Str(s) => parse_template(&s)?,
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Node::Literal(JsonValue::Bool(true)),
+                "false" => Node::Literal(JsonValue::Bool(false)),
+                "null" => Node::Literal(JsonValue::Null),
+                _ => Node::Var(ident),
+            },
+            Token::Op("(") => {
+                let node = self.expr()?;
+                self.expect(")")?;
+                node
+            }
+            token => return Err(expr_error(format!("unexpected {:?}", token))),
+        };
+
+        Ok(node)
+    }
+}
+
+fn parse_template(s: &str) -> Result<Node, Error> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                // a } inside a string literal doesn't close the interpolation
+                let mut inner = String::new();
+                let mut quote = None;
+                loop {
+                    match (chars.next(), quote) {
+                        (Some('}'), None) => break,
+                        (Some('\\'), Some(_)) => {
+                            inner.push('\\');
+                            match chars.next() {
+                                Some(c) => inner.push(c),
+                                None => return Err(expr_error("unclosed { in string")),
+                            }
+                        }
+                        (Some(c), None) if c == '"' || c == '\'' => {
+                            quote = Some(c);
+                            inner.push(c);
+                        }
+                        (Some(c), Some(q)) if c == q => {
+                            quote = None;
+                            inner.push(c);
+                        }
+                        (Some(c), _) => inner.push(c),
+                        (None, _) => return Err(expr_error("unclosed { in string")),
+                    }
+                }
+
+                let mut parser = Parser {
+                    tokens: tokenize(&inner)?,
+                    pos: 0,
+                };
+                let node = parser.expr()?;
+                if parser.peek() != Token::End {
+                    return Err(expr_error(format!("invalid interpolation {{{}}}", inner)));
+                }
+
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(TemplatePart::Expr(node));
+            }
+            c => text.push(c),
+        }
+    }
+
+    if parts.is_empty() {
+        return Ok(Node::Literal(JsonValue::String(text)));
+    }
+
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+
+    Ok(Node::Template(parts))
+}
+
+impl Node {
+    fn eval(&self, vars: &HashMap<String, JsonValue>) -> Result<JsonValue, Error> {
+        let res = match self {
+            Node::Literal(v) => v.clone(),
+            Node::Template(parts) => {
+                let mut s = String::new();
+                for part in parts {
+                    match part {
+                        TemplatePart::Text(text) => s.push_str(text),
+                        TemplatePart::Expr(node) => s.push_str(&to_string(&node.eval(vars)?)),
+                    }
+                }
+                JsonValue::String(s)
+            }
+            Node::Var(name) => vars
+                .get(name)
+                .cloned()
+                .ok_or_else(|| expr_error(format!("unknown variable {}", name)))?,
+            Node::Index(node, index) => {
+                let value = node.eval(vars)?;
+                let index = index.eval(vars)?;
+
+                let res = match (&value, &index) {
+                    (JsonValue::Object(map), JsonValue::String(key)) => map.get(key),
+                    (JsonValue::Array(array), JsonValue::Number(i)) => {
+                        i.as_u64().and_then(|i| array.get(i as usize))
+                    }
+                    _ => return Err(expr_error(format!("can't index {} with {}", value, index))),
+                };
+
+                res.cloned().unwrap_or(JsonValue::Null)
+            }
+            Node::Unary(op, node) => {
+                let value = node.eval(vars)?;
+                match (*op, &value) {
+                    ("!", JsonValue::Bool(b)) => JsonValue::Bool(!b),
+                    ("-", JsonValue::Number(_)) => arithmetic("-", &JsonValue::from(0), &value)?,
+                    _ => return Err(expr_error(format!("can't apply {} to {}", op, value))),
+                }
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(vars)?;
+
+                // short circuit
+                match (*op, &lhs) {
+                    ("&&", JsonValue::Bool(false)) => return Ok(JsonValue::Bool(false)),
+                    ("||", JsonValue::Bool(true)) => return Ok(JsonValue::Bool(true)),
+                    _ => (),
+                }
+
+                let rhs = rhs.eval(vars)?;
+
+                binary(op, &lhs, &rhs)?
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+fn binary(op: &str, lhs: &JsonValue, rhs: &JsonValue) -> Result<JsonValue, Error> {
+    let res = match (op, lhs, rhs) {
+        ("&&" | "||", JsonValue::Bool(_), JsonValue::Bool(b)) => JsonValue::Bool(*b),
+        ("==", a, b) => JsonValue::Bool(json_eq(a, b)),
+        ("!=", a, b) => JsonValue::Bool(!json_eq(a, b)),
+        ("<" | ">" | "<=" | ">=", JsonValue::Number(_), JsonValue::Number(_)) => {
+            let ordering = number_cmp(lhs, rhs)
+                .ok_or_else(|| expr_error(format!("can't compare {} and {}", lhs, rhs)))?;
+            JsonValue::Bool(match op {
+                "<" => ordering == Ordering::Less,
+                ">" => ordering == Ordering::Greater,
+                "<=" => ordering != Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            })
+        }
+        ("<" | ">" | "<=" | ">=", JsonValue::String(a), JsonValue::String(b)) => {
+            JsonValue::Bool(match op {
+                "<" => a < b,
+                ">" => a > b,
+                "<=" => a <= b,
+                _ => a >= b,
+            })
+        }
+        // + concatenates as soon as one side is a string
+        ("+", JsonValue::String(_), _) | ("+", _, JsonValue::String(_)) => {
+            JsonValue::String(to_string(lhs) + &to_string(rhs))
+        }
+        (_, JsonValue::Number(_), JsonValue::Number(_)) => arithmetic(op, lhs, rhs)?,
+        _ => {
+            return Err(expr_error(format!(
+                "can't apply {} to {} and {}",
+                op, lhs, rhs
+            )))
+        }
+    };
+
+    Ok(res)
+}
+
+// integers stay integers unless they overflow, division always gives a float
+fn arithmetic(op: &str, lhs: &JsonValue, rhs: &JsonValue) -> Result<JsonValue, Error> {
+    if let (Some(a), Some(b)) = (integer(lhs), integer(rhs)) {
+        let res = match op {
+            "+" => a.checked_add(b),
+            "-" => a.checked_sub(b),
+            "*" => a.checked_mul(b),
+            "%" => a.checked_rem(b),
+            _ => None,
+        };
+
+        if let Some(res) = res {
+            if let Ok(res) = i64::try_from(res) {
+                return Ok(JsonValue::from(res));
+            }
+
+            if let Ok(res) = u64::try_from(res) {
+                return Ok(JsonValue::from(res));
+            }
+        }
+    }
+
+    let (a, b) = match (lhs.as_f64(), rhs.as_f64()) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            return Err(expr_error(format!(
+                "can't apply {} to {} and {}",
+                op, lhs, rhs
+            )))
+        }
+    };
+
+    let res = match op {
+        "+" => a + b,
+        "-" => a - b,
+        "*" => a * b,
+        "/" => a / b,
+        "%" => a % b,
+        _ => return Err(expr_error(format!("unknown operator {}", op))),
+    };
+
+    serde_json::Number::from_f64(res)
+        .map(JsonValue::Number)
+        .ok_or_else(|| expr_error(format!("{} {} {} isn't a finite number", lhs, op, rhs)))
+}
+
+pub(crate) fn json_eq(a: &JsonValue, b: &JsonValue) -> bool {
+    match number_cmp(a, b) {
+        Some(ordering) => ordering == Ordering::Equal,
+        None => a == b,
+    }
+}
+
+// integers are compared exactly, f64 can't tell large u64 apart. numbers are only compared
+// as f64 when one of them is a float
+fn number_cmp(a: &JsonValue, b: &JsonValue) -> Option<Ordering> {
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+    }
+}
+
+fn integer(json: &JsonValue) -> Option<i128> {
+    json.as_i64()
+        .map(i128::from)
+        .or_else(|| json.as_u64().map(i128::from))
+}
+
+fn to_string(json: &JsonValue) -> String {
+    match json {
+        JsonValue::String(s) => s.clone(),
+        json => json.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn evaluates_arithmetic_and_templates() {
+        let vars: HashMap<String, JsonValue> = vec![
+            ("amount".to_owned(), json!(100)),
+            ("lamports".to_owned(), json!(2500000000u64)),
+            ("index".to_owned(), json!(7)),
+            (
+                "meta".to_owned(),
+                json!({ "name": "cat", "tags": ["a", "b"] }),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(eval("amount * 1.5", &vars).unwrap(), json!(150.0));
+        assert_eq!(eval("(amount + 4) % 3", &vars).unwrap(), json!(2));
+        assert_eq!(eval("lamports / 1000000000", &vars).unwrap(), json!(2.5));
+        assert_eq!(
+            eval("lamports >= 1000000000 && !false", &vars).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            eval("\"NFT #{index}: {meta.name} {{{meta.tags[1]}}}\"", &vars).unwrap(),
+            json!("NFT #7: cat {b}")
+        );
+        assert_eq!(eval("meta.tags.0 + 1", &vars).unwrap(), json!("a1"));
+        assert!(eval("missing + 1", &vars).is_err());
+    }
+
+    #[test]
+    fn large_integers_are_compared_and_combined_exactly() {
+        let vars: HashMap<String, JsonValue> = vec![
+            ("a".to_owned(), json!(9007199254740993u64)),
+            ("b".to_owned(), json!(9007199254740992u64)),
+            ("max".to_owned(), json!(u64::MAX)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(eval("a == b", &vars).unwrap(), json!(false));
+        assert_eq!(eval("a > b", &vars).unwrap(), json!(true));
+        assert_eq!(eval("a - b", &vars).unwrap(), json!(1));
+        assert_eq!(eval("max - 1 < max", &vars).unwrap(), json!(true));
+        assert_eq!(eval("b == 9007199254740992.0", &vars).unwrap(), json!(true));
+    }
+
+    #[test]
+    fn digits_after_an_accessor_are_indexes() {
+        let vars: HashMap<String, JsonValue> = vec![("items".to_owned(), json!([[1, 2], [3, 4]]))]
+            .into_iter()
+            .collect();
+
+        assert_eq!(eval("items.0.1", &vars).unwrap(), json!(2));
+        assert_eq!(eval("items.1.0 + 0.5", &vars).unwrap(), json!(3.5));
+    }
+
+    #[test]
+    fn braces_in_interpolated_strings_dont_close_them() {
+        let vars: HashMap<String, JsonValue> = vec![("name".to_owned(), json!("cat"))]
+            .into_iter()
+            .collect();
+
+        assert_eq!(eval("\"{name + '}'}!\"", &vars).unwrap(), json!("cat}!"));
+        assert_eq!(
+            eval("\"{'{' + name + \\\"}\\\"}\"", &vars).unwrap(),
+            json!("{cat}")
+        );
+        assert!(eval("\"{name + '}\"", &vars).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::commands::{util::Args, Env};
 use crate::{Error, Value};
 
 use maplit::hashmap;
@@ -21,57 +22,32 @@ pub struct HttpRequest {
 impl HttpRequest {
     pub(crate) async fn run(
         &self,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let method = match &self.method {
-            Some(s) => s.clone(),
-            None => match inputs.remove("method") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("method".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let url = match &self.url {
-            Some(s) => s.clone(),
-            None => match inputs.remove("url") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("url".to_string())),
-            },
-        };
+        let method = args.required(self.method.clone(), "method")?;
 
-        let auth_token = match self.auth_token.as_ref() {
-            Some(auth_token) => Some(auth_token.clone()),
-            None => match inputs.get("auth_token") {
-                Some(Value::String(ref s)) => Some(s.clone()),
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("auth_token".to_string())),
-            },
-        };
+        let url = args.required(self.url.clone(), "url")?;
 
-        let json_body = match &self.json_body {
-            Some(v) => v
-                .as_ref()
-                .map(|v| JsonValue::try_from(v.clone()))
-                .transpose()?,
-            None => match inputs.remove("json_body") {
-                Some(v) => Some(JsonValue::try_from(v)?),
-                None => None,
-            },
-        };
+        let auth_token: Option<String> =
+            args.optional(self.auth_token.clone().map(Some), "auth_token")?;
 
-        let headers = match &self.json_body {
-            Some(v) => v
-                .as_ref()
-                .map(|v| JsonValue::try_from(v.clone()))
-                .transpose()?,
-            None => match inputs.remove("headers") {
-                Some(v) => Some(JsonValue::try_from(v)?),
-                None => None,
-            },
-        };
+        let json_body = args
+            .optional_value(self.json_body.clone(), "json_body")
+            .map(JsonValue::try_from)
+            .transpose()?;
+
+        let headers = args
+            .optional_value(self.headers.clone(), "headers")
+            .map(JsonValue::try_from)
+            .transpose()?;
 
         let method = Method::from_bytes(method.as_bytes()).map_err(|_| Error::InvalidHttpMethod)?;
 
+        let _permit = env.http_permit(&url).await;
+
         let client = Client::new();
 
         let mut builder = client.request(method, url);
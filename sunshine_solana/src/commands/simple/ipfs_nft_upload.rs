@@ -1,3 +1,4 @@
+use crate::commands::{util::Args, Env};
 use crate::{Error, NftMetadata, Value};
 
 use maplit::hashmap;
@@ -21,31 +22,19 @@ pub struct IpfsNftUpload {
 impl IpfsNftUpload {
     pub(crate) async fn run(
         &self,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let pinata_url = match &self.pinata_url {
-            Some(s) => s.clone(),
-            None => match inputs.remove("pinata_url") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("pinata_url".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let pinata_jwt = match &self.pinata_jwt {
-            Some(s) => s.clone(),
-            None => match inputs.remove("pinata_jwt") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("pinata_jwt".to_string())),
-            },
-        };
+        let pinata_url = args.required(self.pinata_url.clone(), "pinata_url")?;
 
-        let mut metadata = match &self.metadata {
-            Some(s) => s.clone(),
-            None => match inputs.remove("metadata") {
-                Some(Value::NftMetadata(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("metadata".to_string())),
-            },
-        };
+        let pinata_jwt = args.required(self.pinata_jwt.clone(), "pinata_jwt")?;
+
+        let mut metadata: NftMetadata = args.required(self.metadata.clone(), "metadata")?;
+
+        // every file and the metadata are uploaded under the same permit
+        let _permit = env.http_permit(&pinata_url).await;
 
         metadata.image = format!(
             "ipfs://{}",
@@ -1,3 +1,4 @@
+use crate::commands::{util::Args, Env};
 use crate::{Error, Value};
 
 use maplit::hashmap;
@@ -21,31 +22,16 @@ pub struct IpfsUpload {
 impl IpfsUpload {
     pub(crate) async fn run(
         &self,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let pinata_url = match &self.pinata_url {
-            Some(s) => s.clone(),
-            None => match inputs.remove("pinata_url") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("pinata_url".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let pinata_jwt = match &self.pinata_jwt {
-            Some(s) => s.clone(),
-            None => match inputs.remove("pinata_jwt") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("pinata_jwt".to_string())),
-            },
-        };
+        let pinata_url = args.required(self.pinata_url.clone(), "pinata_url")?;
 
-        let file_path = match &self.file_path {
-            Some(s) => s.clone(),
-            None => match inputs.remove("file_path") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("file_path".to_string())),
-            },
-        };
+        let pinata_jwt = args.required(self.pinata_jwt.clone(), "pinata_jwt")?;
+
+        let file_path = args.required(self.file_path.clone(), "file_path")?;
 
         let path = Path::new(&file_path).to_path_buf();
 
@@ -58,6 +44,8 @@ impl IpfsUpload {
 
         let file = tokio::fs::read(path).await?;
 
+        let _permit = env.http_permit(&pinata_url).await;
+
         let client = Client::new();
 
         let req = client
@@ -1,3 +1,4 @@
+use crate::commands::util::Args;
 use crate::{Error, Value};
 
 use maplit::hashmap;
@@ -17,23 +18,13 @@ pub struct JsonExtract {
 impl JsonExtract {
     pub(crate) async fn run(
         &self,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
-        let json = match &self.json {
-            Some(v) => JsonValue::try_from(v.clone())?,
-            None => match inputs.remove("json") {
-                Some(v) => serde_json::to_value(v)?,
-                _ => return Err(Error::ArgumentNotFound("json".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let path = match &self.path {
-            Some(s) => s.clone(),
-            None => match inputs.remove("path") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("path".to_string())),
-            },
-        };
+        let json = JsonValue::try_from(args.value(self.json.clone(), "json")?)?;
+
+        let path = args.required(self.path.clone(), "path")?;
 
         let val = match json.pointer(&path) {
             Some(v) => Value::try_from(v.clone())?,
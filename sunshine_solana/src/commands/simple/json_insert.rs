@@ -1,3 +1,4 @@
+use crate::commands::util::Args;
 use crate::{
     commands::solana::nft::update_metadata_accounts::MetadataAccountData, Error, NftCreator,
     NftMetadata, Value, ValueKind,
@@ -21,30 +22,17 @@ pub struct JsonInsert {
 impl JsonInsert {
     pub(crate) async fn run(
         &self,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mut json = match &self.json {
-            Some(v) => JsonValue::try_from(v.clone())?,
-            None => match inputs.remove("json") {
-                Some(v) => serde_json::to_value(&v)?,
-                _ => return Err(Error::ArgumentNotFound("json".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let path = match &self.path {
-            Some(s) => s.clone(),
-            None => match inputs.remove("path") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("path".to_string())),
-            },
-        };
+        let mut json = JsonValue::try_from(args.value(self.json.clone(), "json")?)?;
+
+        let path = args.required(self.path.clone(), "path")?;
 
-        let value = match &self.value {
-            Some(v) => JsonValue::try_from(v.clone())?,
-            None => match inputs.remove("value") {
-                Some(v) => JsonValue::try_from(v)?,
-                None => JsonValue::Null,
-            },
+        let value = match args.optional_value(self.value.clone().map(Some), "value") {
+            Some(v) => JsonValue::try_from(v)?,
+            None => JsonValue::Null,
         };
 
         match json.pointer_mut(&path) {
@@ -4,14 +4,21 @@ use serde::{Deserialize, Serialize};
 
 use maplit::hashmap;
 
+use crate::commands::Env;
 use crate::{Error, Value};
 
+pub mod approval;
 pub mod branch;
+pub mod call_flow;
+pub mod expr;
 pub mod http_request;
 pub mod ipfs_nft_upload;
 pub mod ipfs_upload;
 pub mod json_extract;
 pub mod json_insert;
+pub mod switch;
+pub mod timer;
+pub mod variable;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Command {
@@ -24,12 +31,20 @@ pub enum Command {
     Wait,
     Branch(branch::Branch),
     JsonInsert(json_insert::JsonInsert),
+    Switch(switch::Switch),
+    Expr(expr::Expr),
+    CallFlow(call_flow::CallFlow),
+    Approval(approval::Approval),
+    Delay(timer::Delay),
+    WaitUntil(timer::WaitUntil),
+    Variable(variable::Variable),
 }
 
 impl Command {
     pub(crate) async fn run(
         &self,
         mut inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
         match self {
             Command::Const(c) => Ok(hashmap! {
@@ -66,12 +81,19 @@ impl Command {
                     "value".into() => value
                 })
             }
-            Command::HttpRequest(c) => c.run(inputs).await,
+            Command::HttpRequest(c) => c.run(inputs, env).await,
             Command::JsonExtract(c) => c.run(inputs).await,
-            Command::IpfsUpload(c) => c.run(inputs).await,
-            Command::IpfsNftUpload(c) => c.run(inputs).await,
+            Command::IpfsUpload(c) => c.run(inputs, env).await,
+            Command::IpfsNftUpload(c) => c.run(inputs, env).await,
             Command::Branch(c) => c.run(inputs).await,
             Command::JsonInsert(c) => c.run(inputs).await,
+            Command::Switch(c) => c.run(inputs).await,
+            Command::Expr(c) => c.run(inputs, env).await,
+            Command::CallFlow(c) => c.run(inputs, env).await,
+            Command::Approval(c) => c.run(inputs, env).await,
+            Command::Delay(c) => c.run(inputs, env).await,
+            Command::WaitUntil(c) => c.run(inputs, env).await,
+            Command::Variable(c) => c.run(inputs, env).await,
         }
     }
 
@@ -86,6 +108,13 @@ impl Command {
             Command::Wait => CommandKind::Wait,
             Command::Branch(_) => CommandKind::Branch,
             Command::JsonInsert(_) => CommandKind::JsonInsert,
+            Command::Switch(_) => CommandKind::Switch,
+            Command::Expr(_) => CommandKind::Expr,
+            Command::CallFlow(_) => CommandKind::CallFlow,
+            Command::Approval(_) => CommandKind::Approval,
+            Command::Delay(_) => CommandKind::Delay,
+            Command::WaitUntil(_) => CommandKind::WaitUntil,
+            Command::Variable(_) => CommandKind::Variable,
         }
     }
 }
@@ -101,4 +130,11 @@ pub enum CommandKind {
     Wait,
     Branch,
     JsonInsert,
+    Switch,
+    Expr,
+    CallFlow,
+    Approval,
+    Delay,
+    WaitUntil,
+    Variable,
 }
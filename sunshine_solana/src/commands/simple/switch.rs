@@ -0,0 +1,169 @@
+use crate::commands::route;
+use crate::commands::simple::branch::{compare, Operator};
+use crate::commands::simple::expr::json_eq;
+use crate::commands::util::Args;
+use crate::{Error, Value};
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Switch {
+    pub value: Option<Value>,
+    // checked in order, the first matching case is taken
+    pub cases: Vec<Case>,
+    // output taken when no case matches, every output is canceled if it's None
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Case {
+    pub output: String,
+    pub predicate: Predicate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Predicate {
+    Eq(Value),
+    NotEq(Value),
+    Compare(Operator, Value),
+    Regex(Pattern),
+    JsonPointer(String, Box<Predicate>),
+    Any,
+}
+
+impl Switch {
+    pub(crate) async fn run(
+        &self,
+        inputs: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let value = args.value(self.value.clone(), "value")?;
+
+        let mut taken = None;
+
+        for case in self.cases.iter() {
+            if case.predicate.matches(&value)? {
+                taken = Some(case.output.as_str());
+                break;
+            }
+        }
+
+        let outputs = self
+            .cases
+            .iter()
+            .map(|case| case.output.as_str())
+            .chain(self.default.as_deref());
+
+        let outputs = match taken.or(self.default.as_deref()) {
+            Some(taken) => route(taken, value, outputs),
+            None => outputs
+                .map(|output| (output.to_owned(), Value::Cancel))
+                .collect(),
+        };
+
+        Ok(outputs)
+    }
+}
+
+impl Predicate {
+    fn matches(&self, value: &Value) -> Result<bool, Error> {
+        let res = match self {
+            Predicate::Eq(other) => values_eq(value, other),
+            Predicate::NotEq(other) => !values_eq(value, other),
+            Predicate::Compare(operator, other) => {
+                compare(value.clone(), *operator, other.clone())?
+            }
+            Predicate::Regex(Pattern(regex)) => match value {
+                Value::String(s) => regex.is_match(s),
+                Value::Json(json) => match JsonValue::from(json.clone()) {
+                    JsonValue::String(s) => regex.is_match(&s),
+                    _ => false,
+                },
+                _ => false,
+            },
+            Predicate::JsonPointer(pointer, predicate) => {
+                let json = JsonValue::try_from(value.clone())?;
+
+                match json.pointer(pointer) {
+                    Some(json) => predicate.matches(&Value::try_from(json.clone())?)?,
+                    None => false,
+                }
+            }
+            Predicate::Any => true,
+        };
+
+        Ok(res)
+    }
+}
+
+// a regex compiled when the command is read, serialized as its pattern
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern, Error> {
+        Regex::new(pattern)
+            .map(Pattern)
+            .map_err(|e| Error::InvalidRegex(e.to_string()))
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pattern, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+
+        Pattern::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+// values are compared by their json representation, so e.g. a pubkey equals its base58 string
+fn values_eq(a: &Value, b: &Value) -> bool {
+    let (a, b) = match (
+        JsonValue::try_from(a.clone()),
+        JsonValue::try_from(b.clone()),
+    ) {
+        (Ok(a), Ok(b)) => (a, b),
+        _ => return false,
+    };
+
+    json_eq(&a, &b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_integers_are_compared_exactly() {
+        let big = 1u64 << 60;
+
+        assert!(values_eq(&Value::U64(big), &Value::U64(big)));
+        assert!(!values_eq(&Value::U64(big), &Value::U64(big + 1)));
+        assert!(values_eq(&Value::U64(3), &Value::F64(3.0)));
+    }
+
+    #[test]
+    fn regex_is_compiled_when_read() {
+        let predicate: Predicate =
+            serde_json::from_value(serde_json::json!({ "Regex": "^a+$" })).unwrap();
+
+        assert!(predicate.matches(&Value::String("aaa".to_owned())).unwrap());
+        assert!(!predicate.matches(&Value::String("ab".to_owned())).unwrap());
+
+        let invalid = serde_json::from_value::<Predicate>(serde_json::json!({ "Regex": "(" }));
+
+        assert!(invalid.is_err());
+    }
+}
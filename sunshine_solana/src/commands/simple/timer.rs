@@ -0,0 +1,93 @@
+use crate::commands::{util::Args, Env};
+use crate::{Deadline, Error, RunState, Value};
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeZone, Utc};
+use maplit::hashmap;
+use serde::{Deserialize, Serialize};
+
+// passes `value` on after waiting for `duration_ms`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Delay {
+    pub duration_ms: Option<u64>,
+}
+
+// passes `value` on at `timestamp`, in seconds since the unix epoch. a timestamp in the
+// past passes it on right away
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WaitUntil {
+    pub timestamp: Option<i64>,
+}
+
+impl Delay {
+    pub(crate) async fn run(
+        &self,
+        inputs: HashMap<String, Value>,
+        env: &Env,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let duration_ms = args.required(self.duration_ms, "duration_ms")?;
+
+        let value = args.take("value").unwrap_or(Value::Empty);
+
+        // a delay past the range of timestamps can't be waited for
+        let until = chrono::Duration::from_std(std::time::Duration::from_millis(duration_ms))
+            .ok()
+            .and_then(|duration| Utc::now().checked_add_signed(duration))
+            .ok_or_else(|| Error::InvalidArgument {
+                port: "duration_ms".to_owned(),
+                source: Box::new(Error::InvalidDuration(duration_ms)),
+            })?;
+
+        sleep_until(env, until).await;
+
+        Ok(hashmap! {
+            "value".to_owned() => value,
+        })
+    }
+}
+
+impl WaitUntil {
+    pub(crate) async fn run(
+        &self,
+        inputs: HashMap<String, Value>,
+        env: &Env,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let timestamp = args.required(self.timestamp, "timestamp")?;
+
+        let value = args.take("value").unwrap_or(Value::Empty);
+
+        let until =
+            Utc.timestamp_opt(timestamp, 0)
+                .single()
+                .ok_or_else(|| Error::InvalidArgument {
+                    port: "timestamp".to_owned(),
+                    source: Box::new(Error::InvalidTimestamp(timestamp)),
+                })?;
+
+        sleep_until(env, until).await;
+
+        Ok(hashmap! {
+            "value".to_owned() => value,
+        })
+    }
+}
+
+// the node is Waiting until the deadline. a dry run doesn't wait. stopping the run drops
+// the sleep with the rest of the node
+async fn sleep_until(env: &Env, until: DateTime<Utc>) {
+    if env.dry_run {
+        return;
+    }
+
+    env.set_state(RunState::Waiting(Deadline::Time(until)))
+        .await;
+
+    if let Ok(duration) = (until - Utc::now()).to_std() {
+        tokio::time::sleep(duration).await;
+    }
+}
@@ -0,0 +1,36 @@
+use crate::commands::{util::Args, Env};
+use crate::{Error, Value};
+
+use maplit::hashmap;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+// passes on the value of the flow variable `name`, after the overrides of the deployment
+// were applied. commands only see flow variables through this node or an expression
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Variable {
+    pub name: Option<String>,
+}
+
+impl Variable {
+    pub(crate) async fn run(
+        &self,
+        inputs: HashMap<String, Value>,
+        env: &Env,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let name: String = args.required(self.name.clone(), "name")?;
+
+        let value = env
+            .variables
+            .get(&name)
+            .cloned()
+            .ok_or(Error::UnknownFlowVariable(name))?;
+
+        Ok(hashmap! {
+            "value".to_owned() => value,
+        })
+    }
+}
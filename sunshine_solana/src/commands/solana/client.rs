@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use solana_client::client_error::Result as ClientResult;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use url::Url;
+
+use crate::error::Error;
+use crate::limits::Limiter;
+use crate::metrics::MetricsExporter;
+
+// rpc client of a solana context. requests are only sent through `call`, which counts
+// them for the metrics and spaces them by the rate limit of the rpc url. the client is
+// blocking, so requests run on the blocking thread pool instead of stalling the runtime
+pub struct Client {
+    rpc: Arc<RpcClient>,
+    url: String,
+    limiter: Arc<Limiter>,
+    metrics: Arc<dyn MetricsExporter>,
+}
+
+impl Client {
+    pub(crate) fn new(
+        url: Url,
+        limiter: Arc<Limiter>,
+        metrics: Arc<dyn MetricsExporter>,
+    ) -> Client {
+        Client {
+            rpc: Arc::new(RpcClient::new(url.to_string())),
+            url: url.to_string(),
+            limiter,
+            metrics,
+        }
+    }
+
+    // sends the request of the rpc `method`, `request` sends exactly one
+    pub(crate) async fn call<T: Send + 'static>(
+        &self,
+        method: &str,
+        request: impl FnOnce(&RpcClient) -> ClientResult<T> + Send + 'static,
+    ) -> Result<T, Error> {
+        tracing::trace!(method, "rpc call");
+        self.metrics.rpc_call(method);
+
+        self.limiter.throttle(&self.url).await;
+
+        let rpc = self.rpc.clone();
+
+        let res = tokio::task::spawn_blocking(move || request(&rpc))
+            .await
+            .map_err(|e| Error::RpcTaskFailed(e.to_string()))?;
+
+        Ok(res?)
+    }
+
+    pub async fn get_latest_blockhash(&self) -> Result<Hash, Error> {
+        self.call("getLatestBlockhash", |rpc| rpc.get_latest_blockhash())
+            .await
+    }
+
+    pub async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, Error> {
+        let pubkey = *pubkey;
+
+        self.call("getBalance", move |rpc| rpc.get_balance(&pubkey))
+            .await
+    }
+
+    pub async fn get_fee_for_message(&self, message: &Message) -> Result<u64, Error> {
+        let message = message.clone();
+
+        self.call("getFeeForMessage", move |rpc| {
+            rpc.get_fee_for_message(&message)
+        })
+        .await
+    }
+
+    pub async fn get_minimum_balance_for_rent_exemption(
+        &self,
+        data_len: usize,
+    ) -> Result<u64, Error> {
+        self.call("getMinimumBalanceForRentExemption", move |rpc| {
+            rpc.get_minimum_balance_for_rent_exemption(data_len)
+        })
+        .await
+    }
+
+    pub async fn get_account_data(&self, pubkey: &Pubkey) -> Result<Vec<u8>, Error> {
+        let pubkey = *pubkey;
+
+        self.call("getAccountInfo", move |rpc| rpc.get_account_data(&pubkey))
+            .await
+    }
+
+    pub async fn get_slot(&self) -> Result<u64, Error> {
+        self.call("getSlot", |rpc| rpc.get_slot()).await
+    }
+
+    pub async fn send_and_confirm_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Signature, Error> {
+        let transaction = transaction.clone();
+
+        self.call("sendTransaction", move |rpc| {
+            rpc.send_and_confirm_transaction(&transaction)
+        })
+        .await
+    }
+
+    pub async fn request_airdrop(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+    ) -> Result<Signature, Error> {
+        let pubkey = *pubkey;
+
+        self.call("requestAirdrop", move |rpc| {
+            rpc.request_airdrop(&pubkey, lamports)
+        })
+        .await
+    }
+
+    pub async fn confirm_transaction(&self, signature: &Signature) -> Result<bool, Error> {
+        let signature = *signature;
+
+        self.call("getSignatureStatuses", move |rpc| {
+            rpc.confirm_transaction(&signature)
+        })
+        .await
+    }
+}
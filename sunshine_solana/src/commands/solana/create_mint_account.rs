@@ -2,14 +2,14 @@ use std::{collections::HashMap, sync::Arc};
 
 use maplit::hashmap;
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{program_pack::Pack, pubkey::Pubkey, signer::Signer, system_instruction};
 use spl_token::state::Mint;
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{error::Error, CommandResult, Value};
 
-use super::{instructions::execute, Ctx};
+use super::{client::Client, instructions::execute, Ctx};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CreateMintAccount {
@@ -24,52 +24,22 @@ impl CreateMintAccount {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let decimals = match self.decimals {
-            Some(s) => s,
-            None => match inputs.remove("decimals") {
-                Some(Value::U8(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("decimals".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let authority = match self.authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("authority".to_string())),
-            },
-        };
+        let decimals = args.required(self.decimals, "decimals")?;
 
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let authority = args.keypair(&ctx, self.authority, "authority").await?;
 
-        let memo = match &self.memo {
-            Some(s) => s.clone(),
-            None => match inputs.remove("memo") {
-                Some(Value::String(s)) => s,
-                Some(Value::Empty) => String::new(),
-                None => String::new(),
-                _ => return Err(Error::ArgumentNotFound("memo".to_string())),
-            },
-        };
+        let mint_account = args
+            .keypair(&ctx, self.mint_account, "mint_account")
+            .await?;
+
+        let memo = args.or_default(self.memo.clone(), "memo", String::new())?;
 
         let (minimum_balance_for_rent_exemption, instructions) = command_create_mint_account(
             &ctx.client,
@@ -78,19 +48,22 @@ impl CreateMintAccount {
             &mint_account.pubkey(),
             authority.pubkey(),
             &memo,
-        )?;
+        )
+        .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&authority, &fee_payer, &mint_account];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -105,16 +78,17 @@ impl CreateMintAccount {
     }
 }
 
-pub fn command_create_mint_account(
-    rpc_client: &RpcClient,
+pub async fn command_create_mint_account(
+    rpc_client: &Client,
     fee_payer: &Pubkey,
     decimals: u8,
     mint_account: &Pubkey,
     authority: Pubkey,
     memo: &str,
 ) -> CommandResult {
-    let minimum_balance_for_rent_exemption =
-        rpc_client.get_minimum_balance_for_rent_exemption(Mint::LEN)?;
+    let minimum_balance_for_rent_exemption = rpc_client
+        .get_minimum_balance_for_rent_exemption(Mint::LEN)
+        .await?;
 
     let freeze_authority_pubkey = Some(authority);
 
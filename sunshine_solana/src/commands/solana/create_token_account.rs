@@ -2,17 +2,17 @@ use std::{collections::HashMap, sync::Arc};
 
 use maplit::hashmap;
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     program_pack::Pack, pubkey::Pubkey, signer::Signer, system_instruction, system_program,
 };
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::CommandResult;
 
 use crate::{error::Error, Value};
 
-use super::{instructions::execute, Ctx};
+use super::{client::Client, instructions::execute, Ctx};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CreateTokenAccount {
@@ -26,50 +26,20 @@ impl CreateTokenAccount {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let owner = match self.owner {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("owner") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("owner".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let owner = args.pubkey(&ctx, self.owner, "owner").await?;
 
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let token_account = match self.token_account {
-            Some(s) => match s {
-                Some(token_account) => Some(ctx.get_keypair_by_id(token_account).await?),
-                None => None,
-            },
-            None => match inputs.remove("token_account") {
-                Some(Value::NodeIdOpt(s)) => match s {
-                    Some(token_account) => Some(ctx.get_keypair_by_id(token_account).await?),
-                    None => None,
-                },
-                Some(Value::Keypair(k)) => Some(k.into()),
-                Some(Value::Empty) => None,
-                _ => None,
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
+
+        let token_account = args
+            .optional_keypair(&ctx, self.token_account, "token_account")
+            .await?;
 
         let (minimum_balance_for_rent_exemption, instructions) = command_create_token_account(
             &ctx.client,
@@ -78,6 +48,7 @@ impl CreateTokenAccount {
             owner,
             token_account.as_ref().map(|acc| acc.pubkey()),
         )
+        .await
         .unwrap();
 
         let fee_payer_pubkey = fee_payer.pubkey();
@@ -89,12 +60,14 @@ impl CreateTokenAccount {
         };
 
         let signature = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        )?;
+        )
+        .await?;
 
         let mut outputs = hashmap! {
             "signature".to_owned() => Value::Success(signature),
@@ -125,8 +98,8 @@ impl CreateTokenAccount {
     }
 }
 
-pub fn command_create_token_account(
-    client: &RpcClient,
+pub async fn command_create_token_account(
+    client: &Client,
     fee_payer: Pubkey,
     token: Pubkey,
     owner: Pubkey,
@@ -134,6 +107,7 @@ pub fn command_create_token_account(
 ) -> CommandResult {
     let minimum_balance_for_rent_exemption = client
         .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+        .await
         .unwrap();
 
     let (account, system_account_ok, instructions) = if let Some(account) = maybe_account {
@@ -171,7 +145,10 @@ pub fn command_create_token_account(
     };
 
     if let Some(account_data) = client
-        .get_account_with_commitment(&account, client.commitment())
+        .call("getAccountInfo", move |rpc| {
+            rpc.get_account_with_commitment(&account, rpc.commitment())
+        })
+        .await
         .unwrap()
         .value
     {
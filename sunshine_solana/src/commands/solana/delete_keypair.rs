@@ -5,6 +5,7 @@ use maplit::hashmap;
 use serde::{Deserialize, Serialize};
 use sunshine_core::msg::NodeId;
 
+use crate::commands::util::Args;
 use crate::{error::Error, Value};
 
 use super::Ctx;
@@ -18,17 +19,13 @@ impl DeleteKeypair {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
         match &self.input {
             Either::Left(keypair) => {
-                let keypair = match keypair {
-                    Some(s) => s.clone(),
-                    None => match inputs.remove("keypair") {
-                        Some(Value::String(s)) => s,
-                        _ => return Err(Error::ArgumentNotFound("keypair".to_string())),
-                    },
-                };
+                let keypair: String = args.required(keypair.clone(), "keypair")?;
 
                 let node_id = ctx.get_node_id_by_keypair(keypair.as_str()).await?;
                 let keypair = ctx.remove_keypair(node_id).await?;
@@ -38,13 +35,7 @@ impl DeleteKeypair {
                 })
             }
             Either::Right(node_id) => {
-                let node_id = match node_id {
-                    Some(id) => *id,
-                    None => match inputs.remove("node_id") {
-                        Some(Value::NodeId(id)) => id,
-                        _ => return Err(Error::ArgumentNotFound("node_id".to_string())),
-                    },
-                };
+                let node_id = args.required(*node_id, "node_id")?;
                 let keypair = ctx.remove_keypair(node_id).await?;
 
                 Ok(hashmap! {
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 use sunshine_core::msg::NodeId;
 
+use crate::commands::util::Args;
 use crate::{error::Error, Value};
 
 use super::Ctx;
@@ -19,18 +20,14 @@ impl DeletePubkey {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
         match &self.input {
             Either::Left(pubkey) => {
-                let pubkey = match pubkey {
-                    Some(s) => s.clone(),
-                    None => match inputs.remove("pubkey") {
-                        Some(Value::String(s)) => s,
-                        _ => return Err(Error::ArgumentNotFound("pubkey".to_string())),
-                    },
-                };
-                let pubkey = Pubkey::from_str(&pubkey).unwrap();
+                let pubkey: String = args.required(pubkey.clone(), "pubkey")?;
+                let pubkey = Pubkey::from_str(&pubkey)?;
 
                 let node_id = ctx.get_node_id_by_pubkey(pubkey).await?;
                 let pubkey = ctx.remove_pubkey(node_id).await?;
@@ -40,13 +37,7 @@ impl DeletePubkey {
                 })
             }
             Either::Right(node_id) => {
-                let node_id = match node_id {
-                    Some(id) => *id,
-                    None => match inputs.remove("node_id") {
-                        Some(Value::NodeId(id)) => id,
-                        _ => return Err(Error::ArgumentNotFound("node_id".to_string())),
-                    },
-                };
+                let node_id = args.required(*node_id, "node_id")?;
                 let pubkey = ctx.remove_pubkey(node_id).await?;
 
                 Ok(hashmap! {
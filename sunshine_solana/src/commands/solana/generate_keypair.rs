@@ -1,5 +1,6 @@
 use std::{collections::HashMap, sync::Arc};
 
+use crate::commands::util::Args;
 use crate::{error::Error, Value};
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use maplit::hashmap;
@@ -26,51 +27,32 @@ pub enum Arg {
     None,
 }
 
+impl From<Arg> for Option<Option<String>> {
+    fn from(arg: Arg) -> Self {
+        match arg {
+            Arg::Some(v) => Some(v),
+            Arg::None => None,
+        }
+    }
+}
+
 impl GenerateKeypair {
     pub async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
-        let seed_phrase: Option<String> = match self.seed_phrase.clone() {
-            Arg::Some(val) => val,
-            Arg::None => match inputs.remove("seed_phrase") {
-                Some(Value::String(s)) => Some(s),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("seed_phrase".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let private_key: Option<String> = match self.private_key.clone() {
-            Arg::Some(val) => val,
-            Arg::None => match inputs.remove("private_key") {
-                Some(Value::String(s)) => Some(s),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("private_key".to_string())),
-            },
-        };
+        let seed_phrase: Option<String> =
+            args.optional(self.seed_phrase.clone().into(), "seed_phrase")?;
 
-        let passphrase = match &self.passphrase {
-            Some(s) => s.clone(),
-            None => match inputs.remove("passphrase") {
-                Some(Value::String(s)) => s,
-                Some(Value::Empty) => String::new(),
-                None => String::new(),
-                _ => return Err(Error::ArgumentNotFound("passphrase".to_string())),
-            },
-        };
+        let private_key: Option<String> =
+            args.optional(self.private_key.clone().into(), "private_key")?;
 
-        let save: Option<String> = match self.save.clone() {
-            Arg::Some(val) => val,
-            Arg::None => match inputs.remove("save") {
-                Some(Value::String(s)) => Some(s),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("save".to_string())),
-            },
-        };
+        let passphrase = args.or_default(self.passphrase.clone(), "passphrase", String::new())?;
+
+        let save: Option<String> = args.optional(self.save.clone().into(), "save")?;
 
         let keypair = if let Some(base58_str) = private_key {
             Keypair::from_base58_string(&base58_str)
@@ -1,4 +1,4 @@
-use solana_client::rpc_client::RpcClient;
+use serde::{Deserialize, Serialize};
 use solana_sdk::instruction::Instruction;
 use solana_sdk::message::Message;
 use solana_sdk::pubkey::Pubkey;
@@ -6,12 +6,26 @@ use solana_sdk::signature::Signature;
 use solana_sdk::signer::Signer;
 use solana_sdk::transaction::Transaction;
 
+use crate::commands::solana::client::Client;
+use crate::commands::Env;
 use crate::error::Error;
 
+// what a transaction would have done, recorded in the log node of a command in a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Simulation {
+    pub fee: u64,
+    pub minimum_balance_for_rent_exemption: u64,
+    pub fee_payer_balance: u64,
+    pub units_consumed: u64,
+    pub logs: Vec<String>,
+    pub error: Option<String>,
+}
+
 #[allow(clippy::ptr_arg)]
-pub(crate) fn execute(
+pub(crate) async fn execute(
+    env: &Env,
     signers: &Vec<&dyn Signer>,
-    client: &RpcClient,
+    client: &Client,
     fee_payer: &Pubkey,
     instructions: &[Instruction],
     minimum_balance_for_rent_exemption: u64,
@@ -27,23 +41,107 @@ pub(crate) fn execute(
         Message::new(&instructions, fee_payer)
     };*/
 
-    let recent_blockhash = client.get_latest_blockhash()?;
+    let recent_blockhash = client.get_latest_blockhash().await?;
 
     let message = Message::new_with_blockhash(instructions, Some(fee_payer), &recent_blockhash);
 
-    let balance = client.get_balance(fee_payer)?;
+    // a dry run reports the balance instead of failing, so that unfunded accounts can be used
+    if !env.dry_run {
+        let balance = client.get_balance(fee_payer).await?;
 
-    let needed = minimum_balance_for_rent_exemption + client.get_fee_for_message(&message)?;
+        let needed =
+            minimum_balance_for_rent_exemption + client.get_fee_for_message(&message).await?;
 
-    if balance < needed {
-        return Err(Error::InsufficientSolanaBalance { balance, needed });
+        if balance < needed {
+            return Err(Error::InsufficientSolanaBalance { balance, needed });
+        }
     }
 
     let mut transaction = Transaction::new_unsigned(message);
 
     transaction.try_sign(signers, recent_blockhash)?;
 
-    let signature = client.send_and_confirm_transaction(&transaction)?;
+    send(
+        env,
+        client,
+        &transaction,
+        minimum_balance_for_rent_exemption,
+    )
+    .await
+}
+
+// sends the transaction, in a dry run it's only simulated and a placeholder signature
+// is returned so that the nodes downstream still run
+pub(crate) async fn send(
+    env: &Env,
+    client: &Client,
+    transaction: &Transaction,
+    minimum_balance_for_rent_exemption: u64,
+) -> Result<Signature, Error> {
+    let fee = client.get_fee_for_message(&transaction.message).await?;
+
+    if !env.dry_run {
+        let signature = client.send_and_confirm_transaction(transaction).await?;
+
+        env.engine
+            .metrics
+            .lamports_spent(env.flow_id, fee + minimum_balance_for_rent_exemption);
+
+        return Ok(signature);
+    }
+
+    let fee_payer_balance = match transaction.message.account_keys.first() {
+        Some(fee_payer) => client.get_balance(fee_payer).await?,
+        None => 0,
+    };
+
+    let simulated = transaction.clone();
+
+    let result = client
+        .call("simulateTransaction", move |rpc| {
+            rpc.simulate_transaction(&simulated)
+        })
+        .await?
+        .value;
+
+    let logs = result.logs.unwrap_or_default();
+
+    let error = result.err.map(|e| e.to_string());
+
+    env.simulations.lock().unwrap().push(Simulation {
+        fee,
+        minimum_balance_for_rent_exemption,
+        fee_payer_balance,
+        units_consumed: units_consumed(&logs),
+        logs,
+        error: error.clone(),
+    });
+
+    match error {
+        Some(error) => Err(Error::SimulationFailed(error)),
+        None => Ok(Signature::default()),
+    }
+}
+
+// sums the compute units of the top level instructions from lines like
+// "Program <id> consumed 2370 of 200000 compute units", inner invocations are
+// already included in the units of the instruction invoking them
+fn units_consumed(logs: &[String]) -> u64 {
+    let mut depth = 0;
+    let mut units = 0;
+
+    for line in logs {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["Program", _, "invoke", _] => depth += 1,
+            ["Program", _, "success"] | ["Program", _, "failed:", ..] => depth -= 1,
+            ["Program", _, "consumed", consumed, "of", _, "compute", "units"] if depth == 1 => {
+                units += consumed.parse::<u64>().unwrap_or(0);
+            }
+            _ => (),
+        }
+    }
 
-    Ok(signature)
+    units
 }
@@ -2,14 +2,14 @@ use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use maplit::hashmap;
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signer::Signer};
 use spl_token::instruction::mint_to_checked;
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{error::Error, CommandResult, Value};
 
-use super::{instructions::execute, Ctx};
+use super::{client::Client, instructions::execute, Ctx};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MintToken {
@@ -24,51 +24,22 @@ impl MintToken {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let recipient = match self.recipient {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("recipient") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("recipient".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let mint_authority = match self.mint_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("mint_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("mint_authority".to_string())),
-            },
-        };
+        let recipient = args.pubkey(&ctx, self.recipient, "recipient").await?;
 
-        let amount = match self.amount {
-            Some(s) => s,
-            None => match inputs.remove("amount") {
-                Some(Value::F64(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("amount".to_string())),
-            },
-        };
+        let mint_authority = args
+            .keypair(&ctx, self.mint_authority, "mint_authority")
+            .await?;
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let amount = args.required(self.amount, "amount")?;
+
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
@@ -79,17 +50,20 @@ impl MintToken {
             amount,
             recipient,
             mint_authority.pubkey(),
-        )?;
+        )
+        .await?;
 
         let signers: Vec<&dyn Signer> = vec![&mint_authority, &fee_payer];
 
         let signature = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        )?;
+        )
+        .await?;
 
         let outputs = hashmap! {
             "signature".to_owned() => Value::Success(signature),
@@ -104,27 +78,30 @@ impl MintToken {
 
 // checks mint account's decimals
 // https://github.com/solana-labs/solana-program-library/blob/707382ee96c1197b50ab3e837b3c46b975e75a4f/token/cli/src/main.rs#L516
-pub(crate) fn resolve_mint_info(
-    client: &RpcClient,
+pub(crate) async fn resolve_mint_info(
+    client: &Client,
     token_account: &Pubkey,
 ) -> Result<(Pubkey, u8), Error> {
+    let pubkey = *token_account;
+
     let source_account = client
-        .get_token_account(token_account)
+        .call("getAccountInfo", move |rpc| rpc.get_token_account(&pubkey))
+        .await
         .map_err(|_| Error::NotTokenAccount(token_account.to_string()))?
         .ok_or_else(|| Error::NotTokenAccount(token_account.to_string()))?;
     let source_mint = Pubkey::from_str(&source_account.mint).unwrap();
     Ok((source_mint, source_account.token_amount.decimals))
 }
 
-pub fn command_mint(
-    client: &RpcClient,
+pub async fn command_mint(
+    client: &Client,
     mint_account: Pubkey,
     fee_payer: Pubkey,
     ui_amount: f64,
     recipient: Pubkey,
     mint_authority: Pubkey,
 ) -> CommandResult {
-    let (_, decimals) = resolve_mint_info(client, &recipient)?;
+    let (_, decimals) = resolve_mint_info(client, &recipient).await?;
     let amount = spl_token::ui_amount_to_amount(ui_amount, decimals);
 
     let instructions = vec![mint_to_checked(
@@ -1,17 +1,20 @@
 use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
 use sunshine_core::msg::{Graph, GraphId, Properties};
 use sunshine_core::store::Datastore;
 use url::Url;
 
+use crate::commands::Env;
+use crate::limits::{Limiter, Limits};
+use crate::metrics::{MetricsExporter, NoopMetrics};
 use crate::{error::Error, Value};
 
 use sunshine_core::msg::NodeId;
 
-mod instructions;
+pub(crate) mod client;
+pub(crate) mod instructions;
 
 pub mod add_pubkey;
 pub mod create_mint_account;
@@ -25,6 +28,7 @@ pub mod nft;
 pub mod request_airdrop;
 pub mod transfer_solana;
 pub mod transfer_token;
+pub mod wait_for_slot;
 
 const KEYPAIR_MARKER: &str = "KEYPAIR_MARKER";
 const NAME_MARKER: &str = "NAME_MARKER";
@@ -34,15 +38,27 @@ const PUBKEY_MARKER: &str = "PUBKEY_MARKER";
 pub struct Config {
     pub solana_net: SolanaNet,
     pub wallet_graph: GraphId,
+    // flow variable which overrides solana_net, lets a deployment pick e.g. devnet
+    #[serde(default)]
+    pub solana_net_variable: Option<String>,
 }
 
 pub struct Ctx {
-    client: RpcClient,
+    client: client::Client,
     db: Arc<dyn Datastore>,
     wallet_graph: GraphId,
     solana_net: SolanaNet,
 }
 
+// a named key stored in the wallet graph
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WalletEntry {
+    pub node_id: NodeId,
+    pub name: String,
+    pub pubkey: Pubkey,
+    pub has_keypair: bool,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub enum SolanaNet {
     Devnet,
@@ -60,18 +76,48 @@ impl SolanaNet {
 
         Url::parse(solana_url).unwrap()
     }
+
+    pub fn ws_url(&self) -> Url {
+        let solana_url = match self {
+            SolanaNet::Devnet => "wss://api.devnet.solana.com",
+            SolanaNet::Testnet => "wss://api.testnet.solana.com",
+            SolanaNet::Mainnet => "wss://api.mainnet-beta.solana.com",
+        };
+
+        Url::parse(solana_url).unwrap()
+    }
 }
 
 impl Ctx {
+    // the rpc isn't limited and its requests aren't counted, see with_limits
     pub fn new(cfg: Config, db: Arc<dyn Datastore>) -> Result<Ctx, Error> {
+        Ctx::with_limits(
+            cfg,
+            db,
+            Arc::new(Limiter::new(&Limits::default())),
+            Arc::new(NoopMetrics),
+        )
+    }
+
+    // requests to the rpc are limited and counted by the flow context
+    pub(crate) fn with_limits(
+        cfg: Config,
+        db: Arc<dyn Datastore>,
+        limiter: Arc<Limiter>,
+        metrics: Arc<dyn MetricsExporter>,
+    ) -> Result<Ctx, Error> {
         Ok(Ctx {
-            client: RpcClient::new(cfg.solana_net.url()),
+            client: client::Client::new(cfg.solana_net.url(), limiter, metrics),
             wallet_graph: cfg.wallet_graph,
             db,
             solana_net: cfg.solana_net,
         })
     }
 
+    pub fn rpc_url(&self) -> Url {
+        self.solana_net.url()
+    }
+
     fn check_name(graph: &Graph, name: &str) -> Result<(), Error> {
         let has_name = graph.nodes.iter().any(|node| {
             if let Some(node_name) = node.properties.get(NAME_MARKER) {
@@ -88,7 +134,7 @@ impl Ctx {
         }
     }
 
-    async fn insert_keypair(&self, name: String, keypair: &Keypair) -> Result<NodeId, Error> {
+    pub async fn insert_keypair(&self, name: String, keypair: &Keypair) -> Result<NodeId, Error> {
         let graph = self.db.read_graph(self.wallet_graph).await?;
 
         Self::check_name(&graph, &name)?;
@@ -103,7 +149,7 @@ impl Ctx {
         Ok(node_id)
     }
 
-    async fn remove_keypair(&self, node_id: NodeId) -> Result<Keypair, Error> {
+    pub async fn remove_keypair(&self, node_id: NodeId) -> Result<Keypair, Error> {
         let keypair = self.get_keypair_by_id(node_id).await?;
 
         self.db.delete_node(node_id, self.wallet_graph).await?;
@@ -111,7 +157,33 @@ impl Ctx {
         Ok(keypair)
     }
 
-    async fn get_keypair_by_id(&self, node_id: NodeId) -> Result<Keypair, Error> {
+    pub async fn list_wallet(&self) -> Result<Vec<WalletEntry>, Error> {
+        let graph = self.db.read_graph(self.wallet_graph).await?;
+
+        let mut entries = Vec::new();
+
+        for node in graph.nodes.iter() {
+            let name = match node.properties.get(NAME_MARKER).and_then(|n| n.as_str()) {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+
+            let has_keypair = node.properties.contains_key(KEYPAIR_MARKER);
+
+            entries.push(WalletEntry {
+                node_id: node.node_id,
+                name,
+                pubkey: self.get_pubkey_by_id(node.node_id).await?,
+                has_keypair,
+            });
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(entries)
+    }
+
+    pub(crate) async fn get_keypair_by_id(&self, node_id: NodeId) -> Result<Keypair, Error> {
         let node = self.db.read_node(node_id).await?;
 
         let keypair = node
@@ -144,7 +216,7 @@ impl Ctx {
         Ok(node_id)
     }
 
-    async fn insert_pubkey(&self, name: String, pubkey: Pubkey) -> Result<NodeId, Error> {
+    pub async fn insert_pubkey(&self, name: String, pubkey: Pubkey) -> Result<NodeId, Error> {
         let graph = self.db.read_graph(self.wallet_graph).await?;
 
         Self::check_name(&graph, &name)?;
@@ -159,7 +231,7 @@ impl Ctx {
         Ok(node_id)
     }
 
-    async fn remove_pubkey(&self, node_id: NodeId) -> Result<Pubkey, Error> {
+    pub async fn remove_pubkey(&self, node_id: NodeId) -> Result<Pubkey, Error> {
         let pubkey = self.get_pubkey_by_id(node_id).await?;
 
         self.db.delete_node(node_id, self.wallet_graph).await?;
@@ -191,7 +263,7 @@ impl Ctx {
         Ok(node_id)
     }
 
-    async fn get_pubkey_by_id(&self, node_id: NodeId) -> Result<Pubkey, Error> {
+    pub(crate) async fn get_pubkey_by_id(&self, node_id: NodeId) -> Result<Pubkey, Error> {
         match self.get_keypair_by_id(node_id).await {
             Ok(keypair) => return Ok(keypair.pubkey()),
             Err(Error::KeypairDoesntExist) => (),
@@ -240,6 +312,7 @@ pub enum Kind {
     TransferToken(transfer_token::TransferToken),
     Nft(nft::Command),
     TransferSolana(transfer_solana::TransferSolana),
+    WaitForSlot(wait_for_slot::WaitForSlot),
 }
 
 impl Kind {
@@ -257,6 +330,7 @@ impl Kind {
             Kind::TransferToken(_) => CommandKind::TransferToken,
             Kind::Nft(n) => CommandKind::Nft(n.kind()),
             Kind::TransferSolana(_) => CommandKind::TransferSolana,
+            Kind::WaitForSlot(_) => CommandKind::WaitForSlot,
         }
     }
 }
@@ -275,26 +349,29 @@ pub enum CommandKind {
     TransferToken,
     Nft(nft::CommandKind),
     TransferSolana,
+    WaitForSlot,
 }
 
 impl Command {
     pub(crate) async fn run(
         &self,
         inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
         match &self.kind {
             Kind::GenerateKeypair(k) => k.run(self.ctx.clone(), inputs).await,
             Kind::DeleteKeypair(k) => k.run(self.ctx.clone(), inputs).await,
             Kind::AddPubkey(k) => k.run(self.ctx.clone(), inputs).await,
             Kind::DeletePubkey(k) => k.run(self.ctx.clone(), inputs).await,
-            Kind::CreateTokenAccount(k) => k.run(self.ctx.clone(), inputs).await,
+            Kind::CreateTokenAccount(k) => k.run(self.ctx.clone(), inputs, env).await,
             Kind::GetBalance(k) => k.run(self.ctx.clone(), inputs).await,
-            Kind::CreateMintAccount(k) => k.run(self.ctx.clone(), inputs).await,
-            Kind::RequestAirdrop(k) => k.run(self.ctx.clone(), inputs).await,
-            Kind::MintToken(k) => k.run(self.ctx.clone(), inputs).await,
-            Kind::TransferToken(k) => k.run(self.ctx.clone(), inputs).await,
-            Kind::Nft(k) => k.run(self.ctx.clone(), inputs).await,
-            Kind::TransferSolana(k) => k.run(self.ctx.clone(), inputs).await,
+            Kind::CreateMintAccount(k) => k.run(self.ctx.clone(), inputs, env).await,
+            Kind::RequestAirdrop(k) => k.run(self.ctx.clone(), inputs, env).await,
+            Kind::MintToken(k) => k.run(self.ctx.clone(), inputs, env).await,
+            Kind::TransferToken(k) => k.run(self.ctx.clone(), inputs, env).await,
+            Kind::Nft(k) => k.run(self.ctx.clone(), inputs, env).await,
+            Kind::TransferSolana(k) => k.run(self.ctx.clone(), inputs, env).await,
+            Kind::WaitForSlot(k) => k.run(self.ctx.clone(), inputs, env).await,
         }
     }
 
@@ -312,6 +389,7 @@ impl Command {
             Kind::TransferToken(_) => CommandKind::TransferToken,
             Kind::Nft(n) => CommandKind::Nft(n.kind()),
             Kind::TransferSolana(_) => CommandKind::TransferSolana,
+            Kind::WaitForSlot(_) => CommandKind::WaitForSlot,
         }
     }
 }
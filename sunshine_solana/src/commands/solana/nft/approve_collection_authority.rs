@@ -1,14 +1,15 @@
 use std::{collections::HashMap, sync::Arc};
 
 use super::super::Ctx;
+use crate::commands::solana::client::Client;
 use maplit::hashmap;
 use mpl_token_metadata::state::{Collection, Creator, UseMethod, Uses};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair, signer::Signer};
 
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{commands::solana::instructions::execute, CommandResult, Error, NftCreator, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,47 +24,26 @@ impl ApproveCollectionAuthority {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let new_collection_authority = match self.new_collection_authority {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("new_collection_authority") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "new_collection_authority".to_string(),
-                    ))
-                }
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let update_authority = match self.update_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("update_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("update_authority".to_string())),
-            },
-        };
+        let new_collection_authority = args
+            .pubkey(
+                &ctx,
+                self.new_collection_authority,
+                "new_collection_authority",
+            )
+            .await?;
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let update_authority = args
+            .keypair(&ctx, self.update_authority, "update_authority")
+            .await?;
 
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
+
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
         let program_id = mpl_token_metadata::id();
 
@@ -90,19 +70,22 @@ impl ApproveCollectionAuthority {
                 fee_payer.pubkey(),
                 metadata_pubkey,
                 mint_account,
-            )?;
+            )
+            .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&update_authority, &fee_payer];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -117,8 +100,8 @@ impl ApproveCollectionAuthority {
     }
 }
 
-pub fn command_approve_collection_authority(
-    rpc_client: &RpcClient,
+pub async fn command_approve_collection_authority(
+    rpc_client: &Client,
     collection_authority_record: Pubkey,
     new_collection_authority: Pubkey,
     update_authority: Pubkey,
@@ -126,10 +109,11 @@ pub fn command_approve_collection_authority(
     metadata: Pubkey,
     mint: Pubkey,
 ) -> CommandResult {
-    let minimum_balance_for_rent_exemption =
-        rpc_client.get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+    let minimum_balance_for_rent_exemption = rpc_client
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
             mpl_token_metadata::state::CollectionAuthorityRecord,
-        >())?;
+        >())
+        .await?;
 
     let instructions = vec![
         mpl_token_metadata::instruction::approve_collection_authority(
@@ -1,14 +1,15 @@
 use std::{collections::HashMap, sync::Arc};
 
 use super::super::Ctx;
+use crate::commands::solana::client::Client;
 use maplit::hashmap;
 use mpl_token_metadata::state::{Collection, Creator, UseMethod, Uses};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair, signer::Signer};
 
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{commands::solana::instructions::execute, CommandResult, Error, NftCreator, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,78 +27,28 @@ impl ApproveUseAuthority {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let use_authority = match self.use_authority {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("use_authority") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("use_authority".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let owner = match self.owner {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("owner") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("owner".to_string())),
-            },
-        };
+        let use_authority = args
+            .pubkey(&ctx, self.use_authority, "use_authority")
+            .await?;
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let owner = args.keypair(&ctx, self.owner, "owner").await?;
 
-        let token_account = match self.token_account {
-            Some(s) => match s {
-                Some(token_account) => Some(ctx.get_pubkey_by_id(token_account).await?),
-                None => None,
-            },
-            None => match inputs.remove("token_account") {
-                Some(Value::NodeIdOpt(s)) => match s {
-                    Some(token_account) => Some(ctx.get_pubkey_by_id(token_account).await?),
-                    None => None,
-                },
-                Some(Value::Keypair(k)) => Some(Keypair::from(k).pubkey()),
-                Some(Value::Pubkey(p)) => Some(p.into()),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("token_account".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let token_account = args
+            .optional_pubkey(&ctx, self.token_account, "token_account")
+            .await?;
 
-        let burner = match self.burner {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("burner") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("burner".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let number_of_uses = match self.number_of_uses {
-            Some(s) => s,
-            None => match inputs.remove("number_of_uses") {
-                Some(Value::U64(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("number_of_uses".to_string())),
-            },
-        };
+        let burner = args.pubkey(&ctx, self.burner, "burner").await?;
+
+        let number_of_uses = args.required(self.number_of_uses, "number_of_uses")?;
 
         let (metadata_account, _) = mpl_token_metadata::pda::find_metadata_account(&mint_account);
 
@@ -122,19 +73,22 @@ impl ApproveUseAuthority {
             mint_account,
             burner,
             number_of_uses,
-        )?;
+        )
+        .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&owner, &fee_payer];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -153,8 +107,8 @@ impl ApproveUseAuthority {
     }
 }
 
-pub fn command_approve_use_authority(
-    rpc_client: &RpcClient,
+pub async fn command_approve_use_authority(
+    rpc_client: &Client,
     use_authority_record_pubkey: Pubkey,
     user: Pubkey,
     owner: Pubkey,
@@ -165,10 +119,11 @@ pub fn command_approve_use_authority(
     burner: Pubkey,
     number_of_uses: u64,
 ) -> CommandResult {
-    let minimum_balance_for_rent_exemption =
-        rpc_client.get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+    let minimum_balance_for_rent_exemption = rpc_client
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
             mpl_token_metadata::state::UseAuthorityRecord,
-        >())?;
+        >())
+        .await?;
 
     let instructions = vec![mpl_token_metadata::instruction::approve_use_authority(
         mpl_token_metadata::id(),
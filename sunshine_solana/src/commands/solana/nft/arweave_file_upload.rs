@@ -17,6 +17,7 @@ use sunshine_core::msg::NodeId;
 
 use crate::commands::solana::instructions::execute;
 use crate::commands::solana::SolanaNet;
+use crate::commands::{util::Args, Env};
 use crate::{Error, NftMetadata, Value};
 
 use solana_sdk::signer::keypair::write_keypair_file;
@@ -36,36 +37,18 @@ impl ArweaveFileUpload {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let file_path = match &self.file_path {
-            Some(s) => s.clone(),
-            None => match inputs.remove("file_path") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("file_path".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let fund_bundlr = match self.fund_bundlr {
-            Some(b) => b,
-            None => match inputs.remove("fund_bundlr") {
-                Some(Value::Bool(b)) => b,
-                Some(Value::Empty) => true,
-                None => true,
-                _ => return Err(Error::ArgumentNotFound("fund_bundlr".to_string())),
-            },
-        };
+        let file_path = args.required(self.file_path.clone(), "file_path")?;
+
+        let fund_bundlr = args.or_default(self.fund_bundlr, "fund_bundlr", true)?;
 
-        let mut uploader = Uploader::new(ctx.solana_net, &fee_payer, ctx.clone())?;
+        let mut uploader = Uploader::new(ctx.solana_net, &fee_payer, ctx.clone(), env.clone())?;
 
         if fund_bundlr {
             uploader.lazy_fund(&file_path).await?;
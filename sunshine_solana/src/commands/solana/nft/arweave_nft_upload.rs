@@ -16,6 +16,7 @@ use sunshine_core::msg::NodeId;
 
 use crate::commands::solana::instructions::execute;
 use crate::commands::solana::SolanaNet;
+use crate::commands::{util::Args, Env};
 use crate::{Error, NftMetadata, Value};
 
 use solana_sdk::signer::keypair::write_keypair_file;
@@ -35,36 +36,18 @@ impl ArweaveNftUpload {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let mut metadata = match &self.metadata {
-            Some(s) => s.clone(),
-            None => match inputs.remove("metadata") {
-                Some(Value::NftMetadata(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("metadata".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let fund_bundlr = match self.fund_bundlr {
-            Some(b) => b,
-            None => match inputs.remove("fund_bundlr") {
-                Some(Value::Bool(b)) => b,
-                Some(Value::Empty) => true,
-                None => true,
-                _ => return Err(Error::ArgumentNotFound("fund_bundlr".to_string())),
-            },
-        };
+        let mut metadata: NftMetadata = args.required(self.metadata.clone(), "metadata")?;
 
-        let mut uploader = Uploader::new(ctx.solana_net, &fee_payer, ctx.clone())?;
+        let fund_bundlr = args.or_default(self.fund_bundlr, "fund_bundlr", true)?;
+
+        let mut uploader = Uploader::new(ctx.solana_net, &fee_payer, ctx.clone(), env.clone())?;
 
         if fund_bundlr {
             uploader.lazy_fund_metadata(&metadata).await?;
@@ -102,6 +85,8 @@ pub struct Uploader {
     fee_payer: String,
     node_url: String,
     ctx: Arc<Ctx>,
+    // requests to bundlr are limited like any other http request
+    env: Env,
 }
 
 impl Uploader {
@@ -109,6 +94,7 @@ impl Uploader {
         solana_net: SolanaNet,
         fee_payer: &Keypair,
         ctx: Arc<Ctx>,
+        env: Env,
     ) -> Result<Uploader, Error> {
         let node_url = match solana_net {
             SolanaNet::Mainnet => "https://node1.bundlr.network".to_owned(),
@@ -121,6 +107,7 @@ impl Uploader {
             fee_payer: fee_payer.to_base58_string(),
             node_url,
             ctx,
+            env,
         })
     }
 
@@ -187,6 +174,8 @@ impl Uploader {
     }
 
     async fn get_price(&self, size: u64) -> Result<u64, Error> {
+        let _permit = self.env.http_permit(&self.node_url).await;
+
         let resp = reqwest::get(format!("{}/price/solana/{}", &self.node_url, size,)).await?;
 
         Ok(u64::from_str(&resp.text().await?).map_err(|_| Error::BundlrApiInvalidResponse)?)
@@ -200,6 +189,8 @@ impl Uploader {
 
         let keypair = Keypair::from_base58_string(&self.fee_payer);
 
+        let _permit = self.env.http_permit(&self.node_url).await;
+
         let resp = reqwest::get(format!(
             "{}/account/balance/solana/?address={}",
             &self.node_url,
@@ -223,7 +214,11 @@ impl Uploader {
             addresses: Addresses,
         }
 
-        let resp = reqwest::get(format!("{}/info", &self.node_url)).await?;
+        let resp = {
+            let _permit = self.env.http_permit(&self.node_url).await;
+
+            reqwest::get(format!("{}/info", &self.node_url)).await?
+        };
 
         let info: Info = serde_json::from_str(&resp.text().await?)?;
 
@@ -231,7 +226,7 @@ impl Uploader {
 
         let fee_payer = Keypair::from_base58_string(&self.fee_payer);
 
-        let recent_blockhash = self.ctx.client.get_latest_blockhash()?;
+        let recent_blockhash = self.ctx.client.get_latest_blockhash().await?;
 
         let tx = solana_sdk::system_transaction::transfer(
             &fee_payer,
@@ -240,7 +235,9 @@ impl Uploader {
             recent_blockhash,
         );
 
-        let signature = self.ctx.client.send_and_confirm_transaction(&tx)?;
+        let signature = self.ctx.client.send_and_confirm_transaction(&tx).await?;
+
+        let _permit = self.env.http_permit(&self.node_url).await;
 
         let resp = reqwest::Client::new()
             .post(format!("{}/account/balance/solana", &self.node_url))
@@ -288,6 +285,8 @@ impl Uploader {
             vec![Tag::new("Content-Type".into(), content_type)],
         );
 
+        let _permit = self.env.http_permit(&self.node_url).await;
+
         let resp: BundlrResponse = serde_json::from_value(bundlr.send_transaction(tx).await?)?;
 
         Ok(format!("https://arweave.net/{}", resp.id))
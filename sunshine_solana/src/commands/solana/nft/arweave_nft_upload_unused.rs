@@ -18,6 +18,7 @@ use arloader::{commands::command_upload_nfts, status::StatusCode};
 use sunshine_core::msg::NodeId;
 
 use crate::commands::solana::SolanaNet;
+use crate::commands::util::Args;
 use crate::{Error, NftMetadata, Value};
 
 use solana_sdk::signer::keypair::write_keypair_file;
@@ -37,49 +38,19 @@ impl ArweaveNftUpload {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let reward_mult = match self.reward_mult {
-            Some(s) => s,
-            None => match inputs.remove("reward_mult") {
-                Some(Value::F32(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("reward_mult".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let arweave_key_path = match &self.arweave_key_path {
-            Some(s) => s.clone(),
-            None => match inputs.remove("arweave_key_path") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("arweave_key_path".to_string())),
-            },
-        };
+        let reward_mult = args.required(self.reward_mult, "reward_mult")?;
 
-        let mut metadata = match &self.metadata {
-            Some(s) => s.clone(),
-            None => match inputs.remove("metadata") {
-                Some(Value::NftMetadata(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("metadata".to_string())),
-            },
-        };
+        let arweave_key_path = args.required(self.arweave_key_path.clone(), "arweave_key_path")?;
 
-        let pay_with_solana = match self.pay_with_solana {
-            Some(b) => b,
-            None => match inputs.remove("pay_with_solana") {
-                Some(Value::Bool(b)) => b,
-                Some(Value::Empty) => false,
-                _ => return Err(Error::ArgumentNotFound("pay_with_solana".to_string())),
-            },
-        };
+        let mut metadata: NftMetadata = args.required(self.metadata.clone(), "metadata")?;
+
+        let pay_with_solana = args.or_default(self.pay_with_solana, "pay_with_solana", false)?;
 
         let mut tmpfile = tempfile::NamedTempFile::new().unwrap();
 
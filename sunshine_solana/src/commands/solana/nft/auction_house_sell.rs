@@ -3,12 +3,12 @@ use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
 
 use super::super::Ctx;
+use crate::commands::solana::client::Client;
 use anchor_lang::{InstructionData, ToAccountMetas};
 use dashmap::DashMap;
 use maplit::hashmap;
 use mpl_token_metadata::state::{Collection, Creator, UseMethod, Uses};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::instruction::Instruction;
 use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair, signer::Signer};
 use spl_token::instruction::transfer_checked;
@@ -18,6 +18,7 @@ use sunshine_core::msg::NodeId;
 
 use crate::commands::solana::instructions::execute;
 use crate::commands::solana::SolanaNet;
+use crate::commands::{util::Args, Env};
 use crate::{Error, NftMetadata, Value};
 
 use solana_sdk::signer::keypair::write_keypair_file;
@@ -41,90 +42,40 @@ impl AuctionHouseSell {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let treasury_mint_account = match self.treasury_mint_account {
-            Some(s) => match s {
-                Some(treasury_mint_account) => {
-                    Some(ctx.get_pubkey_by_id(treasury_mint_account).await?)
-                }
-                None => None,
-            },
-            None => match inputs.remove("treasury_mint_account") {
-                Some(Value::NodeIdOpt(s)) => match s {
-                    Some(treasury_mint_account) => {
-                        Some(ctx.get_pubkey_by_id(treasury_mint_account).await?)
-                    }
-                    None => None,
-                },
-                Some(Value::Keypair(k)) => Some(Keypair::from(k).pubkey()),
-                Some(Value::Pubkey(p)) => Some(p.into()),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("treasury_mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let treasury_mint_account = args
+            .optional_pubkey(&ctx, self.treasury_mint_account, "treasury_mint_account")
+            .await?;
 
-        let auction_house_authority = match self.auction_house_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("auction_house_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "auction_house_authority".to_string(),
-                    ))
-                }
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let seller = match self.seller {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("seller") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("seller".to_string())),
-            },
-        };
+        let auction_house_authority = args
+            .keypair(
+                &ctx,
+                self.auction_house_authority,
+                "auction_house_authority",
+            )
+            .await?;
 
-        let seller_token_account = match self.seller_token_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("seller_token_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("seller_token_account".to_string())),
-            },
-        };
+        let seller = args.keypair(&ctx, self.seller, "seller").await?;
 
-        let seller_token_mint_account = match self.seller_token_mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("seller_token_mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "seller_token_mint_account".to_string(),
-                    ))
-                }
-            },
-        };
+        let seller_token_account = args
+            .pubkey(&ctx, self.seller_token_account, "seller_token_account")
+            .await?;
 
-        let sale_price = match self.sale_price {
-            Some(s) => s,
-            None => match inputs.remove("sale_price") {
-                Some(Value::U64(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("sale_price".to_string())),
-            },
-        };
+        let seller_token_mint_account = args
+            .pubkey(
+                &ctx,
+                self.seller_token_mint_account,
+                "seller_token_mint_account",
+            )
+            .await?;
+
+        let sale_price = args.required(self.sale_price, "sale_price")?;
 
         let treasury_mint_account =
             treasury_mint_account.unwrap_or_else(spl_token::native_mint::id);
@@ -137,19 +88,22 @@ impl AuctionHouseSell {
             seller_token_account,
             seller_token_mint_account,
             sale_price,
-        )?;
+        )
+        .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&fee_payer, &seller];
 
         let signature = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        )?;
+        )
+        .await?;
 
         let outputs = hashmap! {
             "signature".to_owned() => Value::Success(signature),
@@ -162,8 +116,8 @@ impl AuctionHouseSell {
     }
 }
 
-pub fn command_auction_house_sell(
-    rpc_client: &RpcClient,
+pub async fn command_auction_house_sell(
+    rpc_client: &Client,
     treasury_mint_account: Pubkey,
     auction_house_authority: Pubkey,
     seller: Pubkey,
@@ -171,9 +125,11 @@ pub fn command_auction_house_sell(
     seller_token_mint_account: Pubkey,
     sale_price: u64,
 ) -> Result<(u64, Vec<Instruction>), Error> {
-    let minimum_balance_for_rent_exemption = rpc_client.get_minimum_balance_for_rent_exemption(
-        mpl_auction_house::TRADE_STATE_SIZE + mpl_auction_house::receipt::LISTING_RECEIPT_SIZE,
-    )?;
+    let minimum_balance_for_rent_exemption = rpc_client
+        .get_minimum_balance_for_rent_exemption(
+            mpl_auction_house::TRADE_STATE_SIZE + mpl_auction_house::receipt::LISTING_RECEIPT_SIZE,
+        )
+        .await?;
 
     let (seller_token_metadata_account, _) =
         mpl_token_metadata::pda::find_metadata_account(&seller_token_mint_account);
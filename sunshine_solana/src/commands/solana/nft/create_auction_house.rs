@@ -5,11 +5,11 @@ use std::{collections::HashMap, sync::Arc};
 use super::super::Ctx;
 use anchor_lang::{InstructionData, ToAccountMetas};
 
+use crate::commands::solana::client::Client;
 use dashmap::DashMap;
 use maplit::hashmap;
 use mpl_token_metadata::state::{Collection, Creator, UseMethod, Uses};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::instruction::Instruction;
 use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair, signer::Signer};
 use spl_token::instruction::transfer_checked;
@@ -19,6 +19,7 @@ use sunshine_core::msg::NodeId;
 
 use crate::commands::solana::instructions::execute;
 use crate::commands::solana::SolanaNet;
+use crate::commands::{util::Args, Env};
 use crate::{Error, NftMetadata, Value};
 
 use solana_sdk::signer::keypair::write_keypair_file;
@@ -44,123 +45,57 @@ impl CreateAuctionHouse {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let treasury_mint_account = match self.treasury_mint_account {
-            Some(s) => match s {
-                Some(treasury_mint_account) => {
-                    Some(ctx.get_pubkey_by_id(treasury_mint_account).await?)
-                }
-                None => None,
-            },
-            None => match inputs.remove("treasury_mint_account") {
-                Some(Value::NodeIdOpt(s)) => match s {
-                    Some(treasury_mint_account) => {
-                        Some(ctx.get_pubkey_by_id(treasury_mint_account).await?)
-                    }
-                    None => None,
-                },
-                Some(Value::Keypair(k)) => Some(Keypair::from(k).pubkey()),
-                Some(Value::Pubkey(p)) => Some(p.into()),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("treasury_mint_account".to_string())),
-            },
-        };
-
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
-
-        let fee_withdrawal_destination = match self.fee_withdrawal_destination {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("fee_withdrawal_destination") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "fee_withdrawal_destination".to_string(),
-                    ))
-                }
-            },
-        };
-
-        let auction_house_authority = match self.auction_house_authority {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("auction_house_authority") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "auction_house_authority".to_string(),
-                    ))
-                }
-            },
-        };
-
-        let treasury_withdrawal_destination = match self.treasury_withdrawal_destination {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("treasury_withdrawal_destination") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "treasury_withdrawal_destination".to_string(),
-                    ))
-                }
-            },
-        };
-
-        let treasury_withdrawal_destination_owner = match self.treasury_withdrawal_destination_owner
-        {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("treasury_withdrawal_destination_owner") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "treasury_withdrawal_destination_owner".to_string(),
-                    ))
-                }
-            },
-        };
-
-        let seller_fee_basis_points = match self.seller_fee_basis_points {
-            Some(s) => s,
-            None => match inputs.remove("seller_fee_basis_points") {
-                Some(Value::U16(s)) => s,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "seller_fee_basis_points".to_string(),
-                    ))
-                }
-            },
-        };
-
-        let requires_sign_off = match self.requires_sign_off {
-            Some(s) => s,
-            None => match inputs.remove("requires_sign_off") {
-                Some(Value::Bool(s)) => s,
-                Some(Value::Empty) => false,
-                None => false,
-                _ => return Err(Error::ArgumentNotFound("requires_sign_off".to_string())),
-            },
-        };
-
-        let can_change_sale_price = match self.can_change_sale_price {
-            Some(s) => s,
-            None => match inputs.remove("can_change_sale_price") {
-                Some(Value::Bool(s)) => s,
-                Some(Value::Empty) => true,
-                None => true,
-                _ => return Err(Error::ArgumentNotFound("can_change_sale_price".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
+
+        let treasury_mint_account = args
+            .optional_pubkey(&ctx, self.treasury_mint_account, "treasury_mint_account")
+            .await?;
+
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
+
+        let fee_withdrawal_destination = args
+            .pubkey(
+                &ctx,
+                self.fee_withdrawal_destination,
+                "fee_withdrawal_destination",
+            )
+            .await?;
+
+        let auction_house_authority = args
+            .pubkey(
+                &ctx,
+                self.auction_house_authority,
+                "auction_house_authority",
+            )
+            .await?;
+
+        let treasury_withdrawal_destination = args
+            .pubkey(
+                &ctx,
+                self.treasury_withdrawal_destination,
+                "treasury_withdrawal_destination",
+            )
+            .await?;
+
+        let treasury_withdrawal_destination_owner = args
+            .pubkey(
+                &ctx,
+                self.treasury_withdrawal_destination_owner,
+                "treasury_withdrawal_destination_owner",
+            )
+            .await?;
+
+        let seller_fee_basis_points =
+            args.required(self.seller_fee_basis_points, "seller_fee_basis_points")?;
+
+        let requires_sign_off =
+            args.or_default(self.requires_sign_off, "requires_sign_off", false)?;
+
+        let can_change_sale_price =
+            args.or_default(self.can_change_sale_price, "can_change_sale_price", true)?;
 
         let treasury_mint_account =
             treasury_mint_account.unwrap_or_else(spl_token::native_mint::id);
@@ -176,19 +111,22 @@ impl CreateAuctionHouse {
             seller_fee_basis_points,
             requires_sign_off,
             can_change_sale_price,
-        )?;
+        )
+        .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&fee_payer];
 
         let signature = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        )?;
+        )
+        .await?;
 
         let outputs = hashmap! {
             "treasury_withdrawal_destination".to_owned()=> Value::Pubkey(treasury_withdrawal_destination.into()),
@@ -202,8 +140,8 @@ impl CreateAuctionHouse {
     }
 }
 
-pub fn command_create_auction_house(
-    rpc_client: &RpcClient,
+pub async fn command_create_auction_house(
+    rpc_client: &Client,
     treasury_mint_account: Pubkey,
     fee_payer: Pubkey,
     fee_withdrawal_destination: Pubkey,
@@ -214,8 +152,9 @@ pub fn command_create_auction_house(
     requires_sign_off: bool,
     can_change_sale_price: bool,
 ) -> Result<(u64, Vec<Instruction>), Error> {
-    let minimum_balance_for_rent_exemption =
-        rpc_client.get_minimum_balance_for_rent_exemption(mpl_auction_house::AUCTION_HOUSE_SIZE)?;
+    let minimum_balance_for_rent_exemption = rpc_client
+        .get_minimum_balance_for_rent_exemption(mpl_auction_house::AUCTION_HOUSE_SIZE)
+        .await?;
 
     let (auction_house_address, bump) = mpl_auction_house::pda::find_auction_house_address(
         &auction_house_authority,
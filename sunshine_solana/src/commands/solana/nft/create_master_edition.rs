@@ -1,14 +1,15 @@
 use std::{collections::HashMap, sync::Arc};
 
 use super::super::Ctx;
+use crate::commands::solana::client::Client;
 use maplit::hashmap;
 use mpl_token_metadata::state::Creator;
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{commands::solana::instructions::execute, CommandResult, Error, NftCreator, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,57 +27,38 @@ pub enum Arg {
     None,
 }
 
+impl From<Arg> for Option<Option<u64>> {
+    fn from(arg: Arg) -> Self {
+        match arg {
+            Arg::Some(v) => Some(v),
+            Arg::None => None,
+        }
+    }
+}
+
 impl CreateMasterEdition {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let mint_authority = match self.mint_authority {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_authority") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_authority".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mint_authority = args
+            .pubkey(&ctx, self.mint_authority, "mint_authority")
+            .await?;
 
-        let update_authority = match self.update_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("update_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("update_authority".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let max_supply: Option<u64> = match self.max_supply.clone() {
-            Arg::Some(val) => val,
-            Arg::None => match inputs.remove("max_supply") {
-                Some(Value::U64(s)) => Some(s),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("max_supply".to_string())),
-            },
-        };
+        let update_authority = args
+            .keypair(&ctx, self.update_authority, "update_authority")
+            .await?;
+
+        let max_supply: Option<u64> =
+            args.optional(self.max_supply.clone().into(), "max_supply")?;
 
         let (metadata_account, _) = mpl_token_metadata::pda::find_metadata_account(&mint_account);
 
@@ -92,19 +74,22 @@ impl CreateMasterEdition {
             fee_payer.pubkey(),
             update_authority.pubkey(),
             max_supply,
-        )?;
+        )
+        .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&update_authority, &fee_payer];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -120,8 +105,8 @@ impl CreateMasterEdition {
     }
 }
 
-pub fn command_create_master_edition(
-    rpc_client: &RpcClient,
+pub async fn command_create_master_edition(
+    rpc_client: &Client,
     metadata_pubkey: Pubkey,
     master_edition_pubkey: Pubkey,
     mint: Pubkey,
@@ -130,10 +115,11 @@ pub fn command_create_master_edition(
     update_authority: Pubkey,
     max_supply: Option<u64>,
 ) -> CommandResult {
-    let minimum_balance_for_rent_exemption =
-        rpc_client.get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+    let minimum_balance_for_rent_exemption = rpc_client
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
             mpl_token_metadata::state::MasterEditionV2,
-        >())?;
+        >())
+        .await?;
 
     let instructions = vec![mpl_token_metadata::instruction::create_master_edition_v3(
         mpl_token_metadata::id(),
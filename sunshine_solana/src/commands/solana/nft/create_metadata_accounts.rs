@@ -1,14 +1,15 @@
 use std::{collections::HashMap, sync::Arc};
 
 use super::super::Ctx;
+use crate::commands::solana::client::Client;
 use maplit::hashmap;
 use mpl_token_metadata::state::{Collection, Creator, UseMethod, Uses};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
 
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{
     commands::solana::instructions::execute, CommandResult, Error, NftCreator, NftMetadata, Value,
 };
@@ -80,135 +81,48 @@ impl CreateMetadataAccounts {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let mint_authority = match self.mint_authority {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_authority") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_authority".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mint_authority = args
+            .pubkey(&ctx, self.mint_authority, "mint_authority")
+            .await?;
 
-        let update_authority = match self.update_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("update_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("update_authority".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let metadata = match &self.metadata {
-            Some(s) => s.clone(),
-            None => match inputs.remove("metadata") {
-                Some(Value::NftMetadata(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("metadata".to_string())),
-            },
-        };
+        let update_authority = args
+            .keypair(&ctx, self.update_authority, "update_authority")
+            .await?;
+
+        let metadata: NftMetadata = args.required(self.metadata.clone(), "metadata")?;
 
         let name = metadata.name;
 
         let symbol = metadata.symbol;
 
-        let metadata_uri = match &self.metadata_uri {
-            Some(s) => s.clone(),
-            None => match inputs.remove("metadata_uri") {
-                Some(Value::String(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("metadata_uri".to_string())),
-            },
-        };
+        let metadata_uri = args.required(self.metadata_uri.clone(), "metadata_uri")?;
 
         let seller_fee_basis_points = metadata.seller_fee_basis_points;
 
-        let is_mutable = match self.is_mutable {
-            Some(s) => s,
-            None => match inputs.remove("is_mutable") {
-                Some(Value::Bool(s)) => s,
-                Some(Value::Empty) => false,
-                None => false,
-                _ => return Err(Error::ArgumentNotFound("is_mutable".to_string())),
-            },
-        };
+        let is_mutable = args.or_default(self.is_mutable, "is_mutable", false)?;
 
-        let uses = match self.uses.clone() {
-            Some(uses) => uses,
-            None => match inputs.remove("uses") {
-                Some(Value::Uses(uses)) => Some(uses),
-                None => None,
-                Some(Value::Empty) => None,
-                _ => return Err(Error::ArgumentNotFound("uses".to_string())),
-            },
-        };
+        let uses = args.optional(self.uses.clone(), "uses")?;
 
-        let collection_mint_account = match self.collection_mint_account {
-            Some(s) => match s {
-                Some(collection_mint_account) => {
-                    Some(ctx.get_pubkey_by_id(collection_mint_account).await?)
-                }
-                None => None,
-            },
-            None => match inputs.remove("collection_mint_account") {
-                Some(Value::NodeIdOpt(s)) => match s {
-                    Some(collection_mint_account) => {
-                        Some(ctx.get_pubkey_by_id(collection_mint_account).await?)
-                    }
-                    None => None,
-                },
-                Some(Value::Keypair(k)) => Some(Keypair::from(k).pubkey()),
-                Some(Value::Pubkey(p)) => Some(p.into()),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "collection_mint_account".to_string(),
-                    ))
-                }
-            },
-        };
+        let collection_mint_account = args
+            .optional_pubkey(
+                &ctx,
+                self.collection_mint_account,
+                "collection_mint_account",
+            )
+            .await?;
 
-        let creators = match self.creators.as_ref() {
-            Some(creators) => {
-                if creators.len() == 0 {
-                    None
-                } else {
-                    Some(creators.clone())
-                }
-            }
-            None => match inputs.remove("creators") {
-                Some(Value::Json(json)) => {
-                    let creators: Vec<NftCreator> = serde_json::from_value(json.into())?;
-                    if creators.len() == 0 {
-                        None
-                    } else {
-                        Some(creators)
-                    }
-                }
-                Some(Value::NftCreators(creators)) => Some(creators),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("creators".to_string())),
-            },
-        };
+        let creators: Option<Vec<NftCreator>> = args
+            .optional(self.creators.clone().map(Some), "creators")?
+            .filter(|creators: &Vec<NftCreator>| !creators.is_empty());
 
         let (metadata_account, _) = mpl_token_metadata::pda::find_metadata_account(&mint_account);
 
@@ -231,19 +145,22 @@ impl CreateMetadataAccounts {
                 key: collection,
             }),
             uses.map(Into::into),
-        )?;
+        )
+        .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&update_authority, &fee_payer];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -262,8 +179,8 @@ impl CreateMetadataAccounts {
     }
 }
 
-pub fn command_create_metadata_accounts(
-    rpc_client: &RpcClient,
+pub async fn command_create_metadata_accounts(
+    rpc_client: &Client,
     metadata_pubkey: Pubkey,
     mint: Pubkey,
     mint_authority: Pubkey,
@@ -279,10 +196,11 @@ pub fn command_create_metadata_accounts(
     collection: Option<Collection>,
     uses: Option<Uses>,
 ) -> CommandResult {
-    let minimum_balance_for_rent_exemption =
-        rpc_client.get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+    let minimum_balance_for_rent_exemption = rpc_client
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
             mpl_token_metadata::state::Metadata,
-        >())?;
+        >())
+        .await?;
 
     let instructions = vec![
         mpl_token_metadata::instruction::create_metadata_accounts_v2(
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use sunshine_core::msg::NodeId;
 
+use crate::commands::util::Args;
 use crate::{Error, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -19,20 +20,15 @@ impl GetLeftUses {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
+
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
         let (metadata_account, _) = mpl_token_metadata::pda::find_metadata_account(&mint_account);
 
-        let account_data = ctx.client.get_account_data(&metadata_account)?;
+        let account_data = ctx.client.get_account_data(&metadata_account).await?;
 
         let mut account_data_ptr = account_data.as_slice();
 
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 
 use super::Ctx;
+use crate::commands::Env;
 use crate::{error::Error, Value};
 
 pub mod approve_collection_authority;
@@ -57,21 +58,22 @@ impl Command {
         &self,
         ctx: Arc<Ctx>,
         inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
         match self {
-            Command::CreateMetadataAccounts(k) => k.run(ctx, inputs).await,
-            Command::CreateMasterEdition(k) => k.run(ctx, inputs).await,
-            Command::UpdateMetadataAccounts(k) => k.run(ctx, inputs).await,
-            Command::Utilize(k) => k.run(ctx, inputs).await,
-            Command::ApproveUseAuthority(k) => k.run(ctx, inputs).await,
+            Command::CreateMetadataAccounts(k) => k.run(ctx, inputs, env).await,
+            Command::CreateMasterEdition(k) => k.run(ctx, inputs, env).await,
+            Command::UpdateMetadataAccounts(k) => k.run(ctx, inputs, env).await,
+            Command::Utilize(k) => k.run(ctx, inputs, env).await,
+            Command::ApproveUseAuthority(k) => k.run(ctx, inputs, env).await,
             Command::GetLeftUses(k) => k.run(ctx, inputs).await,
-            Command::ArweaveNftUpload(k) => k.run(ctx, inputs).await,
-            Command::ArweaveFileUpload(k) => k.run(ctx, inputs).await,
-            Command::VerifyCollection(k) => k.run(ctx, inputs).await,
-            Command::SignMetadata(k) => k.run(ctx, inputs).await,
-            Command::ApproveCollectionAuthority(k) => k.run(ctx, inputs).await,
-            Command::CreateAuctionHouse(k) => k.run(ctx, inputs).await,
-            Command::AuctionHouseSell(k) => k.run(ctx, inputs).await,
+            Command::ArweaveNftUpload(k) => k.run(ctx, inputs, env).await,
+            Command::ArweaveFileUpload(k) => k.run(ctx, inputs, env).await,
+            Command::VerifyCollection(k) => k.run(ctx, inputs, env).await,
+            Command::SignMetadata(k) => k.run(ctx, inputs, env).await,
+            Command::ApproveCollectionAuthority(k) => k.run(ctx, inputs, env).await,
+            Command::CreateAuctionHouse(k) => k.run(ctx, inputs, env).await,
+            Command::AuctionHouseSell(k) => k.run(ctx, inputs, env).await,
         }
     }
 
@@ -1,17 +1,16 @@
 use std::{collections::HashMap, sync::Arc};
 
 use super::super::Ctx;
+use crate::commands::solana::client::Client;
 use maplit::hashmap;
 use mpl_token_metadata::state::{Creator, DataV2};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
 use sunshine_core::msg::NodeId;
 
-use serde_json::Value as JsonValue;
-
 use super::create_metadata_accounts::{NftCollection, NftUses};
+use crate::commands::{util::Args, Env};
 use crate::{commands::solana::instructions::execute, CommandResult, Error, NftCreator, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -66,81 +65,31 @@ impl UpdateMetadataAccounts {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let update_authority = match self.update_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("update_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("update_authority".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let new_update_authority = match self.new_update_authority {
-            Some(s) => match s {
-                Some(s) => Some(ctx.get_pubkey_by_id(s).await?),
-                None => None,
-            },
-            None => match inputs.remove("new_update_authority") {
-                Some(Value::NodeId(s)) => Some(ctx.get_pubkey_by_id(s).await?),
-                Some(Value::Pubkey(k)) => Some(k.into()),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("new_update_authority".to_string())),
-            },
-        };
+        let update_authority = args
+            .keypair(&ctx, self.update_authority, "update_authority")
+            .await?;
 
-        let data: Option<DataV2> = match self.data.clone() {
-            Some(data) => data.map(Into::into),
-            None => match inputs.remove("data") {
-                Some(Value::MetadataAccountData(data)) => Some(data.into()),
-                Some(Value::Json(json)) => Some(
-                    serde_json::from_value::<MetadataAccountData>(JsonValue::from(json))?.into(),
-                ),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("data".to_string())),
-            },
-        };
+        let new_update_authority = args
+            .optional_pubkey(&ctx, self.new_update_authority, "new_update_authority")
+            .await?;
 
-        let primary_sale_happened = match self.primary_sale_happened {
-            Some(s) => s,
-            None => match inputs.remove("primary_sale_happened") {
-                Some(Value::Bool(s)) => Some(s),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("primary_sale_happened".to_string())),
-            },
-        };
+        let data: Option<DataV2> = args
+            .optional::<MetadataAccountData>(self.data.clone(), "data")?
+            .map(Into::into);
 
-        let is_mutable = match self.is_mutable {
-            Some(s) => s,
-            None => match inputs.remove("is_mutable") {
-                Some(Value::Bool(s)) => Some(s),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("is_mutable".to_string())),
-            },
-        };
+        let primary_sale_happened =
+            args.optional(self.primary_sale_happened, "primary_sale_happened")?;
+
+        let is_mutable = args.optional(self.is_mutable, "is_mutable")?;
 
         let (metadata_account, _) = mpl_token_metadata::pda::find_metadata_account(&mint_account);
 
@@ -159,12 +108,14 @@ impl UpdateMetadataAccounts {
         let signers: Vec<&dyn Signer> = vec![&update_authority, &fee_payer];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -180,7 +131,7 @@ impl UpdateMetadataAccounts {
 }
 
 pub fn command_update_metadata_accounts(
-    rpc_client: &RpcClient,
+    rpc_client: &Client,
     metadata_pubkey: Pubkey,
     update_authority: Pubkey,
     new_update_authority: Option<Pubkey>,
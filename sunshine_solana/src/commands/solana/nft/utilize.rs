@@ -9,6 +9,7 @@ use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair, signer::Signer};
 
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{commands::solana::instructions::execute, CommandResult, Error, NftCreator, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,78 +27,26 @@ impl Utilize {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let use_authority = match self.use_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("use_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("use_authority".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let use_authority = args
+            .keypair(&ctx, self.use_authority, "use_authority")
+            .await?;
 
-        let account = match self.account {
-            Some(s) => match s {
-                Some(account) => Some(ctx.get_pubkey_by_id(account).await?),
-                None => None,
-            },
-            None => match inputs.remove("account") {
-                Some(Value::NodeIdOpt(s)) => match s {
-                    Some(account) => Some(ctx.get_pubkey_by_id(account).await?),
-                    None => None,
-                },
-                Some(Value::Keypair(k)) => Some(Keypair::from(k).pubkey()),
-                Some(Value::Pubkey(p)) => Some(p.into()),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("account".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let owner = match self.owner {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("owner") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("owner".to_string())),
-            },
-        };
+        let account = args.optional_pubkey(&ctx, self.account, "account").await?;
 
-        let burner = match self.burner {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("burner") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("burner".to_string())),
-            },
-        };
+        let owner = args.pubkey(&ctx, self.owner, "owner").await?;
 
-        let number_of_uses = match self.number_of_uses {
-            Some(s) => s,
-            None => match inputs.remove("number_of_uses") {
-                Some(Value::U64(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("number_of_uses".to_string())),
-            },
-        };
+        let burner = args.pubkey(&ctx, self.burner, "burner").await?;
+
+        let number_of_uses = args.required(self.number_of_uses, "number_of_uses")?;
 
         let (metadata_account, _) = mpl_token_metadata::pda::find_metadata_account(&mint_account);
 
@@ -120,12 +69,14 @@ impl Utilize {
         let signers: Vec<&dyn Signer> = vec![&use_authority, &fee_payer];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -9,6 +9,7 @@ use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{commands::solana::instructions::execute, CommandResult, Error, NftCreator, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -24,60 +25,32 @@ impl VerifyCollection {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let collection_authority = match self.collection_authority {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("collection_authority") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("collection_authority".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let collection_mint_account = match self.collection_mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("collection_mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "collection_mint_account".to_string(),
-                    ))
-                }
-            },
-        };
+        let collection_authority = args
+            .keypair(&ctx, self.collection_authority, "collection_authority")
+            .await?;
 
-        let collection_authority_is_delegated = match self.collection_authority_is_delegated {
-            Some(s) => s,
-            None => match inputs.remove("collection_authority_is_delegated") {
-                Some(Value::Bool(s)) => s,
-                None => false,
-                _ => {
-                    return Err(Error::ArgumentNotFound(
-                        "collection_authority_is_delegated".to_string(),
-                    ))
-                }
-            },
-        };
+        let collection_mint_account = args
+            .pubkey(
+                &ctx,
+                self.collection_mint_account,
+                "collection_mint_account",
+            )
+            .await?;
+
+        let collection_authority_is_delegated = args.or_default(
+            self.collection_authority_is_delegated,
+            "collection_authority_is_delegated",
+            false,
+        )?;
 
         let (collection_metadata_account, _) =
             mpl_token_metadata::pda::find_metadata_account(&collection_mint_account);
@@ -114,12 +87,14 @@ impl VerifyCollection {
         let signers: Vec<&dyn Signer> = vec![&collection_authority, &fee_payer];
 
         let res = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        );
+        )
+        .await;
 
         let signature = res?;
 
@@ -3,8 +3,10 @@ use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
 use maplit::hashmap;
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{error::Error, Value};
 
 use super::Ctx;
@@ -21,30 +23,27 @@ impl RequestAirdrop {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let pubkey = match &self.pubkey {
-            Some(p) => ctx.get_pubkey_by_id(*p).await?,
-            None => match inputs.remove("pubkey") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("pubkey".to_string())),
-            },
-        };
-
-        let amount = match &self.amount {
-            Some(s) => *s,
-            None => match inputs.remove("amount") {
-                Some(Value::U64(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("amount".to_string())),
-            },
-        };
-
-        let signature = ctx.client.request_airdrop(&pubkey, amount)?;
+        let mut args = Args::new(inputs);
+
+        let pubkey = args.pubkey(&ctx, self.pubkey, "pubkey").await?;
+
+        let amount = args.required(self.amount.clone(), "amount")?;
+
+        // airdrops can't be simulated, a dry run passes the placeholder signature on
+        if env.dry_run {
+            return Ok(hashmap! {
+                "signature".to_owned()=> Value::Success(Signature::default()),
+            });
+        }
+
+        let signature = ctx.client.request_airdrop(&pubkey, amount).await?;
 
         tokio::time::sleep(Duration::from_secs(30)).await;
 
-        let succeeded = ctx.client.confirm_transaction(&signature)?;
+        let succeeded = ctx.client.confirm_transaction(&signature).await?;
 
         if !succeeded {
             return Err(Error::AirdropFailed);
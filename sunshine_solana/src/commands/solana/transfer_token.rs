@@ -1,6 +1,5 @@
 use maplit::hashmap;
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::instruction::Instruction;
 use solana_sdk::signature::Keypair;
 use solana_sdk::{program_pack::Pack, pubkey::Pubkey, signer::Signer, system_program};
@@ -8,8 +7,10 @@ use spl_token::instruction::transfer_checked;
 use std::{collections::HashMap, sync::Arc};
 use sunshine_core::msg::NodeId;
 
+use crate::commands::{util::Args, Env};
 use crate::{error::Error, Value};
 
+use super::client::Client;
 use super::instructions::execute;
 use super::mint_token::resolve_mint_info;
 use super::Ctx;
@@ -31,102 +32,32 @@ impl TransferToken {
     pub(crate) async fn run(
         &self,
         ctx: Arc<Ctx>,
-        mut inputs: HashMap<String, Value>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
     ) -> Result<HashMap<String, Value>, Error> {
-        let fee_payer = match self.fee_payer {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("fee_payer") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("fee_payer".to_string())),
-            },
-        };
+        let mut args = Args::new(inputs);
 
-        let mint_account = match self.mint_account {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("mint_account") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("mint_account".to_string())),
-            },
-        };
+        let fee_payer = args.keypair(&ctx, self.fee_payer, "fee_payer").await?;
 
-        let amount = match self.amount {
-            Some(s) => s,
-            None => match inputs.remove("amount") {
-                Some(Value::F64(s)) => s,
-                _ => return Err(Error::ArgumentNotFound("amount".to_string())),
-            },
-        };
+        let mint_account = args.pubkey(&ctx, self.mint_account, "mint_account").await?;
 
-        let recipient = match self.recipient {
-            Some(s) => ctx.get_pubkey_by_id(s).await?,
-            None => match inputs.remove("recipient") {
-                Some(Value::NodeId(id)) => ctx.get_pubkey_by_id(id).await?,
-                Some(v) => v.try_into()?,
-                _ => return Err(Error::ArgumentNotFound("recipient".to_string())),
-            },
-        };
+        let amount = args.required(self.amount, "amount")?;
 
-        let sender_token_account = match self.sender_token_account {
-            Some(s) => match s {
-                Some(sender_token_account) => {
-                    Some(ctx.get_pubkey_by_id(sender_token_account).await?)
-                }
-                None => None,
-            },
-            None => match inputs.remove("sender_token_account") {
-                Some(Value::NodeIdOpt(s)) => match s {
-                    Some(sender_token_account) => {
-                        Some(ctx.get_pubkey_by_id(sender_token_account).await?)
-                    }
-                    None => None,
-                },
-                Some(Value::Keypair(k)) => Some(Keypair::from(k).pubkey()),
-                Some(Value::Pubkey(p)) => Some(p.into()),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("sender_token_account".to_string())),
-            },
-        };
+        let recipient = args.pubkey(&ctx, self.recipient, "recipient").await?;
 
-        let sender_owner = match self.sender_owner {
-            Some(s) => ctx.get_keypair_by_id(s).await?,
-            None => match inputs.remove("sender_owner") {
-                Some(Value::NodeId(s)) => ctx.get_keypair_by_id(s).await?,
-                Some(Value::Keypair(k)) => k.into(),
-                _ => return Err(Error::ArgumentNotFound("sender_owner".to_string())),
-            },
-        };
+        let sender_token_account = args
+            .optional_pubkey(&ctx, self.sender_token_account, "sender_token_account")
+            .await?;
 
-        let allow_unfunded = match self.allow_unfunded {
-            Some(s) => s,
-            None => match inputs.remove("allow_unfunded") {
-                Some(Value::Bool(s)) => s,
-                None => true,
-                _ => return Err(Error::ArgumentNotFound("allow_unfunded".to_string())),
-            },
-        };
+        let sender_owner = args
+            .keypair(&ctx, self.sender_owner, "sender_owner")
+            .await?;
 
-        let fund_recipient = match self.fund_recipient {
-            Some(s) => s,
-            None => match inputs.remove("fund_recipient") {
-                Some(Value::Bool(s)) => s,
-                None => true,
-                _ => return Err(Error::ArgumentNotFound("fund_recipient".to_string())),
-            },
-        };
+        let allow_unfunded = args.or_default(self.allow_unfunded, "allow_unfunded", true)?;
 
-        let memo: Option<String> = match self.memo.clone() {
-            Some(val) => val,
-            None => match inputs.remove("memo") {
-                Some(Value::StringOpt(s)) => s,
-                Some(Value::String(s)) => Some(s),
-                Some(Value::Empty) => None,
-                None => None,
-                _ => return Err(Error::ArgumentNotFound("memo".to_string())),
-            },
-        };
+        let fund_recipient = args.or_default(self.fund_recipient, "fund_recipient", true)?;
+
+        let memo: Option<String> = args.optional(self.memo.clone(), "memo")?;
 
         let (minimum_balance_for_rent_exemption, instructions, recipient_acc) =
             command_transfer_token(
@@ -140,19 +71,22 @@ impl TransferToken {
                 allow_unfunded,
                 fund_recipient,
                 memo,
-            )?;
+            )
+            .await?;
 
         let fee_payer_pubkey = fee_payer.pubkey();
 
         let signers: Vec<&dyn Signer> = vec![&fee_payer, &sender_owner];
 
         let signature = execute(
+            env,
             &signers,
             &ctx.client,
             &fee_payer_pubkey,
             &instructions,
             minimum_balance_for_rent_exemption,
-        )?;
+        )
+        .await?;
 
         let outputs = hashmap! {
             "sender_owner".to_owned()=> Value::Pubkey(sender_owner.pubkey().into()),
@@ -168,8 +102,8 @@ impl TransferToken {
 // https://spl.solana.com/associated-token-account
 // https://github.com/solana-labs/solana-program-library/blob/master/token/cli/src/main.rs#L555
 #[allow(clippy::too_many_arguments)]
-pub fn command_transfer_token(
-    client: &RpcClient,
+pub async fn command_transfer_token(
+    client: &Client,
     fee_payer: &Pubkey,
     token: Pubkey,
     ui_amount: f64,
@@ -185,11 +119,14 @@ pub fn command_transfer_token(
     } else {
         spl_associated_token_account::get_associated_token_address(&sender_owner, &token)
     };
-    let (_, decimals) = resolve_mint_info(client, &sender)?;
+    let (_, decimals) = resolve_mint_info(client, &sender).await?;
     let transfer_balance = spl_token::ui_amount_to_amount(ui_amount, decimals);
     let transfer_balance = {
         let sender_token_amount = client
-            .get_token_account_balance(&sender)
+            .call("getTokenAccountBalance", move |rpc| {
+                rpc.get_token_account_balance(&sender)
+            })
+            .await
             .map_err(|err| {
                 format!(
                     "Error: Failed to get token balance of sender address {}: {}",
@@ -226,7 +163,10 @@ pub fn command_transfer_token(
 
     let recipient_is_token_account = {
         let recipient_account_info = client
-            .get_account_with_commitment(&recipient, client.commitment())?
+            .call("getAccountInfo", move |rpc| {
+                rpc.get_account_with_commitment(&recipient, rpc.commitment())
+            })
+            .await?
             .value
             .map(|account| {
                 account.owner == spl_token::id()
@@ -246,7 +186,10 @@ pub fn command_transfer_token(
 
         let needs_funding = {
             if let Some(recipient_token_account_data) = client
-                .get_account_with_commitment(&recipient_token_account, client.commitment())?
+                .call("getAccountInfo", move |rpc| {
+                    rpc.get_account_with_commitment(&recipient_token_account, rpc.commitment())
+                })
+                .await?
                 .value
             {
                 if recipient_token_account_data.owner == system_program::id() {
@@ -264,7 +207,8 @@ pub fn command_transfer_token(
         if needs_funding {
             if fund_recipient {
                 minimum_balance_for_rent_exemption += client
-                    .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+                    .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+                    .await?;
                 instructions.push(
                     spl_associated_token_account::create_associated_token_account(
                         fee_payer, &recipient, &token,
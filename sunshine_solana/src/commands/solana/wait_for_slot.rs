@@ -0,0 +1,55 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use maplit::hashmap;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{util::Args, Env};
+use crate::{error::Error, Deadline, RunState, Value};
+
+use super::Ctx;
+
+// slots are about 400ms apart
+const SLOT_MS: u64 = 400;
+// the slot is polled at least this often, in slots
+const MAX_POLL_SLOTS: u64 = 25;
+
+// passes `value` on once the cluster reached `slot`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WaitForSlot {
+    pub slot: Option<u64>,
+}
+
+impl WaitForSlot {
+    pub(crate) async fn run(
+        &self,
+        ctx: Arc<Ctx>,
+        inputs: HashMap<String, Value>,
+        env: &Env,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let mut args = Args::new(inputs);
+
+        let slot = args.required(self.slot, "slot")?;
+
+        let value = args.take("value").unwrap_or(Value::Empty);
+
+        let mut current = ctx.client.get_slot().await?;
+
+        // a dry run doesn't wait
+        if !env.dry_run && current < slot {
+            env.set_state(RunState::Waiting(Deadline::Slot(slot))).await;
+
+            while current < slot {
+                let slots = (slot - current).min(MAX_POLL_SLOTS);
+
+                tokio::time::sleep(Duration::from_millis(slots * SLOT_MS)).await;
+
+                current = ctx.client.get_slot().await?;
+            }
+        }
+
+        Ok(hashmap! {
+            "value".to_owned() => value,
+            "slot".to_owned() => Value::U64(current),
+        })
+    }
+}
@@ -1,24 +1,180 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
-use crate::{error::Error, Msg};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use sunshine_core::msg::NodeId;
 
-pub struct Arg<T> {
-    pub inner: Option<T>,
-    pub name: String,
+use crate::commands::solana::Ctx;
+use crate::{error::Error, Value, ValueKind};
+
+// resolves the arguments of a command: a value set in the command config wins,
+// otherwise the input with the same name is used, coerced to the type of the argument.
+// a value holding Empty is treated as a missing one
+pub(crate) struct Args {
+    inputs: HashMap<String, Value>,
 }
 
-impl<T> Arg<T>
-where
-    Msg: TryInto<T, Error = Error>,
-    T: Clone,
-{
-    fn remove_from(&self, inputs: &mut HashMap<String, Msg>) -> Result<T, Error> {
-        match &self.inner {
-            Some(arg) => arg.clone(),
-            None => match inputs.remove(&self.name) {
-                Some(msg) => msg.try_into(),
-                None => Err(Error::ArgumentNotFound(self.name.clone())),
+impl Args {
+    pub fn new(inputs: HashMap<String, Value>) -> Args {
+        Args { inputs }
+    }
+
+    // the raw input, for arguments which accept several unrelated types
+    pub fn take(&mut self, name: &str) -> Option<Value> {
+        self.inputs.remove(name).filter(is_present)
+    }
+
+    // the remaining inputs, for commands which take arbitrary inputs
+    pub fn into_inputs(self) -> HashMap<String, Value> {
+        self.inputs
+    }
+
+    // any value, without coercion
+    pub fn value(&mut self, config: Option<Value>, name: &str) -> Result<Value, Error> {
+        match config {
+            Some(value) => Ok(value),
+            None => self
+                .take(name)
+                .ok_or_else(|| Error::ArgumentNotFound(name.to_owned())),
+        }
+    }
+
+    pub fn optional_value(&mut self, config: Option<Option<Value>>, name: &str) -> Option<Value> {
+        match config {
+            Some(value) => value,
+            None => self.take(name),
+        }
+    }
+
+    pub fn required<T>(&mut self, config: Option<T>, name: &str) -> Result<T, Error>
+    where
+        T: TryFrom<Value, Error = Error>,
+    {
+        match config {
+            Some(value) => Ok(value),
+            None => match self.take(name) {
+                Some(value) => value.coerce_arg(name),
+                None => Err(Error::ArgumentNotFound(name.to_owned())),
+            },
+        }
+    }
+
+    pub fn optional<T>(&mut self, config: Option<Option<T>>, name: &str) -> Result<Option<T>, Error>
+    where
+        T: TryFrom<Value, Error = Error>,
+    {
+        match config {
+            Some(value) => Ok(value),
+            None => self
+                .take(name)
+                .map(|value| value.coerce_arg(name))
+                .transpose(),
+        }
+    }
+
+    pub fn or_default<T>(&mut self, config: Option<T>, name: &str, default: T) -> Result<T, Error>
+    where
+        T: TryFrom<Value, Error = Error>,
+    {
+        Ok(self.optional(config.map(Some), name)?.unwrap_or(default))
+    }
+
+    // a keypair from the wallet, either configured by node id or passed as an input
+    pub async fn keypair(
+        &mut self,
+        ctx: &Ctx,
+        config: Option<NodeId>,
+        name: &str,
+    ) -> Result<Keypair, Error> {
+        if let Some(node_id) = config {
+            return ctx.get_keypair_by_id(node_id).await;
+        }
+
+        match self.take(name) {
+            Some(value) => resolve_keypair(ctx, value, name).await,
+            None => Err(Error::ArgumentNotFound(name.to_owned())),
+        }
+    }
+
+    pub async fn optional_keypair(
+        &mut self,
+        ctx: &Ctx,
+        config: Option<Option<NodeId>>,
+        name: &str,
+    ) -> Result<Option<Keypair>, Error> {
+        match config {
+            Some(Some(node_id)) => Ok(Some(ctx.get_keypair_by_id(node_id).await?)),
+            Some(None) => Ok(None),
+            None => match self.take(name) {
+                Some(value) => Ok(Some(resolve_keypair(ctx, value, name).await?)),
+                None => Ok(None),
             },
         }
     }
+
+    // a pubkey from the wallet by node id, or any value which converts to a pubkey
+    pub async fn pubkey(
+        &mut self,
+        ctx: &Ctx,
+        config: Option<NodeId>,
+        name: &str,
+    ) -> Result<Pubkey, Error> {
+        if let Some(node_id) = config {
+            return ctx.get_pubkey_by_id(node_id).await;
+        }
+
+        match self.take(name) {
+            Some(value) => resolve_pubkey(ctx, value, name).await,
+            None => Err(Error::ArgumentNotFound(name.to_owned())),
+        }
+    }
+
+    pub async fn optional_pubkey(
+        &mut self,
+        ctx: &Ctx,
+        config: Option<Option<NodeId>>,
+        name: &str,
+    ) -> Result<Option<Pubkey>, Error> {
+        match config {
+            Some(Some(node_id)) => Ok(Some(ctx.get_pubkey_by_id(node_id).await?)),
+            Some(None) => Ok(None),
+            None => match self.take(name) {
+                Some(value) => Ok(Some(resolve_pubkey(ctx, value, name).await?)),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+fn is_present(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Empty | Value::StringOpt(None) | Value::NodeIdOpt(None)
+    )
+}
+
+async fn resolve_keypair(ctx: &Ctx, value: Value, name: &str) -> Result<Keypair, Error> {
+    match value {
+        Value::NodeId(node_id) | Value::NodeIdOpt(Some(node_id)) => {
+            ctx.get_keypair_by_id(node_id).await
+        }
+        Value::Keypair(keypair) => Ok(keypair.into()),
+        value => Err(Error::InvalidArgument {
+            port: name.to_owned(),
+            source: Box::new(Error::CantCoerce {
+                from: value.kind(),
+                to: ValueKind::Keypair,
+            }),
+        }),
+    }
+}
+
+async fn resolve_pubkey(ctx: &Ctx, value: Value, name: &str) -> Result<Pubkey, Error> {
+    match value {
+        Value::NodeId(node_id) | Value::NodeIdOpt(Some(node_id)) => {
+            ctx.get_pubkey_by_id(node_id).await
+        }
+        value => value.coerce_arg(name),
+    }
 }
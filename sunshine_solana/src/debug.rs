@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sunshine_core::msg::{NodeId, Properties};
+use tokio::sync::oneshot;
+
+use crate::Value;
+
+// node property, a debugged run pauses before running the node when it's true
+pub const BREAKPOINT_MARKER: &str = "BREAKPOINT_MARKER";
+
+// node of a debugged run waiting at its breakpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PausedNode {
+    pub node_id: NodeId,
+    pub name: String,
+    pub inputs: HashMap<String, Value>,
+}
+
+pub(crate) enum Resume {
+    // inputs replacing the received ones with the same name
+    Continue(HashMap<String, Value>),
+    Skip,
+}
+
+pub(crate) struct Breakpoint {
+    pub node: PausedNode,
+    pub resume: oneshot::Sender<Resume>,
+}
+
+pub(crate) type Breakpoints = Arc<DashMap<NodeId, Breakpoint>>;
+
+pub(crate) fn read_breakpoint(props: &Properties) -> bool {
+    props
+        .get(BREAKPOINT_MARKER)
+        .and_then(|breakpoint| breakpoint.as_bool())
+        .unwrap_or(false)
+}
+
+// waits until the node is continued or skipped, returns the inputs to run it with or
+// None if it's skipped
+pub(crate) async fn pause(
+    breakpoints: &Breakpoints,
+    node: PausedNode,
+) -> Option<HashMap<String, Value>> {
+    let (tx, rx) = oneshot::channel();
+
+    let node_id = node.node_id;
+    let mut inputs = node.inputs.clone();
+
+    breakpoints.insert(node_id, Breakpoint { node, resume: tx });
+
+    match rx.await {
+        Ok(Resume::Continue(overrides)) => {
+            inputs.extend(overrides);
+            Some(inputs)
+        }
+        // a dropped sender means the breakpoint was discarded with the run
+        Ok(Resume::Skip) | Err(_) => None,
+    }
+}
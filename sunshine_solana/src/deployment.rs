@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sunshine_core::msg::{GraphId, NodeId, Properties};
+use sunshine_core::store::Datastore;
+
+use crate::error::Error;
+use crate::{FlowId, RecurringSchedule};
+
+pub const DEPLOYMENTS_GRAPH_MARKER: &str = "DEPLOYMENTS_GRAPH_MARKER";
+pub const DEPLOYMENT_MARKER: &str = "DEPLOYMENT_MARKER";
+
+// a deployed schedule. it's stored as a node of the registry graph rather than on the root
+// of the flow, which only keeps the run count and inputs of the deployment, so restore finds
+// every schedule in one place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deployment {
+    pub flow_id: FlowId,
+    pub schedule: RecurringSchedule,
+    pub deployed_at: DateTime<Utc>,
+    // the published version which is run, the live graph without one
+    #[serde(default)]
+    pub version: Option<u64>,
+}
+
+// finds the graph holding one node per deployed flow, creating it on first use
+pub(crate) async fn find_or_create_registry(db: &Arc<dyn Datastore>) -> Result<GraphId, Error> {
+    let registry = db
+        .list_graphs()
+        .await?
+        .into_iter()
+        .find(|(_, props)| props.contains_key(DEPLOYMENTS_GRAPH_MARKER));
+
+    if let Some((graph_id, _)) = registry {
+        return Ok(graph_id);
+    }
+
+    let mut props = Properties::new();
+
+    props.insert(DEPLOYMENTS_GRAPH_MARKER.to_owned(), JsonValue::Bool(true));
+
+    let (_, graph_id) = db.create_graph(props).await?;
+
+    Ok(graph_id)
+}
+
+pub(crate) async fn read_deployments(
+    db: &Arc<dyn Datastore>,
+    registry: GraphId,
+) -> Result<Vec<(NodeId, Deployment)>, Error> {
+    let graph = db.read_graph(registry).await?;
+
+    let mut deployments = Vec::new();
+
+    for node in graph.nodes {
+        if let Some(deployment) = node.properties.get(DEPLOYMENT_MARKER) {
+            let deployment = serde_json::from_value(deployment.clone())?;
+
+            deployments.push((node.node_id, deployment));
+        }
+    }
+
+    Ok(deployments)
+}
+
+pub(crate) async fn insert_deployment(
+    db: &Arc<dyn Datastore>,
+    registry: GraphId,
+    deployment: &Deployment,
+) -> Result<(), Error> {
+    remove_deployment(db, registry, deployment.flow_id).await?;
+
+    let mut props = Properties::new();
+
+    props.insert(
+        DEPLOYMENT_MARKER.to_owned(),
+        serde_json::to_value(deployment)?,
+    );
+
+    db.create_node((registry, props)).await?;
+
+    Ok(())
+}
+
+pub(crate) async fn remove_deployment(
+    db: &Arc<dyn Datastore>,
+    registry: GraphId,
+    flow_id: FlowId,
+) -> Result<(), Error> {
+    for (node_id, deployment) in read_deployments(db, registry).await? {
+        if deployment.flow_id == flow_id {
+            db.delete_node(node_id, registry).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// removes the deployment unless the flow was deployed again since, returns whether it was
+// removed
+pub(crate) async fn remove_deployed_at(
+    db: &Arc<dyn Datastore>,
+    registry: GraphId,
+    flow_id: FlowId,
+    deployed_at: DateTime<Utc>,
+) -> Result<bool, Error> {
+    let mut removed = false;
+
+    for (node_id, deployment) in read_deployments(db, registry).await? {
+        if deployment.flow_id == flow_id && deployment.deployed_at == deployed_at {
+            db.delete_node(node_id, registry).await?;
+            removed = true;
+        }
+    }
+
+    Ok(removed)
+}
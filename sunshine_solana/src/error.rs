@@ -22,10 +22,60 @@ pub enum Error {
     PubkeyDoesntExist,
     #[error("flow doesn't exist")]
     FlowDoesntExist,
+    #[error("run doesn't exist or has already finished")]
+    RunDoesntExist,
+    #[error("run is still in progress")]
+    RunInProgress,
+    #[error("version {0} of the flow isn't published")]
+    VersionDoesntExist(u64),
+    #[error("graph {0} is a published snapshot and can't be changed")]
+    SnapshotIsImmutable(sunshine_core::msg::GraphId),
+    #[error("invalid flow file: {0}")]
+    InvalidFlowFile(String),
+    #[error("invalid flow definition: {0}")]
+    InvalidFlowDefinition(String),
+    #[error("can't connect {output} to {input}: {reason}")]
+    IncompatibleEdge {
+        output: String,
+        input: String,
+        reason: String,
+    },
+    #[error("node {0} isn't paused at a breakpoint")]
+    NodeNotPaused(sunshine_core::msg::NodeId),
+    #[error("node {0} isn't awaiting approval")]
+    NodeNotAwaitingApproval(sunshine_core::msg::NodeId),
+    #[error("run was aborted before it finished")]
+    RunAborted,
+    #[error("invalid timestamp: {0}")]
+    InvalidTimestamp(i64),
+    #[error("invalid duration: {0}ms")]
+    InvalidDuration(u64),
+    #[error("invalid schedule: {0}")]
+    InvalidSchedule(String),
+    #[error("flow variables must be a json object")]
+    InvalidFlowVariables,
+    #[error("flow doesn't define variable: {0}")]
+    UnknownFlowVariable(String),
+    #[error("node {0} isn't part of the flow")]
+    NodeNotInFlow(sunshine_core::msg::NodeId),
+    #[error("no flow is bound to webhook: {0}")]
+    WebhookNotFound(String),
+    #[error("webhook path is already bound to another flow: {0}")]
+    WebhookPathInUse(String),
+    #[error("solana subscription error: {0}")]
+    Subscription(String),
+    #[error("rpc request task failed: {0}")]
+    RpcTaskFailed(String),
     #[error("core error: {0}")]
     Core(sunshine_core::Error),
     #[error("argument not found: {0}")]
     ArgumentNotFound(String),
+    #[error("invalid argument {port}: {source}")]
+    InvalidArgument { port: String, source: Box<Error> },
+    #[error("can't convert {from} to {to} without losing information")]
+    CantCoerce { from: ValueKind, to: ValueKind },
+    #[error("node {node}: {source}")]
+    Node { node: String, source: Box<Error> },
     #[error("can't get mnemonic from phrase")]
     CantGetMnemonicFromPhrase,
     #[error("failed to get keypair from seed: {0}")]
@@ -46,8 +96,6 @@ pub enum Error {
     SolanaProgram(SolanaProgramError),
     #[error("no context for command")]
     NoContextForCommand,
-    #[error("error when trying to convert value of type {0} to type {1}")]
-    ValueIntoError(ValueKind, String),
     #[error("solana airdrop failed")]
     AirdropFailed,
     #[error("io error: {0}")]
@@ -84,6 +132,8 @@ pub enum Error {
     BundlrApiInvalidResponse,
     #[error("failed to register funding tx to bundlr. tx_id={0};")]
     BundlrTxRegisterFailed(String),
+    #[error("transaction simulation failed: {0}")]
+    SimulationFailed(String),
     #[error("insufficient solana balance, needed={needed}; have={balance};")]
     InsufficientSolanaBalance { needed: u64, balance: u64 },
     #[error("can't compare {a} with {b} using {operator} operator")]
@@ -96,6 +146,10 @@ pub enum Error {
     IncompatibleJson(JsonValueWrapper),
     #[error("can't convert value to json: {0}")]
     IncompatibleValue(Value),
+    #[error("expression error: {0}")]
+    Expression(String),
+    #[error("invalid regex: {0}")]
+    InvalidRegex(String),
     #[error("invalid http headers passed in arguments")]
     InvalidHttpHeaders,
     #[error("multiple outputs connected to same input")]
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sunshine_core::msg::{CreateEdge, NodeId, Properties};
+use sunshine_core::store::Datastore;
+
+use crate::error::Error;
+use crate::variables::DEPLOY_INPUTS_MARKER;
+use crate::version::{FLOW_VERSIONS_MARKER, SNAPSHOT_MARKER};
+use crate::{FlowId, SCHEDULE_RUN_COUNT_MARKER};
+
+// a flow graph as a standalone json document, for moving flows between datastores.
+// node ids are only used to connect the edges, importing creates new ones
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlowFile {
+    #[serde(default)]
+    pub properties: Properties,
+    pub nodes: Vec<FlowFileNode>,
+    #[serde(default)]
+    pub edges: Vec<FlowFileEdge>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowFileNode {
+    pub id: NodeId,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowFileEdge {
+    pub from: NodeId,
+    pub to: NodeId,
+    #[serde(default)]
+    pub properties: Properties,
+}
+
+// properties of the flow root which belong to a deployment or the store, not the flow
+fn is_flow_property(name: &str) -> bool {
+    !name.starts_with('_')
+        && ![
+            SCHEDULE_RUN_COUNT_MARKER,
+            DEPLOY_INPUTS_MARKER,
+            FLOW_VERSIONS_MARKER,
+            SNAPSHOT_MARKER,
+        ]
+        .contains(&name)
+}
+
+pub async fn export_flow(db: &Arc<dyn Datastore>, flow_id: FlowId) -> Result<FlowFile, Error> {
+    let properties = db
+        .read_node(flow_id)
+        .await?
+        .properties
+        .into_iter()
+        .filter(|(name, _)| is_flow_property(name))
+        .collect();
+
+    let graph = db.read_graph(flow_id).await?;
+
+    let mut edges = Vec::new();
+
+    for node in graph.nodes.iter() {
+        for edge in node.outbound_edges.iter() {
+            let properties = match graph.edges.get(&edge.id) {
+                Some(properties) => properties.clone(),
+                None => db.read_edge_properties(*edge).await?,
+            };
+
+            edges.push(FlowFileEdge {
+                from: edge.from,
+                to: edge.to,
+                properties,
+            });
+        }
+    }
+
+    let nodes = graph
+        .nodes
+        .into_iter()
+        .map(|node| FlowFileNode {
+            id: node.node_id,
+            properties: node.properties,
+        })
+        .collect();
+
+    Ok(FlowFile {
+        properties,
+        nodes,
+        edges,
+    })
+}
+
+// creates a new flow graph from the file and returns its id
+pub async fn import_flow(db: &Arc<dyn Datastore>, file: &FlowFile) -> Result<FlowId, Error> {
+    let (_, flow_id) = db.create_graph(file.properties.clone()).await?;
+
+    let mut node_ids = HashMap::new();
+
+    for node in file.nodes.iter() {
+        let (_, node_id) = db.create_node((flow_id, node.properties.clone())).await?;
+
+        if node_ids.insert(node.id, node_id).is_some() {
+            return Err(Error::InvalidFlowFile(format!(
+                "node {} is defined twice",
+                node.id
+            )));
+        }
+    }
+
+    for edge in file.edges.iter() {
+        let node_id = |id: NodeId| {
+            node_ids.get(&id).copied().ok_or_else(|| {
+                Error::InvalidFlowFile(format!("edge refers to unknown node {}", id))
+            })
+        };
+
+        db.create_edge(
+            CreateEdge {
+                from: node_id(edge.from)?,
+                to: node_id(edge.to)?,
+                properties: edge.properties.clone(),
+            },
+            flow_id,
+        )
+        .await?;
+    }
+
+    Ok(flow_id)
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedReceiver as Receiver;
+
+use crate::Value;
+
+// node property holding a map of input name to MergeStrategy
+pub const INPUT_MERGE_MARKER: &str = "INPUT_MERGE_MARKER";
+
+// how values from several edges connected to the same input are combined
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MergeStrategy {
+    // the first value that arrives, including Cancel
+    FirstArrival,
+    // waits for every edge, canceled edges are left out
+    Collect,
+    // the first value which isn't Cancel, used to rejoin the paths of a branch
+    FirstNotCanceled,
+}
+
+pub(crate) fn read_merge_strategies(
+    props: &sunshine_core::msg::Properties,
+) -> Result<HashMap<String, MergeStrategy>, serde_json::Error> {
+    match props.get(INPUT_MERGE_MARKER) {
+        Some(merge) => serde_json::from_value(merge.clone()),
+        None => Ok(HashMap::new()),
+    }
+}
+
+// every edge connected to the input shares the same channel
+pub(crate) struct FlowInput {
+    rx: Receiver<Value>,
+    edges: usize,
+    merge: MergeStrategy,
+}
+
+impl FlowInput {
+    pub fn new(rx: Receiver<Value>, merge: MergeStrategy) -> FlowInput {
+        FlowInput {
+            rx,
+            edges: 1,
+            merge,
+        }
+    }
+
+    pub fn add_edge(&mut self) {
+        self.edges += 1;
+    }
+
+    // returns None when the input can't be received anymore
+    pub async fn recv(&mut self) -> Option<Value> {
+        match self.merge {
+            MergeStrategy::FirstArrival => self.rx.recv().await,
+            MergeStrategy::FirstNotCanceled => {
+                for _ in 0..self.edges {
+                    match self.rx.recv().await? {
+                        Value::Cancel => continue,
+                        value => return Some(value),
+                    }
+                }
+
+                Some(Value::Cancel)
+            }
+            MergeStrategy::Collect => {
+                let mut values = Vec::with_capacity(self.edges);
+                let mut canceled = 0;
+
+                for _ in 0..self.edges {
+                    match self.rx.recv().await? {
+                        Value::Cancel => canceled += 1,
+                        value => values.push(value),
+                    }
+                }
+
+                if canceled == self.edges {
+                    return Some(Value::Cancel);
+                }
+
+                Some(Value::Array(values))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    // an input with an edge per value, the values arrive in order
+    fn input(merge: MergeStrategy, values: Vec<Value>) -> FlowInput {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let mut input = FlowInput::new(rx, merge);
+
+        for _ in 1..values.len() {
+            input.add_edge();
+        }
+
+        for value in values {
+            tx.send(value).unwrap();
+        }
+
+        input
+    }
+
+    #[tokio::test]
+    async fn collect_leaves_out_canceled_edges_in_arrival_order() {
+        let values = vec![Value::U64(2), Value::Cancel, Value::U64(1)];
+
+        let collected = input(MergeStrategy::Collect, values).recv().await;
+
+        match collected {
+            Some(Value::Array(values)) => {
+                assert!(matches!(values.as_slice(), [Value::U64(2), Value::U64(1)]))
+            }
+            other => panic!("unexpected value: {:?}", other),
+        }
+
+        let canceled = vec![Value::Cancel, Value::Cancel];
+
+        assert!(matches!(
+            input(MergeStrategy::Collect, canceled).recv().await,
+            Some(Value::Cancel)
+        ));
+    }
+
+    #[tokio::test]
+    async fn first_not_canceled_skips_canceled_edges() {
+        let values = vec![Value::Cancel, Value::U64(5), Value::U64(6)];
+
+        assert!(matches!(
+            input(MergeStrategy::FirstNotCanceled, values).recv().await,
+            Some(Value::U64(5))
+        ));
+
+        let canceled = vec![Value::Cancel, Value::Cancel];
+
+        assert!(matches!(
+            input(MergeStrategy::FirstNotCanceled, canceled)
+                .recv()
+                .await,
+            Some(Value::Cancel)
+        ));
+    }
+
+    #[tokio::test]
+    async fn first_arrival_takes_cancel_too() {
+        let values = vec![Value::Cancel, Value::U64(5)];
+
+        assert!(matches!(
+            input(MergeStrategy::FirstArrival, values).recv().await,
+            Some(Value::Cancel)
+        ));
+    }
+
+    #[tokio::test]
+    async fn closed_edges_end_the_input() {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let mut input = FlowInput::new(rx, MergeStrategy::Collect);
+        input.add_edge();
+
+        tx.send(Value::U64(1)).unwrap();
+        drop(tx);
+
+        assert!(input.recv().await.is_none());
+    }
+}
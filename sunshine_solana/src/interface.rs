@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use sunshine_core::msg::{Graph, NodeId, Properties};
+
+use crate::error::Error;
+use crate::Value;
+
+// node property mapping inputs of the node to the flow input they receive,
+// e.g. {"amount": "amount", "recipient": "to"}
+pub const FLOW_INPUTS_MARKER: &str = "FLOW_INPUTS_MARKER";
+// node property mapping outputs of the node to the flow output they're returned as
+pub const FLOW_OUTPUTS_MARKER: &str = "FLOW_OUTPUTS_MARKER";
+// inputs passed to the nodes of a run when it started, e.g. flow inputs and trigger payloads.
+// stored on the log graph so that a resumed run gets them again
+pub const RUN_INPUTS_MARKER: &str = "RUN_INPUTS_MARKER";
+// flow outputs of a finished run, stored on the log graph
+pub const RUN_OUTPUTS_MARKER: &str = "RUN_OUTPUTS_MARKER";
+
+pub(crate) fn read_bindings(
+    props: &Properties,
+    marker: &str,
+) -> Result<HashMap<String, String>, serde_json::Error> {
+    match props.get(marker) {
+        Some(bindings) => serde_json::from_value(bindings.clone()),
+        None => Ok(HashMap::new()),
+    }
+}
+
+// splits the values passed to a run into the inputs of the nodes bound to flow inputs and
+// overrides of flow variables. flow inputs which aren't passed are left unconnected
+pub(crate) fn split_inputs(
+    graph: &Graph,
+    mut values: HashMap<String, Value>,
+) -> Result<
+    (
+        HashMap<NodeId, HashMap<String, Value>>,
+        HashMap<String, Value>,
+    ),
+    Error,
+> {
+    let mut start_inputs: HashMap<NodeId, HashMap<String, Value>> = HashMap::new();
+    let mut bound = Vec::new();
+
+    for node in graph.nodes.iter() {
+        for (input, name) in read_bindings(&node.properties, FLOW_INPUTS_MARKER)? {
+            if let Some(value) = values.get(&name) {
+                start_inputs
+                    .entry(node.node_id)
+                    .or_default()
+                    .insert(input, value.clone());
+            }
+
+            bound.push(name);
+        }
+    }
+
+    for name in bound {
+        values.remove(&name);
+    }
+
+    Ok((start_inputs, values))
+}
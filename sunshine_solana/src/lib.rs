@@ -2,13 +2,11 @@ use commands::simple::branch::Operator;
 use commands::solana::nft::create_metadata_accounts::NftUses;
 use serde::{Deserialize, Serialize};
 use solana_sdk::signature::Signature;
-use solana_sdk::signer::Signer;
 use std::fmt;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
 
-use commands::Command;
+use commands::{Command, Env};
 use dashmap::DashMap;
 use mpl_token_metadata::state::Creator;
 use serde_json::Value as JsonValue;
@@ -16,19 +14,71 @@ use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signer::keypair::Keypair;
 use std::collections::HashMap;
-use sunshine_core::msg::{Action, CreateEdge, GraphId, NodeId, Properties, QueryKind};
+use sunshine_core::msg::{CreateEdge, EdgeId, Graph, GraphId, NodeId, Properties};
 use sunshine_core::store::Datastore;
 
-use tokio::sync::mpsc::{self, UnboundedReceiver as Receiver, UnboundedSender as Sender};
-use tokio::sync::watch;
+use tokio::sync::mpsc::{self, UnboundedSender as Sender};
+use tokio::sync::{broadcast, watch, OnceCell};
+use tokio::task::JoinHandle;
+use tracing::Instrument;
 
 use parse_display::Display as ParseDisplay;
 
 use uuid::Uuid;
 
+mod approval;
+mod builder;
+mod cache;
+mod coerce;
 pub mod commands;
+mod debug;
+mod deployment;
 mod error;
-use error::Error;
+mod flow_file;
+mod input;
+mod interface;
+mod limits;
+mod metrics;
+mod ports;
+mod record;
+mod schedule;
+mod trigger;
+mod validate;
+mod variables;
+mod version;
+pub use error::Error;
+
+pub use approval::{Decision, PendingApproval};
+pub use builder::{EdgeSpec, FlowBuilder, NodeSpec};
+pub use cache::{CachePolicy, CACHE_MARKER};
+pub use coerce::Conversion;
+pub use debug::{PausedNode, BREAKPOINT_MARKER};
+pub use deployment::Deployment;
+pub use flow_file::{export_flow, import_flow, FlowFile, FlowFileEdge, FlowFileNode};
+pub use input::{MergeStrategy, INPUT_MERGE_MARKER};
+pub use interface::{
+    FLOW_INPUTS_MARKER, FLOW_OUTPUTS_MARKER, RUN_INPUTS_MARKER, RUN_OUTPUTS_MARKER,
+};
+pub use limits::{Limit, Limits};
+pub use metrics::{MetricsExporter, NoopMetrics, PrometheusMetrics};
+pub use ports::{check_edge, EdgeCheck, EDGE_COERCE_MARKER};
+pub use record::{NodeRunRecord, RunEvent, StateChange, NODE_RUN_RECORD_MARKER};
+pub use schedule::{OverlapPolicy, Recurrence, RecurringSchedule, Schedule};
+pub use trigger::{Trigger, TriggerKind, TRIGGER_MARKER};
+pub use validate::{validate_flow, Problem};
+pub use variables::{DEPLOY_INPUTS_MARKER, FLOW_VARIABLES_MARKER, RUN_VARIABLES_MARKER};
+pub use version::{
+    FlowVersion, Snapshot, FLOW_VERSIONS_MARKER, FLOW_VERSION_MARKER, SNAPSHOT_MARKER,
+};
+
+use approval::Approvals;
+use cache::{Cache, NodeCache};
+use debug::{Breakpoints, Resume};
+use input::FlowInput;
+use limits::Limiter;
+use ports::OutputEdge;
+use record::NodeLog;
+use trigger::WebhookBinding;
 
 use commands::solana::nft::update_metadata_accounts::MetadataAccountData;
 
@@ -49,76 +99,510 @@ pub const CTX_EDGE_MARKER: &str = "CTX_EDGE_MARKER";
 pub const CTX_MARKER: &str = "CTX_MARKER";
 pub const COMMAND_NAME_MARKER: &str = "COMMAND_NAME_MARKER";
 pub const RUN_ID_MARKER: &str = "RUN_ID_MARKER";
+pub const FLOW_ID_MARKER: &str = "FLOW_ID_MARKER";
+pub const CHECKPOINT_MARKER: &str = "CHECKPOINT_MARKER";
+pub const SCHEDULE_RUN_COUNT_MARKER: &str = "SCHEDULE_RUN_COUNT_MARKER";
+pub const DRY_RUN_MARKER: &str = "DRY_RUN_MARKER";
 
 pub struct FlowContext {
-    deployed: DashMap<FlowId, watch::Sender<u8>>,
-    db: Arc<dyn Datastore>,
+    deployed: Arc<DashMap<FlowId, ScheduleHandle>>,
+    deployments: OnceCell<GraphId>,
+    triggers: DashMap<FlowId, watch::Sender<u8>>,
+    webhooks: DashMap<String, WebhookBinding>,
+    engine: Engine,
+}
+
+// state shared by the flow context with its runs and the commands they execute
+#[derive(Clone)]
+pub(crate) struct Engine {
+    pub db: Arc<dyn Datastore>,
+    pub runs: Arc<DashMap<RunId, RunHandle>>,
+    pub metrics: Arc<dyn MetricsExporter>,
+    pub events: broadcast::Sender<RunEvent>,
+    pub limiter: Arc<Limiter>,
+    pub cache: Cache,
+}
+
+// schedule of a deployment, which is told apart from the schedules of earlier deployments
+// of the flow by when it was deployed
+struct ScheduleHandle {
+    deployed_at: chrono::DateTime<chrono::Utc>,
+    stop_signal: watch::Sender<u8>,
+}
+
+struct RunHandle {
+    flow_id: FlowId,
+    stop_signal: watch::Sender<u8>,
+    breakpoints: Breakpoints,
+    approvals: Approvals,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions {
+    // transactions are simulated instead of sent
+    pub dry_run: bool,
+    // the run pauses before nodes with a BREAKPOINT_MARKER
+    pub debug: bool,
+    // the published version to run instead of the live graph
+    pub version: Option<u64>,
 }
 
 impl FlowContext {
     pub fn new(db: Arc<dyn Datastore>) -> FlowContext {
+        FlowContext::with_metrics(db, Arc::new(NoopMetrics))
+    }
+
+    pub fn with_metrics(db: Arc<dyn Datastore>, metrics: Arc<dyn MetricsExporter>) -> FlowContext {
+        let (events, _) = broadcast::channel(1024);
+
         FlowContext {
-            deployed: DashMap::new(),
-            db,
+            deployed: Arc::new(DashMap::new()),
+            deployments: OnceCell::new(),
+            triggers: DashMap::new(),
+            webhooks: DashMap::new(),
+            engine: Engine {
+                db,
+                runs: Arc::new(DashMap::new()),
+                metrics,
+                events,
+                limiter: Arc::new(Limiter::new(&Limits::default())),
+                cache: Cache::default(),
+            },
         }
     }
 
-    pub fn undeploy_flow(&self, flow_id: FlowId) -> Result<(), Error> {
-        let (_, stop_signal) = self
+    // limits how many commands run at once and how fast solana rpcs and http hosts are
+    // called, across every flow of the context
+    pub fn with_limits(mut self, limits: &Limits) -> FlowContext {
+        self.engine.limiter = Arc::new(Limiter::new(limits));
+        self
+    }
+
+    // state changes of the nodes of every run, slow receivers miss the oldest changes
+    pub fn subscribe(&self) -> broadcast::Receiver<RunEvent> {
+        self.engine.events.subscribe()
+    }
+
+    async fn deployments_graph(&self) -> Result<GraphId, Error> {
+        self.deployments
+            .get_or_try_init(|| deployment::find_or_create_registry(&self.engine.db))
+            .await
+            .map(|graph_id| *graph_id)
+    }
+
+    // stops scheduling new runs, runs already in progress are left alone
+    #[tracing::instrument(skip(self))]
+    pub async fn undeploy_flow(&self, flow_id: FlowId) -> Result<(), Error> {
+        self.stop_schedule(flow_id)?;
+
+        self.forget_deployment(flow_id).await
+    }
+
+    // removes the persisted deployment of the flow, so restore doesn't bring it back
+    async fn forget_deployment(&self, flow_id: FlowId) -> Result<(), Error> {
+        let registry = self.deployments_graph().await?;
+
+        deployment::remove_deployment(&self.engine.db, registry, flow_id).await?;
+
+        clear_deploy_props(&self.engine.db, flow_id).await
+    }
+
+    // freezes the current graph of the flow as the next version, later edits of the flow
+    // don't change what deployments of the version run
+    #[tracing::instrument(skip(self))]
+    pub async fn publish_flow(&self, flow_id: FlowId) -> Result<FlowVersion, Error> {
+        version::publish(&self.engine.db, flow_id).await
+    }
+
+    pub async fn list_versions(&self, flow_id: FlowId) -> Result<Vec<FlowVersion>, Error> {
+        let props = self.engine.db.read_node(flow_id).await?.properties;
+
+        version::read_versions(&props)
+    }
+
+    pub async fn validate_flow(&self, flow_id: FlowId) -> Result<Vec<Problem>, Error> {
+        validate::validate_flow(&self.engine.db, flow_id).await
+    }
+
+    pub async fn export_flow(&self, flow_id: FlowId) -> Result<FlowFile, Error> {
+        flow_file::export_flow(&self.engine.db, flow_id).await
+    }
+
+    pub async fn import_flow(&self, file: &FlowFile) -> Result<FlowId, Error> {
+        flow_file::import_flow(&self.engine.db, file).await
+    }
+
+    // how values would get from an output of one node to an input of another
+    pub async fn check_edge(
+        &self,
+        from: NodeId,
+        output: &str,
+        to: NodeId,
+        input: &str,
+    ) -> Result<EdgeCheck, Error> {
+        let db = &self.engine.db;
+
+        match (
+            ports::read_command(db, from).await?,
+            ports::read_command(db, to).await?,
+        ) {
+            (Some(from_cfg), Some(to_cfg)) => {
+                Ok(ports::check_edge(&from_cfg, output, &to_cfg, input))
+            }
+            _ => Ok(EdgeCheck::Unchecked),
+        }
+    }
+
+    // checks the ports named by the properties of a new edge and sets EDGE_COERCE_MARKER,
+    // None when the edge doesn't connect two commands
+    pub async fn type_edge(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        properties: &mut Properties,
+    ) -> Result<Option<EdgeCheck>, Error> {
+        ports::type_stored_edge(&self.engine.db, from, to, properties).await
+    }
+
+    // connects an output to an input, rejecting ports whose kinds don't convert
+    #[tracing::instrument(skip(self))]
+    pub async fn connect(
+        &self,
+        flow_id: FlowId,
+        from: NodeId,
+        output: &str,
+        to: NodeId,
+        input: &str,
+    ) -> Result<(EdgeId, EdgeCheck), Error> {
+        let db = &self.engine.db;
+
+        if version::read_snapshot(&db.read_node(flow_id).await?.properties)?.is_some() {
+            return Err(Error::SnapshotIsImmutable(flow_id));
+        }
+
+        let mut properties = Properties::new();
+        properties.insert(OUTPUT_ARG_NAME_MARKER.to_owned(), JsonValue::from(output));
+        properties.insert(INPUT_ARG_NAME_MARKER.to_owned(), JsonValue::from(input));
+
+        let check = self
+            .type_edge(from, to, &mut properties)
+            .await?
+            .unwrap_or(EdgeCheck::Unchecked);
+
+        let (_, edge_id) = db
+            .create_edge(
+                CreateEdge {
+                    from,
+                    to,
+                    properties,
+                },
+                flow_id,
+            )
+            .await?;
+
+        Ok((edge_id, check))
+    }
+
+    pub async fn list_deployed(&self) -> Result<Vec<Deployment>, Error> {
+        let registry = self.deployments_graph().await?;
+
+        let deployments = deployment::read_deployments(&self.engine.db, registry)
+            .await?
+            .into_iter()
+            .map(|(_, deployment)| deployment)
+            .collect();
+
+        Ok(deployments)
+    }
+
+    // redeploys every persisted schedule, meant to be called once on startup
+    pub async fn restore(&self) -> Result<Vec<FlowId>, Error> {
+        let registry = self.deployments_graph().await?;
+
+        let mut restored = Vec::new();
+
+        for (_, deployment) in deployment::read_deployments(&self.engine.db, registry).await? {
+            let flow_id = deployment.flow_id;
+
+            match self.resume_deployment(deployment, registry).await {
+                Ok(()) => restored.push(flow_id),
+                Err(e) => tracing::error!(%flow_id, error = %e, "failed to restore deployment"),
+            }
+        }
+
+        Ok(restored)
+    }
+
+    fn stop_schedule(&self, flow_id: FlowId) -> Result<(), Error> {
+        let (_, schedule) = self
             .deployed
             .remove(&flow_id)
             .ok_or(Error::FlowDoesntExist)?;
-        if stop_signal.send(5).is_err() {
-            eprintln!("flow already undeployed itself");
+        if schedule.stop_signal.send(5).is_err() {
+            tracing::warn!(%flow_id, "flow already undeployed itself");
         }
         Ok(())
     }
 
-    async fn read_flow(db: Arc<dyn Datastore>, flow_id: FlowId) -> Result<Flow, Error> {
-        let graph = db
-            .execute(Action::Query(QueryKind::ReadGraph(flow_id)))
-            .await?
-            .into_graph()
-            .unwrap();
+    // binds every start node carrying a TRIGGER_MARKER to its webhook or solana subscription
+    pub async fn enable_triggers(&self, flow_id: FlowId) -> Result<usize, Error> {
+        self.disable_triggers(flow_id).ok();
+
+        let graph = self.engine.db.read_graph(flow_id).await?;
 
-        let (_, log_graph_id) = db.create_graph(Default::default()).await.unwrap();
+        let triggers = trigger::read_triggers(&graph)?;
 
-        // let timestamp = chrono::offset::Utc::now().timestamp_millis();
-        // let timestamp = JsonValue::Number(serde_json::Number::from(timestamp));
-        let timestamp = chrono::offset::Utc::now()
-            .format("%Y-%m-%d %H%M UTC")
-            .to_string();
+        let (send_stop_signal, stop_signal) = watch::channel(1u8);
 
-        let timestamp = JsonValue::String(timestamp);
+        let mut webhooks = Vec::new();
 
-        let mut props = Properties::default();
+        for (node_id, trigger) in triggers.iter().cloned() {
+            if let TriggerKind::Webhook { path } = &trigger.kind {
+                match self.webhooks.get(path) {
+                    Some(binding) if binding.flow_id != flow_id => {
+                        return Err(Error::WebhookPathInUse(path.clone()));
+                    }
+                    _ => (),
+                }
 
-        let run_id = Uuid::new_v4();
+                webhooks.push((
+                    path.clone(),
+                    WebhookBinding {
+                        flow_id,
+                        node_id,
+                        input: trigger.input,
+                    },
+                ));
+            }
+        }
 
-        props.insert("timestamp".to_owned(), timestamp);
-        props.insert(
-            RUN_ID_MARKER.to_owned(),
-            JsonValue::String(run_id.to_string()),
-        );
+        // on error send_stop_signal is dropped, which stops the subscriptions started so far.
+        // webhooks are only bound once every subscription was started
+        for (node_id, trigger) in triggers.iter().cloned() {
+            if !matches!(trigger.kind, TriggerKind::Webhook { .. }) {
+                trigger::spawn_subscription(
+                    self.engine.clone(),
+                    flow_id,
+                    node_id,
+                    trigger,
+                    stop_signal.clone(),
+                )
+                .await?;
+            }
+        }
 
-        db.create_edge(
-            CreateEdge {
-                from: flow_id,
-                to: log_graph_id,
-                properties: props,
-            },
-            flow_id,
+        for (path, binding) in webhooks {
+            self.webhooks.insert(path, binding);
+        }
+
+        self.triggers.insert(flow_id, send_stop_signal);
+
+        Ok(triggers.len())
+    }
+
+    pub fn disable_triggers(&self, flow_id: FlowId) -> Result<(), Error> {
+        let (_, stop_signal) = self
+            .triggers
+            .remove(&flow_id)
+            .ok_or(Error::FlowDoesntExist)?;
+
+        stop_signal.send(5).ok();
+
+        self.webhooks.retain(|_, binding| binding.flow_id != flow_id);
+
+        Ok(())
+    }
+
+    // entry point for the http server in front of the flow context
+    #[tracing::instrument(skip(self, payload))]
+    pub async fn handle_webhook(&self, path: &str, payload: JsonValue) -> Result<RunId, Error> {
+        let binding = self
+            .webhooks
+            .get(path)
+            .map(|binding| binding.clone())
+            .ok_or_else(|| Error::WebhookNotFound(path.to_owned()))?;
+
+        let start_inputs = trigger::start_inputs(binding.node_id, &binding.input, payload);
+
+        let (run_id, _) = Self::run_flow(
+            self.engine.clone(),
+            binding.flow_id,
+            start_inputs,
+            HashMap::new(),
+            RunOptions::default(),
         )
-        .await
-        .unwrap();
+        .await?;
+
+        Ok(run_id)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn cancel_run(&self, run_id: RunId) -> Result<(), Error> {
+        let (_, run) = self
+            .engine
+            .runs
+            .remove(&run_id)
+            .ok_or(Error::RunDoesntExist)?;
+        if run.stop_signal.send(5).is_err() {
+            tracing::warn!(%run_id, "run already finished");
+        }
+        Ok(())
+    }
+
+    pub fn list_runs(&self, flow_id: FlowId) -> Vec<RunId> {
+        self.engine
+            .runs
+            .iter()
+            .filter(|run| run.flow_id == flow_id)
+            .map(|run| *run.key())
+            .collect()
+    }
+
+    // records of the nodes of a run, keyed by the node of the flow graph.
+    // nodes which haven't been reached yet have no record
+    pub async fn get_run(&self, run_id: RunId) -> Result<HashMap<NodeId, NodeRunRecord>, Error> {
+        let log_graph = self.engine.db.read_graph(run_id).await?;
+
+        let mut records = HashMap::new();
+
+        for log_node in log_graph.nodes {
+            if let Some(record) = record::read_record(&log_node.properties)? {
+                records.insert(record.node_id, record);
+            }
+        }
+
+        Ok(records)
+    }
+
+    // the state changes recorded for the nodes of the run, oldest first
+    pub async fn run_events(&self, run_id: RunId) -> Result<Vec<RunEvent>, Error> {
+        let run_props = self.engine.db.read_node(run_id).await?.properties;
+
+        let flow_id = run_props
+            .get(FLOW_ID_MARKER)
+            .and_then(JsonValue::as_str)
+            .map(Uuid::parse_str)
+            .ok_or(Error::RunDoesntExist)?
+            .map_err(sunshine_core::Error::from)?;
+
+        let mut events = Vec::new();
+
+        for record in self.get_run(run_id).await?.into_values() {
+            for change in record.states {
+                events.push(RunEvent {
+                    flow_id,
+                    run_id,
+                    node_id: record.node_id,
+                    name: record.name.clone(),
+                    state: change.state,
+                    at: change.at,
+                });
+            }
+        }
+
+        events.sort_by_key(|event| event.at);
+
+        Ok(events)
+    }
+
+    // whether the run was started by this flow context and hasn't finished yet
+    pub fn is_running(&self, run_id: RunId) -> bool {
+        self.engine.runs.contains_key(&run_id)
+    }
+
+    // when resuming, the log graph and log nodes of the previous attempt are reused
+    async fn read_flow(
+        engine: Engine,
+        flow_id: FlowId,
+        graph: Graph,
+        resume: Option<(RunId, HashMap<NodeId, NodeId>)>,
+        variables: HashMap<String, Value>,
+        options: RunOptions,
+    ) -> Result<Flow, Error> {
+        let db = engine.db.clone();
+
+        let (run_id, mut log_nodes) = match resume {
+            Some(resume) => resume,
+            None => {
+                let run_id = Uuid::new_v4();
+
+                let mut props = Properties::default();
+
+                props.insert(
+                    RUN_ID_MARKER.to_owned(),
+                    JsonValue::String(run_id.to_string()),
+                );
+                props.insert(
+                    FLOW_ID_MARKER.to_owned(),
+                    JsonValue::String(flow_id.to_string()),
+                );
+                props.insert(
+                    RUN_VARIABLES_MARKER.to_owned(),
+                    serde_json::to_value(&variables)?,
+                );
+                props.insert(DRY_RUN_MARKER.to_owned(), JsonValue::Bool(options.dry_run));
+
+                if let Some(version) = options.version {
+                    props.insert(FLOW_VERSION_MARKER.to_owned(), JsonValue::from(version));
+                }
+
+                // the log graph shares its id with the run so that it can be found again
+                db.create_graph_with_id(run_id, props).await?;
+
+                // let timestamp = chrono::offset::Utc::now().timestamp_millis();
+                // let timestamp = JsonValue::Number(serde_json::Number::from(timestamp));
+                let timestamp = chrono::offset::Utc::now()
+                    .format("%Y-%m-%d %H%M UTC")
+                    .to_string();
+
+                let timestamp = JsonValue::String(timestamp);
+
+                let mut props = Properties::default();
+
+                props.insert("timestamp".to_owned(), timestamp);
+                props.insert(
+                    RUN_ID_MARKER.to_owned(),
+                    JsonValue::String(run_id.to_string()),
+                );
+
+                db.create_edge(
+                    CreateEdge {
+                        from: flow_id,
+                        to: run_id,
+                        properties: props,
+                    },
+                    flow_id,
+                )
+                .await?;
+
+                (run_id, HashMap::new())
+            }
+        };
+
+        let resuming = !log_nodes.is_empty();
+
+        let log_graph_id = run_id;
 
         let mut contexts = HashMap::new();
 
         for node in graph.nodes.iter() {
             if let Some(cfg) = node.properties.get(CTX_MARKER) {
-                let cfg: commands::solana::Config = serde_json::from_value(cfg.clone()).unwrap();
+                let mut cfg: commands::solana::Config = serde_json::from_value(cfg.clone())?;
 
-                let ctx = Arc::new(commands::solana::Ctx::new(cfg, db.clone())?);
+                if let Some(name) = &cfg.solana_net_variable {
+                    let solana_net = variables
+                        .get(name)
+                        .ok_or_else(|| Error::UnknownFlowVariable(name.clone()))?;
+
+                    cfg.solana_net = serde_json::from_value(solana_net.clone().try_into()?)?;
+                }
+
+                let ctx = Arc::new(commands::solana::Ctx::with_limits(
+                    cfg,
+                    db.clone(),
+                    engine.limiter.clone(),
+                    engine.metrics.clone(),
+                )?);
 
                 contexts.insert(node.node_id, ctx);
             }
@@ -132,7 +616,12 @@ impl FlowContext {
                 None => continue,
             };
 
-            let cfg: commands::Config = serde_json::from_value(cfg).unwrap();
+            let cache = cache::read_policy(&node.properties)?.map(|policy| NodeCache {
+                policy,
+                command: cfg.clone(),
+            });
+
+            let cfg: commands::Config = serde_json::from_value(cfg)?;
 
             let cmd = match cfg {
                 commands::Config::Simple(simple) => Command::Simple(simple),
@@ -141,7 +630,10 @@ impl FlowContext {
                     for edge in node.inbound_edges.iter() {
                         let props = db.read_edge_properties(*edge).await?;
                         if props.get(CTX_EDGE_MARKER).is_some() {
-                            ctx = Some(contexts.get(&edge.from).unwrap().clone());
+                            let ctx_node =
+                                contexts.get(&edge.from).ok_or(Error::NoContextForCommand)?;
+
+                            ctx = Some(ctx_node.clone());
 
                             break;
                         }
@@ -156,24 +648,38 @@ impl FlowContext {
             let name = node
                 .properties
                 .get(COMMAND_NAME_MARKER)
-                .unwrap()
-                .as_str()
-                .unwrap()
+                .and_then(JsonValue::as_str)
+                .ok_or_else(|| {
+                    Error::InvalidFlowDefinition(format!("node {} has no name", node.node_id))
+                })?
                 .to_owned();
 
-            let mut props = Properties::new();
+            let log_node_id = match log_nodes.remove(&node.node_id) {
+                Some(log_node_id) => log_node_id,
+                None => {
+                    let mut props = Properties::new();
 
-            props.insert(
-                "original_props".to_owned(),
-                JsonValue::Object(node.properties.clone()),
-            );
+                    props.insert(
+                        "original_props".to_owned(),
+                        JsonValue::Object(node.properties.clone()),
+                    );
 
-            props.insert(
-                "original_node_id".to_owned(),
-                JsonValue::String(node.node_id.to_string()),
-            );
+                    props.insert(
+                        "original_node_id".to_owned(),
+                        JsonValue::String(node.node_id.to_string()),
+                    );
+
+                    let (_, log_node_id) = db.create_node((log_graph_id, props)).await?;
+
+                    log_node_id
+                }
+            };
 
-            let (_, log_node_id) = db.create_node((log_graph_id, props)).await.unwrap();
+            let merge = input::read_merge_strategies(&node.properties)?;
+
+            let flow_outputs = interface::read_bindings(&node.properties, FLOW_OUTPUTS_MARKER)?;
+
+            let breakpoint = debug::read_breakpoint(&node.properties);
 
             nodes.insert(
                 node.node_id,
@@ -181,6 +687,10 @@ impl FlowContext {
                     name,
                     inputs: HashMap::new(),
                     outputs: HashMap::new(),
+                    merge,
+                    flow_outputs,
+                    breakpoint,
+                    cache,
                     cmd,
                     log_node_id,
                 },
@@ -189,72 +699,115 @@ impl FlowContext {
 
         let mut start_nodes = Vec::new();
 
+        // inputs with a merge strategy share one channel between all of their edges
+        let mut merged_inputs: HashMap<(NodeId, String), Sender<Value>> = HashMap::new();
+
         for node in graph.nodes.iter() {
             if node.properties.get(COMMAND_MARKER).is_none() {
                 continue;
             }
 
             for edge in node.outbound_edges.iter() {
-                db.create_edge(
-                    CreateEdge {
-                        from: nodes.get(&edge.from).unwrap().log_node_id,
-                        to: nodes.get(&edge.to).unwrap().log_node_id,
-                        properties: Default::default(),
-                    },
-                    log_graph_id,
-                )
-                .await
-                .unwrap();
+                let invalid_edge = |reason: &str| {
+                    Error::InvalidFlowDefinition(format!(
+                        "edge from {} to {} {}",
+                        edge.from, edge.to, reason
+                    ))
+                };
 
-                let properties = db
-                    .execute(Action::Query(QueryKind::ReadEdgeProperties(*edge)))
-                    .await
-                    .unwrap()
-                    .into_properties()
-                    .unwrap();
+                let (from, to) = match (nodes.get(&edge.from), nodes.get(&edge.to)) {
+                    (Some(from), Some(to)) => (from.log_node_id, to.log_node_id),
+                    _ => return Err(invalid_edge("connects a node without a command")),
+                };
+
+                if !resuming {
+                    db.create_edge(
+                        CreateEdge {
+                            from,
+                            to,
+                            properties: Default::default(),
+                        },
+                        log_graph_id,
+                    )
+                    .await?;
+                }
+
+                let properties = db.read_edge_properties(*edge).await?;
 
                 let input_arg_name = properties
                     .get(INPUT_ARG_NAME_MARKER)
-                    .unwrap()
-                    .as_str()
-                    .unwrap();
+                    .and_then(JsonValue::as_str)
+                    .ok_or_else(|| invalid_edge("has no input name"))?;
 
                 let output_arg_name = properties
                     .get(OUTPUT_ARG_NAME_MARKER)
-                    .unwrap()
-                    .as_str()
-                    .unwrap();
-
-                let (tx, rx) = mpsc::unbounded_channel();
+                    .and_then(JsonValue::as_str)
+                    .ok_or_else(|| invalid_edge("has no output name"))?;
 
                 use std::collections::hash_map::Entry;
 
-                let outputs = &mut nodes.get_mut(&edge.from).unwrap().outputs;
+                let target = nodes
+                    .get_mut(&edge.to)
+                    .ok_or_else(|| invalid_edge("connects a node without a command"))?;
 
-                match outputs.entry(output_arg_name.to_owned()) {
-                    Entry::Occupied(mut entry) => entry.get_mut().push(tx),
-                    Entry::Vacant(entry) => {
-                        entry.insert(vec![tx]);
+                let tx = match merged_inputs.get(&(edge.to, input_arg_name.to_owned())) {
+                    Some(tx) => {
+                        target.inputs.get_mut(input_arg_name).unwrap().add_edge();
+                        tx.clone()
                     }
-                }
+                    None => {
+                        let (tx, rx) = mpsc::unbounded_channel();
 
-                let overridden = nodes
-                    .get_mut(&edge.to)
-                    .unwrap()
-                    .inputs
-                    .insert(input_arg_name.to_owned(), rx);
+                        let merge = target.merge.get(input_arg_name).copied();
 
-                if overridden.is_some() {
-                    return Err(Error::MultipleOutputsToSameInput);
+                        let input =
+                            FlowInput::new(rx, merge.unwrap_or(MergeStrategy::FirstArrival));
+
+                        if target
+                            .inputs
+                            .insert(input_arg_name.to_owned(), input)
+                            .is_some()
+                        {
+                            return Err(Error::MultipleOutputsToSameInput);
+                        }
+
+                        if merge.is_some() {
+                            merged_inputs.insert((edge.to, input_arg_name.to_owned()), tx.clone());
+                        }
+
+                        tx
+                    }
+                };
+
+                let coerce = properties
+                    .get(EDGE_COERCE_MARKER)
+                    .map(|kind| serde_json::from_value(kind.clone()))
+                    .transpose()?;
+
+                let output = OutputEdge { tx, coerce };
+
+                let outputs = &mut nodes
+                    .get_mut(&edge.from)
+                    .ok_or_else(|| invalid_edge("connects a node without a command"))?
+                    .outputs;
+
+                match outputs.entry(output_arg_name.to_owned()) {
+                    Entry::Occupied(mut entry) => entry.get_mut().push(output),
+                    Entry::Vacant(entry) => {
+                        entry.insert(vec![output]);
+                    }
                 }
             }
             if node.properties.contains_key(START_NODE_MARKER) {
                 let (tx, rx) = mpsc::unbounded_channel();
                 nodes
                     .get_mut(&node.node_id)
-                    .unwrap()
+                    .ok_or(Error::NodeNotInFlow(node.node_id))?
                     .inputs
-                    .insert("STARTER_INPUT_MARKER".into(), rx);
+                    .insert(
+                        "STARTER_INPUT_MARKER".into(),
+                        FlowInput::new(rx, MergeStrategy::FirstArrival),
+                    );
                 start_nodes.push(tx);
             }
         }
@@ -262,323 +815,1026 @@ impl FlowContext {
         Ok(Flow {
             start_nodes,
             nodes,
-            log_graph_id,
             run_id,
+            env: Env {
+                run_id,
+                flow_id,
+                variables,
+                dry_run: options.dry_run,
+                debug: options.debug,
+                simulations: Default::default(),
+                rpc_url: None,
+                engine,
+                log: None,
+                node: None,
+                approvals: Approvals::default(),
+            },
         })
     }
 
+    // `inputs` holds the values of flow inputs and overrides of flow variables.
+    // every run reads the live graph, so edits of the flow apply to the next run
     pub async fn deploy_flow(
         &self,
         schedule: Schedule,
         flow_id: FlowId,
+        inputs: HashMap<String, Value>,
     ) -> Result<Option<RunId>, Error> {
-        self.undeploy_flow(flow_id).ok();
+        self.deploy_version(schedule, flow_id, None, inputs).await
+    }
 
-        let (send_stop_signal, stop_signal) = watch::channel(1u8);
+    // like deploy_flow, but runs a version published with publish_flow
+    #[tracing::instrument(skip(self, schedule, inputs))]
+    pub async fn deploy_version(
+        &self,
+        schedule: Schedule,
+        flow_id: FlowId,
+        version: Option<u64>,
+        inputs: HashMap<String, Value>,
+    ) -> Result<Option<RunId>, Error> {
+        let graph_id = version::graph_of(&self.engine.db, flow_id, version).await?;
 
-        let res = match schedule {
-            Schedule::Once => Self::run_flow(self.db.clone(), flow_id, stop_signal).await,
-            Schedule::Interval(period) => {
-                self.start_flow_with_interval(period, flow_id, stop_signal)
-                    .await?;
+        self.stop_schedule(flow_id).ok();
 
-                None
-            }
+        let options = RunOptions {
+            version,
+            ..Default::default()
         };
 
-        self.deployed.insert(flow_id, send_stop_signal);
+        match schedule {
+            Schedule::Once => {
+                // the replaced deployment isn't restored either
+                self.forget_deployment(flow_id).await?;
+
+                let (run_id, _) = Self::run_flow(
+                    self.engine.clone(),
+                    flow_id,
+                    HashMap::new(),
+                    inputs,
+                    options,
+                )
+                .await?;
+
+                Ok(Some(run_id))
+            }
+            Schedule::Recurring(mut schedule) => {
+                schedule.validate()?;
+
+                // pin the start so that ticks stay aligned after a restart
+                schedule.start.get_or_insert_with(chrono::Utc::now);
+
+                // fail the deployment rather than every run on an unknown variable
+                let graph = self.engine.db.read_graph(graph_id).await?;
+                let (_, overrides) = interface::split_inputs(&graph, inputs.clone())?;
+                variables::resolve(&self.engine.db, graph_id, overrides).await?;
+
+                // the schedule and version are kept by the registry, the flow keeps the
+                // state of the schedule
+                let persisted_inputs = serde_json::to_value(&inputs)?;
+
+                update_flow_props(&self.engine.db, flow_id, |props| {
+                    props.insert(SCHEDULE_RUN_COUNT_MARKER.to_owned(), JsonValue::from(0u64));
+                    props.insert(DEPLOY_INPUTS_MARKER.to_owned(), persisted_inputs);
+                })
+                .await?;
+
+                let registry = self.deployments_graph().await?;
+
+                let deployment = Deployment {
+                    flow_id,
+                    schedule,
+                    deployed_at: chrono::Utc::now(),
+                    version,
+                };
+
+                deployment::insert_deployment(&self.engine.db, registry, &deployment).await?;
+
+                self.start_flow_with_schedule(deployment, registry, 0, inputs);
 
-        Ok(res)
+                Ok(None)
+            }
+        }
+    }
+
+    // continues a schedule persisted by deploy_flow, e.g. after the process restarted
+    #[tracing::instrument(skip(self))]
+    pub async fn resume_flow(&self, flow_id: FlowId) -> Result<bool, Error> {
+        let registry = self.deployments_graph().await?;
+
+        let deployment = deployment::read_deployments(&self.engine.db, registry)
+            .await?
+            .into_iter()
+            .map(|(_, deployment)| deployment)
+            .find(|deployment| deployment.flow_id == flow_id);
+
+        match deployment {
+            Some(deployment) => {
+                self.resume_deployment(deployment, registry).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
 
-    async fn start_flow_with_interval(
+    async fn resume_deployment(
         &self,
-        period: Duration,
-        flow_id: FlowId,
-        mut stop_signal: watch::Receiver<u8>,
+        deployment: Deployment,
+        registry: GraphId,
     ) -> Result<(), Error> {
-        let mut interval = tokio::time::interval(period);
+        let props = self
+            .engine
+            .db
+            .read_node(deployment.flow_id)
+            .await?
+            .properties;
+
+        let run_count = props
+            .get(SCHEDULE_RUN_COUNT_MARKER)
+            .and_then(JsonValue::as_u64)
+            .unwrap_or(0);
+
+        let inputs = variables::read_persisted(&props, DEPLOY_INPUTS_MARKER)?;
 
-        let db = self.db.clone();
+        self.stop_schedule(deployment.flow_id).ok();
 
-        let stop_signal_c = stop_signal.clone();
+        self.start_flow_with_schedule(deployment, registry, run_count, inputs);
+
+        Ok(())
+    }
+
+    // the deployment is removed once its schedule has no ticks left
+    fn start_flow_with_schedule(
+        &self,
+        deployment: Deployment,
+        registry: GraphId,
+        mut run_count: u64,
+        inputs: HashMap<String, Value>,
+    ) {
+        use chrono::Utc;
+
+        let Deployment {
+            flow_id,
+            schedule,
+            deployed_at,
+            version,
+        } = deployment;
+
+        let options = RunOptions {
+            version,
+            ..Default::default()
+        };
+
+        let (send_stop_signal, mut stop_signal) = watch::channel(1u8);
+
+        let engine = self.engine.clone();
+        let deployed = self.deployed.clone();
+
+        let schedule_fut = async move {
+            let mut last_run: Option<(RunId, JoinHandle<HashMap<String, Value>>)> = None;
+            let mut last_tick = Utc::now();
+
+            // a new deployment first ticks at its start, which deploy_flow pins to the time of
+            // the deployment, so an interval schedule runs right away instead of a period later
+            let mut first_after = match schedule.start {
+                Some(start) if run_count == 0 => Some(start - chrono::Duration::milliseconds(1)),
+                _ => None,
+            };
 
-        let interval_fut = async move {
             loop {
-                interval.tick().await;
-                Self::run_flow(db.clone(), flow_id, stop_signal_c.clone()).await;
+                // ticks missed while a queued run waited aren't made up, so a slow run
+                // doesn't cause a burst of runs once it finishes
+                let after = first_after
+                    .take()
+                    .unwrap_or_else(|| last_tick.max(Utc::now()));
+
+                let next_tick = match schedule.next_after(after, run_count) {
+                    Ok(Some(next_tick)) => next_tick,
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!(%flow_id, error = %e, "failed to compute next tick of flow schedule");
+                        break;
+                    }
+                };
+
+                if let Ok(wait) = (next_tick - Utc::now()).to_std() {
+                    tokio::time::sleep(wait).await;
+                }
+
+                last_tick = next_tick;
+
+                if let Some((last_run_id, last_run_handle)) = last_run.take() {
+                    match schedule.overlap {
+                        OverlapPolicy::Skip if engine.runs.contains_key(&last_run_id) => {
+                            last_run = Some((last_run_id, last_run_handle));
+                            continue;
+                        }
+                        OverlapPolicy::Queue => {
+                            last_run_handle.await.ok();
+                        }
+                        _ => (),
+                    }
+                }
+
+                let run = Self::run_flow(
+                    engine.clone(),
+                    flow_id,
+                    HashMap::new(),
+                    inputs.clone(),
+                    options,
+                );
+
+                // runs which failed to start don't count towards max_runs
+                match run.await {
+                    Ok(run) => last_run = Some(run),
+                    Err(e) => {
+                        tracing::error!(%flow_id, error = %e, "failed to run flow");
+                        continue;
+                    }
+                }
+
+                run_count += 1;
+
+                if let Err(e) = update_flow_props(&engine.db, flow_id, |props| {
+                    props.insert(
+                        SCHEDULE_RUN_COUNT_MARKER.to_owned(),
+                        JsonValue::from(run_count),
+                    );
+                })
+                .await
+                {
+                    tracing::error!(%flow_id, error = %e, "failed to persist run count of flow schedule");
+                }
+            }
+
+            tracing::info!(%flow_id, "flow schedule finished");
+
+            deployed.remove_if(&flow_id, |_, handle| handle.deployed_at == deployed_at);
+
+            match deployment::remove_deployed_at(&engine.db, registry, flow_id, deployed_at).await {
+                Ok(true) => {
+                    if let Err(e) = clear_deploy_props(&engine.db, flow_id).await {
+                        tracing::error!(%flow_id, error = %e, "failed to clear finished flow schedule");
+                    }
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    tracing::error!(%flow_id, error = %e, "failed to remove finished deployment")
+                }
             }
         };
 
+        // inserted before the schedule can finish and remove it
+        self.deployed.insert(
+            flow_id,
+            ScheduleHandle {
+                deployed_at,
+                stop_signal: send_stop_signal,
+            },
+        );
+
         tokio::spawn(async move {
             tokio::select! {
-                _ = interval_fut => (),
+                _ = schedule_fut => (),
                 _ = stop_signal.changed() => (),
             }
         });
+    }
+
+    // runs the flow once and waits for it to finish, returning the values of its flow outputs.
+    // outputs of nodes which failed or were canceled are missing
+    #[tracing::instrument(skip(self, inputs))]
+    pub async fn call_flow(
+        &self,
+        flow_id: FlowId,
+        inputs: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, Error> {
+        let (_, outputs) = self
+            .run_to_completion(flow_id, inputs, RunOptions::default())
+            .await?;
+
+        Ok(outputs)
+    }
+
+    // starts a run without waiting for it to finish, its nodes can be followed with
+    // subscribe and get_run
+    #[tracing::instrument(skip(self, inputs))]
+    pub async fn start_flow(
+        &self,
+        flow_id: FlowId,
+        inputs: HashMap<String, Value>,
+        options: RunOptions,
+    ) -> Result<RunId, Error> {
+        let (run_id, _) = Self::run_flow(
+            self.engine.clone(),
+            flow_id,
+            HashMap::new(),
+            inputs,
+            options,
+        )
+        .await?;
+
+        Ok(run_id)
+    }
+
+    // like call_flow, but every transaction is simulated instead of sent. the simulation
+    // of each transaction is stored in the NodeRunRecord of its command
+    #[tracing::instrument(skip(self, inputs))]
+    pub async fn dry_run_flow(
+        &self,
+        flow_id: FlowId,
+        inputs: HashMap<String, Value>,
+    ) -> Result<(RunId, HashMap<String, Value>), Error> {
+        let options = RunOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+
+        self.run_to_completion(flow_id, inputs, options).await
+    }
+
+    // starts a run which pauses before every node with a BREAKPOINT_MARKER until it's
+    // continued or skipped, see paused_nodes
+    #[tracing::instrument(skip(self, inputs))]
+    pub async fn debug_flow(
+        &self,
+        flow_id: FlowId,
+        inputs: HashMap<String, Value>,
+        dry_run: bool,
+    ) -> Result<RunId, Error> {
+        let options = RunOptions {
+            dry_run,
+            debug: true,
+            ..Default::default()
+        };
+
+        let (run_id, _) = Self::run_flow(
+            self.engine.clone(),
+            flow_id,
+            HashMap::new(),
+            inputs,
+            options,
+        )
+        .await?;
+
+        Ok(run_id)
+    }
+
+    // nodes of a debugged run waiting at their breakpoint, with the inputs they'll run with
+    pub fn paused_nodes(&self, run_id: RunId) -> Result<Vec<PausedNode>, Error> {
+        let run = self.engine.runs.get(&run_id).ok_or(Error::RunDoesntExist)?;
+
+        let paused = run
+            .breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.node.clone())
+            .collect();
+
+        Ok(paused)
+    }
+
+    // runs a paused node, `overrides` replace the inputs with the same name
+    pub fn continue_node(
+        &self,
+        run_id: RunId,
+        node_id: NodeId,
+        overrides: HashMap<String, Value>,
+    ) -> Result<(), Error> {
+        self.resume_node(run_id, node_id, Resume::Continue(overrides))
+    }
+
+    // doesn't run a paused node, the nodes connected to its outputs are canceled
+    pub fn skip_node(&self, run_id: RunId, node_id: NodeId) -> Result<(), Error> {
+        self.resume_node(run_id, node_id, Resume::Skip)
+    }
+
+    fn resume_node(&self, run_id: RunId, node_id: NodeId, resume: Resume) -> Result<(), Error> {
+        let run = self.engine.runs.get(&run_id).ok_or(Error::RunDoesntExist)?;
+
+        let (_, breakpoint) = run
+            .breakpoints
+            .remove(&node_id)
+            .ok_or(Error::NodeNotPaused(node_id))?;
+
+        breakpoint.resume.send(resume).ok();
+
+        Ok(())
+    }
+
+    // Approval nodes of every run which haven't been decided yet
+    pub fn pending_approvals(&self) -> Vec<PendingApproval> {
+        self.engine
+            .runs
+            .iter()
+            .flat_map(|run| {
+                run.approvals
+                    .iter()
+                    .map(|request| request.pending.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    // the value of the node is passed to its "approved" output
+    pub fn approve(&self, run_id: RunId, node_id: NodeId) -> Result<(), Error> {
+        self.decide(run_id, node_id, Decision::Approve)
+    }
+
+    // the value of the node is passed to its "rejected" output
+    pub fn reject(&self, run_id: RunId, node_id: NodeId) -> Result<(), Error> {
+        self.decide(run_id, node_id, Decision::Reject)
+    }
+
+    fn decide(&self, run_id: RunId, node_id: NodeId, decision: Decision) -> Result<(), Error> {
+        let run = self.engine.runs.get(&run_id).ok_or(Error::RunDoesntExist)?;
+
+        let (_, request) = run
+            .approvals
+            .remove(&node_id)
+            .ok_or(Error::NodeNotAwaitingApproval(node_id))?;
+
+        request.decide.send(decision).ok();
 
         Ok(())
     }
 
+    // runs the flow with the options and waits for its flow outputs. a debug run never
+    // finishes unless something else continues its breakpoints
+    #[tracing::instrument(skip(self, inputs))]
+    pub async fn run_to_completion(
+        &self,
+        flow_id: FlowId,
+        inputs: HashMap<String, Value>,
+        options: RunOptions,
+    ) -> Result<(RunId, HashMap<String, Value>), Error> {
+        let (run_id, run) = Self::run_flow(
+            self.engine.clone(),
+            flow_id,
+            HashMap::new(),
+            inputs,
+            options,
+        )
+        .await?;
+
+        let outputs = run.await.map_err(|_| Error::RunAborted)?;
+
+        Ok((run_id, outputs))
+    }
+
+    // returns a handle which resolves to the flow outputs once every node of the run has
+    // finished. `inputs` are split into flow inputs and overrides of flow variables
     async fn run_flow(
-        db: Arc<dyn Datastore>,
+        engine: Engine,
         flow_id: FlowId,
-        stop_signal: watch::Receiver<u8>,
-    ) -> Option<RunId> {
+        mut start_inputs: HashMap<NodeId, HashMap<String, Value>>,
+        inputs: HashMap<String, Value>,
+        options: RunOptions,
+    ) -> Result<(RunId, JoinHandle<HashMap<String, Value>>), Error> {
+        let db = engine.db.clone();
+
+        let graph_id = version::graph_of(&db, flow_id, options.version).await?;
+
+        let graph = db.read_graph(graph_id).await?;
+
+        let (flow_inputs, overrides) = interface::split_inputs(&graph, inputs)?;
+
+        for (node_id, inputs) in flow_inputs {
+            start_inputs.entry(node_id).or_default().extend(inputs);
+        }
+
+        let variables = variables::resolve(&db, graph_id, overrides).await?;
+
+        let flow = Self::read_flow(engine, flow_id, graph, None, variables, options).await?;
+
+        let persisted = serde_json::to_value(&start_inputs)?;
+
+        update_flow_props(&db, flow.run_id, |props| {
+            props.insert(RUN_INPUTS_MARKER.to_owned(), persisted);
+        })
+        .await?;
+
+        Self::start_run(flow_id, flow, start_inputs, HashMap::new()).await
+    }
+
+    // continues a run which was interrupted, e.g. by the process crashing.
+    // nodes which checkpointed their outputs aren't executed again, their outputs are
+    // sent downstream as they were recorded. nodes that were running when the run was
+    // interrupted are executed again.
+    #[tracing::instrument(skip(self))]
+    pub async fn resume_run(&self, run_id: RunId) -> Result<(), Error> {
+        if self.engine.runs.contains_key(&run_id) {
+            return Err(Error::RunInProgress);
+        }
+
+        let log_graph = self.engine.db.read_graph(run_id).await?;
+
+        let run_props = self.engine.db.read_node(run_id).await?.properties;
+
+        let flow_id = run_props
+            .get(FLOW_ID_MARKER)
+            .and_then(JsonValue::as_str)
+            .map(Uuid::parse_str)
+            .ok_or(Error::RunDoesntExist)?
+            .map_err(sunshine_core::Error::from)?;
+
+        let mut log_nodes = HashMap::new();
+        let mut completed = HashMap::new();
+
+        for log_node in log_graph.nodes {
+            let node_id = match log_node
+                .properties
+                .get("original_node_id")
+                .and_then(JsonValue::as_str)
+                .and_then(|id| Uuid::parse_str(id).ok())
+            {
+                Some(node_id) => node_id,
+                None => continue,
+            };
+
+            if let Some(outputs) = log_node.properties.get(CHECKPOINT_MARKER) {
+                let outputs: HashMap<String, Value> = serde_json::from_value(outputs.clone())?;
+                completed.insert(node_id, outputs);
+            }
+
+            log_nodes.insert(node_id, log_node.node_id);
+        }
+
+        let variables = variables::read_persisted(&run_props, RUN_VARIABLES_MARKER)?;
+
+        let start_inputs = match run_props.get(RUN_INPUTS_MARKER) {
+            Some(inputs) => serde_json::from_value(inputs.clone())?,
+            None => HashMap::new(),
+        };
+
+        let dry_run = run_props
+            .get(DRY_RUN_MARKER)
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(false);
+
+        // a resumed run doesn't stop at breakpoints
+        let options = RunOptions {
+            dry_run,
+            debug: false,
+            version: version::read_version(&run_props, FLOW_VERSION_MARKER),
+        };
+
+        let graph_id = version::graph_of(&self.engine.db, flow_id, options.version).await?;
+
+        let graph = self.engine.db.read_graph(graph_id).await?;
+
+        let flow = Self::read_flow(
+            self.engine.clone(),
+            flow_id,
+            graph,
+            Some((run_id, log_nodes)),
+            variables,
+            options,
+        )
+        .await?;
+
+        Self::start_run(flow_id, flow, start_inputs, completed).await?;
+
+        Ok(())
+    }
+
+    async fn start_run(
+        flow_id: FlowId,
+        flow: Flow,
+        start_inputs: HashMap<NodeId, HashMap<String, Value>>,
+        completed: HashMap<NodeId, HashMap<String, Value>>,
+    ) -> Result<(RunId, JoinHandle<HashMap<String, Value>>), Error> {
         use std::time::Instant;
 
         let Flow {
-            nodes,
+            mut nodes,
             start_nodes,
-            log_graph_id,
             run_id,
-        } = match Self::read_flow(db.clone(), flow_id).await {
-            Ok(flow) => flow,
-            Err(e) => {
-                eprintln!("failed to read flow: {}", e);
-                return None;
+            env,
+        } = flow;
+
+        let Engine { db, runs, .. } = env.engine.clone();
+
+        let mut start_values = Vec::new();
+
+        for (node_id, inputs) in start_inputs {
+            let node = nodes
+                .get_mut(&node_id)
+                .ok_or(Error::NodeNotInFlow(node_id))?;
+
+            for (name, value) in inputs {
+                let (tx, rx) = mpsc::unbounded_channel();
+
+                let input = FlowInput::new(rx, MergeStrategy::FirstArrival);
+
+                if node.inputs.insert(name, input).is_some() {
+                    return Err(Error::MultipleOutputsToSameInput);
+                }
+
+                start_values.push((tx, value));
             }
-        };
+        }
+
+        let (send_stop_signal, stop_signal) = watch::channel(1u8);
+
+        let breakpoints = Breakpoints::default();
+        let approvals = env.approvals.clone();
+
+        runs.insert(
+            run_id,
+            RunHandle {
+                flow_id,
+                stop_signal: send_stop_signal,
+                breakpoints: breakpoints.clone(),
+                approvals: approvals.clone(),
+            },
+        );
+
+        // values of the flow outputs, returned when the run finishes
+        let flow_outputs = Arc::new(DashMap::new());
+
+        // completed nodes only replay their recorded outputs
+        let mut replayed = Vec::new();
+
+        for (node_id, outputs) in completed {
+            if let Some(node) = nodes.remove(&node_id) {
+                replayed.push((node, outputs));
+            }
+        }
+
+        let run_span = tracing::info_span!(
+            "run",
+            %flow_id,
+            %run_id,
+            dry_run = env.dry_run,
+            debug = env.debug
+        );
+
+        env.engine.metrics.run_started(flow_id);
+
+        let mut node_handles = Vec::with_capacity(nodes.len());
+
+        for (node_id, node) in nodes {
+            let kind = format!("{:?}", node.cmd.kind());
+
+            let span = tracing::info_span!(
+                parent: &run_span,
+                "node",
+                %flow_id,
+                %run_id,
+                %node_id,
+                %kind,
+                name = %node.name
+            );
+
+            let mut stop_signal = stop_signal.clone();
+
+            // every node gets its own env so that its simulations can be told apart
+            let env = Env {
+                simulations: Default::default(),
+                rpc_url: match &node.cmd {
+                    Command::Solana(solana) => Some(solana.ctx.rpc_url().to_string()),
+                    Command::Simple(_) => None,
+                },
+                node: Some((node_id, node.name.clone())),
+                ..env.clone()
+            };
+            let flow_outputs = flow_outputs.clone();
+            let breakpoints = breakpoints.clone();
+
+            node_handles.push(tokio::spawn(
+                async move {
+                    let log = match NodeLog::open(
+                        &env.engine,
+                        flow_id,
+                        run_id,
+                        node.log_node_id,
+                        node_id,
+                        node.name.clone(),
+                        kind.clone(),
+                    )
+                    .await
+                    {
+                        Ok(log) => Arc::new(tokio::sync::Mutex::new(log)),
+                        Err(e) => {
+                            tracing::error!(error = %e, "failed to read log node");
+                            return None;
+                        }
+                    };
+
+                    let env = Env {
+                        log: Some(log.clone()),
+                        ..env
+                    };
+
+                    let cancel_log = log.clone();
+
+                    let cmd_fut = async move {
+                        let mut inputs = HashMap::new();
+
+                        log.lock().await.set_state(RunState::WaitingInputs).await;
+
+                        for (name, mut input) in node.inputs {
+                            let input = match input.recv().await {
+                                Some(input) => match input {
+                                    Value::Cancel => {
+                                        for (_, edges) in node.outputs {
+                                            for edge in edges {
+                                                edge.send(Value::Cancel);
+                                            }
+                                        }
+                                        log.lock().await.set_state(RunState::Canceled).await;
+                                        return;
+                                    }
+                                    v => v,
+                                },
+                                None => {
+                                    log.lock()
+                                        .await
+                                        .set_state(RunState::Failed(
+                                            0,
+                                            "can't receive input, quitting".into(),
+                                        ))
+                                        .await;
+                                    return;
+                                }
+                            };
+                            inputs.insert(name, input);
+                        }
+
+                        if env.debug && node.breakpoint {
+                            log.lock().await.set_state(RunState::Paused).await;
+
+                            let paused = PausedNode {
+                                node_id,
+                                name: node.name.clone(),
+                                inputs,
+                            };
+
+                            inputs = match debug::pause(&breakpoints, paused).await {
+                                Some(inputs) => inputs,
+                                None => {
+                                    for (_, edges) in node.outputs {
+                                        for edge in edges {
+                                            edge.send(Value::Cancel);
+                                        }
+                                    }
+                                    log.lock().await.set_state(RunState::Skipped).await;
+                                    return;
+                                }
+                            };
+                        }
+
+                        // dry runs don't send transactions, so their outputs aren't reused
+                        // or cached
+                        let cache_key = match &node.cache {
+                            Some(cache) if !env.dry_run => {
+                                match cache.key(env.rpc_url.as_deref(), &inputs) {
+                                    Ok(key) => Some((cache.policy, key)),
+                                    Err(e) => {
+                                        tracing::warn!(error = %e, "failed to key the cache");
+                                        None
+                                    }
+                                }
+                            }
+                            _ => None,
+                        };
 
-        for (_, node) in nodes {
-            let db = db.clone();
+                        let cached = match &cache_key {
+                            Some((_, key)) => {
+                                match env.engine.cache.get(&env.engine.db, key).await {
+                                    Ok(cached) => cached,
+                                    Err(e) => {
+                                        tracing::warn!(error = %e, "failed to read the cache");
+                                        None
+                                    }
+                                }
+                            }
+                            None => None,
+                        };
 
-            let mut stop_signal = stop_signal.clone();
+                        let (outputs, simulations, start) = match cached {
+                            Some(outputs) => {
+                                log.lock().await.reuse(&inputs).await;
 
-            let cmd_fut = async move {
-                let mut inputs = HashMap::new();
+                                tracing::info!("reusing cached outputs");
 
-                let mut props = db.read_node(node.log_node_id).await.unwrap().properties;
+                                env.engine.metrics.cache_hit(&kind);
 
-                props.insert(
-                    "kind".to_owned(),
-                    JsonValue::String(format!("{:#?}", node.cmd.kind())),
-                );
+                                (outputs, Vec::new(), Instant::now())
+                            }
+                            None => {
+                                // a node waiting for the limits stays in WaitingInputs
+                                let limiter = env.engine.limiter.clone();
+                                let _permit = limiter.command(&node.cmd).await;
+                                let _rpc_permit = match &env.rpc_url {
+                                    Some(rpc_url) if !limits::waits(&node.cmd) => {
+                                        limiter.concurrency(rpc_url).await
+                                    }
+                                    _ => None,
+                                };
+
+                                log.lock().await.start(&inputs).await;
+
+                                tracing::info!("running command");
+
+                                let start = Instant::now();
+
+                                let res = run_command(&node.cmd, inputs, &env).await;
+
+                                env.engine.metrics.node_finished(
+                                    &kind,
+                                    start.elapsed(),
+                                    res.is_err(),
+                                );
+
+                                let simulations =
+                                    std::mem::take(&mut *env.simulations.lock().unwrap());
+
+                                let outputs = match res {
+                                    Ok(outputs) => outputs,
+                                    Err(e) => {
+                                        let error = Error::Node {
+                                            node: node.name.clone(),
+                                            source: Box::new(e),
+                                        };
+
+                                        tracing::error!(error = %error, "command failed");
+
+                                        log.lock()
+                                            .await
+                                            .fail(
+                                                simulations,
+                                                RunState::Failed(
+                                                    start.elapsed().as_millis() as u64,
+                                                    error.to_string(),
+                                                ),
+                                            )
+                                            .await;
+                                        return;
+                                    }
+                                };
 
-                props.insert("name".to_owned(), JsonValue::String(node.name.clone()));
+                                if let Some((policy, key)) = &cache_key {
+                                    let cache = &env.engine.cache;
 
-                props.insert(
-                    "state".to_owned(),
-                    serde_json::to_value(&RunState::WaitingInputs).unwrap(),
-                );
+                                    if let Err(e) =
+                                        cache.put(&env.engine.db, key, *policy, &outputs).await
+                                    {
+                                        tracing::warn!(error = %e, "failed to cache outputs");
+                                    }
+                                }
 
-                if let Err(e) = db
-                    .update_node((node.log_node_id, props), log_graph_id)
-                    .await
-                {
-                    eprintln!("failed to update log node for command: {}", e);
-                };
+                                (outputs, simulations, start)
+                            }
+                        };
 
-                let change_state = |db: Arc<dyn Datastore>, state: RunState| async move {
-                    let mut props = db.read_node(node.log_node_id).await.unwrap().properties;
+                        let mut log = log.lock().await;
 
-                    props.insert("state".to_owned(), serde_json::to_value(state).unwrap());
+                        log.finish(&outputs, simulations).await;
 
-                    if let Err(e) = db
-                        .update_node((node.log_node_id, props.clone()), log_graph_id)
-                        .await
-                    {
-                        eprintln!("failed to update logs for command: {}", e);
-                    }
-                };
+                        record_flow_outputs(&node.flow_outputs, &outputs, &flow_outputs);
 
-                for (name, mut rx) in node.inputs {
-                    let input = match rx.recv().await {
-                        Some(input) => match input {
-                            Value::Cancel => {
-                                for (_, txs) in node.outputs {
-                                    for tx in txs {
-                                        tx.send(Value::Cancel).ok();
-                                    }
-                                }
-                                change_state(db.clone(), RunState::Canceled).await;
-                                return;
-                            }
-                            v => v,
-                        },
-                        None => {
-                            change_state(
-                                db.clone(),
-                                RunState::Failed(0, "can't receive input, quitting".into()),
-                            )
-                            .await;
+                        if let Err(e) = send_outputs(node.outputs, &outputs) {
+                            log.set_state(RunState::Failed(start.elapsed().as_millis() as u64, e))
+                                .await;
                             return;
                         }
-                    };
-                    inputs.insert(name, input);
-                }
 
-                {
-                    let mut props = db.read_node(node.log_node_id).await.unwrap().properties;
+                        tracing::info!(
+                            elapsed_ms = start.elapsed().as_millis() as u64,
+                            "command finished"
+                        );
 
-                    props.insert("inputs".to_owned(), serde_json::to_value(&inputs).unwrap());
+                        log.set_state(RunState::Success(start.elapsed().as_millis() as u64))
+                            .await;
+                    };
 
-                    if let Err(e) = db
-                        .update_node((node.log_node_id, props.clone()), log_graph_id)
-                        .await
-                    {
-                        eprintln!("failed to update logs for command: {}", e);
+                    tokio::select! {
+                        _ = cmd_fut => (),
+                        _ = stop_signal.changed() => {
+                            tracing::info!("node canceled");
+                            cancel_log.lock().await.set_state(RunState::Canceled).await;
+                        }
                     }
+
+                    let state = cancel_log.lock().await.state();
+
+                    state
                 }
+                .instrument(span),
+            ));
+        }
 
-                change_state(db.clone(), RunState::Running).await;
+        for (node, outputs) in replayed {
+            record_flow_outputs(&node.flow_outputs, &outputs, &flow_outputs);
 
-                let start = Instant::now();
+            if let Err(e) = send_outputs(node.outputs, &outputs) {
+                tracing::error!(%run_id, node = %node.name, error = %e, "failed to replay outputs");
+            }
+        }
 
-                let outputs = match run_command(&node.cmd, inputs.clone()).await {
-                    Ok(outputs) => outputs,
-                    Err(e) => {
-                        change_state(
-                            db.clone(),
-                            RunState::Failed(
-                                start.elapsed().as_millis() as u64,
-                                format!("failed to run command: {:#?}", e),
-                            ),
-                        )
-                        .await;
-                        return;
-                    }
-                };
+        for node in start_nodes {
+            node.send(Value::Empty).ok();
+        }
 
-                if let Some(output) = outputs.get("__print_output") {
-                    let mut props = db.read_node(node.log_node_id).await.unwrap().properties;
-                    let output = match output {
-                        Value::String(output) => output,
-                        _ => unreachable!(),
-                    };
+        // inputs of completed nodes aren't received anymore
+        for (tx, value) in start_values {
+            tx.send(value).ok();
+        }
 
-                    props.insert(
-                        "__print_output".to_owned(),
-                        JsonValue::String(output.clone()),
-                    );
+        let metrics = env.engine.metrics.clone();
 
-                    if let Err(e) = db
-                        .update_node((node.log_node_id, props.clone()), log_graph_id)
-                        .await
-                    {
-                        eprintln!("failed to update logs for command: {}", e);
-                    }
-                }
+        let run_handle = tokio::spawn(
+            async move {
+                let states = futures::future::join_all(node_handles).await;
+                runs.remove(&run_id);
 
-                let mut node_outputs = node.outputs;
+                let failed = states
+                    .iter()
+                    .any(|state| matches!(state, Ok(Some(RunState::Failed(..)))));
 
-                if let Some(_) = outputs.get("__true_branch") {
-                    match node_outputs.remove("__true_branch") {
-                        Some(txs) => {
-                            for tx in txs {
-                                tx.send(Value::Empty).ok();
-                            }
-                        }
-                        None => {
-                            change_state(
-                                db.clone(),
-                                RunState::Failed(
-                                    start.elapsed().as_millis() as u64,
-                                    "output with name __true_branch not found".to_owned(),
-                                ),
-                            )
-                            .await;
-                            return;
-                        }
-                    }
-                    match node_outputs.remove("__false_branch") {
-                        Some(txs) => {
-                            for tx in txs {
-                                tx.send(Value::Cancel).ok();
-                            }
-                        }
-                        None => {
-                            change_state(
-                                db.clone(),
-                                RunState::Failed(
-                                    start.elapsed().as_millis() as u64,
-                                    "output with name __false_branch not found".to_owned(),
-                                ),
-                            )
-                            .await;
-                            return;
-                        }
-                    }
-                } else if let Some(_) = outputs.get("__false_branch") {
-                    match node_outputs.remove("__false_branch") {
-                        Some(txs) => {
-                            for tx in txs {
-                                tx.send(Value::Empty).ok();
-                            }
-                        }
-                        None => {
-                            change_state(
-                                db.clone(),
-                                RunState::Failed(
-                                    start.elapsed().as_millis() as u64,
-                                    "output with name __false_branch not found".to_owned(),
-                                ),
-                            )
-                            .await;
-                            return;
-                        }
-                    }
-                    match node_outputs.remove("__true_branch") {
-                        Some(txs) => {
-                            for tx in txs {
-                                tx.send(Value::Cancel).ok();
-                            }
-                        }
-                        None => {
-                            change_state(
-                                db.clone(),
-                                RunState::Failed(
-                                    start.elapsed().as_millis() as u64,
-                                    "output with name __true_branch not found".to_owned(),
-                                ),
-                            )
-                            .await;
-                            return;
-                        }
-                    }
-                } else {
-                    for (name, txs) in node_outputs.into_iter() {
-                        let val = match outputs.get(&name) {
-                            Some(val) => val.clone(),
-                            None => {
-                                change_state(
-                                    db.clone(),
-                                    RunState::Failed(
-                                        start.elapsed().as_millis() as u64,
-                                        format!("output with name {} not found", name),
-                                    ),
-                                )
-                                .await;
-                                return;
-                            }
-                        };
-                        for tx in txs {
-                            tx.send(val.clone()).ok();
+                metrics.run_finished(flow_id, failed);
+
+                tracing::info!(failed, "run finished");
+
+                let outputs: HashMap<String, Value> = flow_outputs
+                    .iter()
+                    .map(|output| (output.key().clone(), output.value().clone()))
+                    .collect();
+
+                match serde_json::to_value(&outputs) {
+                    Ok(persisted) => {
+                        if let Err(e) = update_flow_props(&db, run_id, |props| {
+                            props.insert(RUN_OUTPUTS_MARKER.to_owned(), persisted);
+                        })
+                        .await
+                        {
+                            tracing::error!(error = %e, "failed to store outputs of run");
                         }
                     }
+                    Err(e) => tracing::error!(error = %e, "failed to serialize outputs of run"),
                 }
 
-                change_state(
-                    db.clone(),
-                    RunState::Success(start.elapsed().as_millis() as u64),
-                )
-                .await;
-            };
+                outputs
+            }
+            .instrument(run_span),
+        );
 
-            tokio::spawn(async move {
-                tokio::select! {
-                    _ = cmd_fut => (),
-                    _ = stop_signal.changed() => (),
-                }
-            });
-        }
+        Ok((run_id, run_handle))
+    }
+}
 
-        for node in start_nodes {
-            node.send(Value::Empty).unwrap();
+// copies the outputs of a command which are bound to flow outputs
+fn record_flow_outputs(
+    bindings: &HashMap<String, String>,
+    outputs: &HashMap<String, Value>,
+    flow_outputs: &DashMap<String, Value>,
+) {
+    for (output, name) in bindings {
+        if let Some(value) = outputs.get(output) {
+            flow_outputs.insert(name.clone(), value.clone());
         }
+    }
+}
 
-        Some(run_id)
+// sends the outputs of a command to the inputs connected to it
+fn send_outputs(
+    node_outputs: HashMap<String, Vec<OutputEdge>>,
+    outputs: &HashMap<String, Value>,
+) -> Result<(), String> {
+    for (name, edges) in node_outputs.into_iter() {
+        let val = outputs
+            .get(&name)
+            .ok_or_else(|| format!("output with name {} not found", name))?;
+
+        for edge in edges {
+            edge.send(val.clone());
+        }
     }
+
+    Ok(())
+}
+
+// state of the schedule of a deployment, kept on the root of the flow
+async fn clear_deploy_props(db: &Arc<dyn Datastore>, flow_id: FlowId) -> Result<(), Error> {
+    update_flow_props(db, flow_id, |props| {
+        props.remove(SCHEDULE_RUN_COUNT_MARKER);
+        props.remove(DEPLOY_INPUTS_MARKER);
+    })
+    .await
+}
+
+async fn update_flow_props(
+    db: &Arc<dyn Datastore>,
+    flow_id: FlowId,
+    update: impl FnOnce(&mut Properties),
+) -> Result<(), Error> {
+    let mut props = db.read_node(flow_id).await?.properties;
+
+    update(&mut props);
+
+    db.update_node((flow_id, props), flow_id).await?;
+
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -588,11 +1844,19 @@ pub enum RunState {
     Failed(u64, String),
     Success(u64),
     Canceled,
+    // waiting at a breakpoint of a debugged run
+    Paused,
+    // an Approval node waiting until it's approved or rejected
+    AwaitingApproval,
+    Skipped,
+    // a timer node waiting until the deadline
+    Waiting(Deadline),
 }
 
-pub enum Schedule {
-    Once,
-    Interval(Duration),
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Deadline {
+    Time(chrono::DateTime<chrono::Utc>),
+    Slot(u64),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, derive_more::Display)]
@@ -643,6 +1907,10 @@ pub enum Value {
     Operator(Operator),
     #[display(fmt = "{}", _0)]
     Json(JsonValueWrapper),
+    #[display(fmt = "{:?}", _0)]
+    Array(Vec<Value>),
+    #[display(fmt = "{:?}", _0)]
+    Map(HashMap<String, Value>),
     #[display(fmt = "cancel")]
     Cancel,
 }
@@ -657,17 +1925,26 @@ impl TryFrom<JsonValue> for Value {
             JsonValue::Number(n) => {
                 if let Some(v) = n.as_u64() {
                     Value::U64(v)
-                } else if let Some(v) = n.as_f64() {
-                    Value::F64(v)
                 } else if let Some(v) = n.as_i64() {
                     Value::I64(v)
+                } else if let Some(v) = n.as_f64() {
+                    Value::F64(v)
                 } else {
                     return Err(Error::IncompatibleJson(JsonValue::Number(n).into()));
                 }
             }
-            JsonValue::String(s) => Value::String(s.clone()),
-            JsonValue::Array(_) => return Err(Error::IncompatibleJson(json.into())),
-            JsonValue::Object(_) => return Err(Error::IncompatibleJson(json.into())),
+            JsonValue::String(s) => Value::String(s),
+            JsonValue::Array(array) => Value::Array(
+                array
+                    .into_iter()
+                    .map(Value::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            JsonValue::Object(map) => Value::Map(
+                map.into_iter()
+                    .map(|(k, v)| Ok((k, Value::try_from(v)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
         };
 
         Ok(v)
@@ -714,6 +1991,17 @@ impl TryFrom<Value> for JsonValue {
             Value::NftMetadata(val) => serde_json::to_value(val).unwrap(),
             Value::Operator(op) => JsonValue::String(format!("{:?}", op)),
             Value::Json(json) => json.into(),
+            Value::Array(array) => JsonValue::Array(
+                array
+                    .into_iter()
+                    .map(JsonValue::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(map) => JsonValue::Object(
+                map.into_iter()
+                    .map(|(k, v)| Ok((k, JsonValue::try_from(v)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
             Value::Cancel => JsonValue::Null,
         };
 
@@ -843,30 +2131,14 @@ impl Value {
             Value::NftMetadata(_) => ValueKind::NftMetadata,
             Value::Operator(_) => ValueKind::Operator,
             Value::Json(_) => ValueKind::Json,
+            Value::Array(_) => ValueKind::Array,
+            Value::Map(_) => ValueKind::Map,
             Value::Cancel => ValueKind::Cancel,
         }
     }
 }
 
-impl TryInto<Pubkey> for Value {
-    type Error = Error;
-
-    fn try_into(self) -> Result<Pubkey, Error> {
-        let res = match self {
-            Value::Keypair(kp) => {
-                let kp: Keypair = kp.into();
-                kp.pubkey()
-            }
-            Value::Pubkey(p) => p.into(),
-            Value::String(s) => Pubkey::from_str(s.as_str())?,
-            _ => return Err(Error::ValueIntoError(self.kind(), "Pubkey".to_owned())),
-        };
-
-        Ok(res)
-    }
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, ParseDisplay /* , derive_more::Display */)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ParseDisplay)]
 #[display(style = "snake_case")]
 pub enum ValueKind {
     Integer,
@@ -892,6 +2164,8 @@ pub enum ValueKind {
     NftMetadata,
     Operator,
     Json,
+    Array,
+    Map,
     Cancel,
 }
 
@@ -928,24 +2202,385 @@ impl From<WrappedPubkey> for Pubkey {
 pub struct Flow {
     start_nodes: Vec<Sender<Value>>,
     nodes: HashMap<NodeId, FlowNode>,
-    log_graph_id: GraphId,
     run_id: Uuid,
+    env: Env,
 }
 
 struct FlowNode {
     log_node_id: NodeId,
     name: String,
-    inputs: HashMap<String, Receiver<Value>>,
-    outputs: HashMap<String, Vec<Sender<Value>>>,
+    inputs: HashMap<String, FlowInput>,
+    outputs: HashMap<String, Vec<OutputEdge>>,
+    merge: HashMap<String, MergeStrategy>,
+    // output of the command -> flow output
+    flow_outputs: HashMap<String, String>,
+    breakpoint: bool,
+    cache: Option<NodeCache>,
     cmd: Command,
 }
 
 async fn run_command(
     cmd: &Command,
     inputs: HashMap<String, Value>,
+    env: &Env,
 ) -> Result<HashMap<String, Value>, Error> {
     match cmd {
-        Command::Simple(simple) => simple.run(inputs).await,
-        Command::Solana(solana) => solana.run(inputs).await,
+        Command::Simple(simple) => simple.run(inputs, env).await,
+        Command::Solana(solana) => solana.run(inputs, env).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::simple::approval::Approval;
+    use crate::commands::simple::timer::Delay;
+
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    use sunshine_indra::store::{DbConfig, DB};
+
+    // runs in progress at the same time, at most and in total
+    #[derive(Default)]
+    struct RunCounter {
+        active: AtomicU64,
+        max_active: AtomicU64,
+        started: AtomicU64,
+    }
+
+    impl MetricsExporter for RunCounter {
+        fn run_started(&self, _flow_id: FlowId) {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            self.started.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn run_finished(&self, _flow_id: FlowId, _failed: bool) {
+            self.active.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn test_db(dir: &tempfile::TempDir) -> Arc<dyn Datastore> {
+        let db = DB::new(&DbConfig {
+            db_path: dir.path().join("db").to_str().unwrap().to_owned(),
+        })
+        .unwrap();
+
+        Arc::new(db)
+    }
+
+    // a flow whose only node waits for `duration_ms`
+    async fn slow_flow(flows: &FlowContext, duration_ms: u64) -> FlowId {
+        let file = FlowBuilder::new()
+            .start_node(
+                "slow",
+                Delay {
+                    duration_ms: Some(duration_ms),
+                },
+            )
+            .build()
+            .unwrap();
+
+        flows.import_flow(&file).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn interval_deployments_run_right_away() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = Arc::new(RunCounter::default());
+        let flows = FlowContext::with_metrics(test_db(&dir), counter.clone());
+
+        let flow_id = slow_flow(&flows, 0).await;
+
+        let schedule = Schedule::interval(Duration::from_secs(3600), OverlapPolicy::Skip);
+
+        flows
+            .deploy_flow(schedule, flow_id, HashMap::new())
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        flows.undeploy_flow(flow_id).await.unwrap();
+
+        assert_eq!(counter.started.load(Ordering::SeqCst), 1);
+    }
+
+    // runs taking 250ms scheduled every 100ms for a second
+    async fn schedule_slow_runs(overlap: OverlapPolicy) -> Arc<RunCounter> {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = Arc::new(RunCounter::default());
+        let flows = FlowContext::with_metrics(test_db(&dir), counter.clone());
+
+        let flow_id = slow_flow(&flows, 250).await;
+
+        let schedule = Schedule::interval(Duration::from_millis(100), overlap);
+
+        flows
+            .deploy_flow(schedule, flow_id, HashMap::new())
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+
+        flows.undeploy_flow(flow_id).await.unwrap();
+
+        counter
+    }
+
+    #[tokio::test]
+    async fn skip_doesnt_overlap_runs() {
+        let counter = schedule_slow_runs(OverlapPolicy::Skip).await;
+
+        let started = counter.started.load(Ordering::SeqCst);
+
+        assert_eq!(counter.max_active.load(Ordering::SeqCst), 1);
+        assert!((2..=5).contains(&started), "{} runs started", started);
+    }
+
+    #[tokio::test]
+    async fn queue_runs_one_after_another_without_bursts() {
+        let counter = schedule_slow_runs(OverlapPolicy::Queue).await;
+
+        let started = counter.started.load(Ordering::SeqCst);
+
+        assert_eq!(counter.max_active.load(Ordering::SeqCst), 1);
+        assert!((2..=5).contains(&started), "{} runs started", started);
+    }
+
+    #[tokio::test]
+    async fn allow_concurrent_overlaps_runs() {
+        let counter = schedule_slow_runs(OverlapPolicy::AllowConcurrent).await;
+
+        assert!(counter.max_active.load(Ordering::SeqCst) > 1);
+        assert!(counter.started.load(Ordering::SeqCst) >= 7);
+    }
+
+    #[tokio::test]
+    async fn restore_brings_back_unfinished_schedules_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = test_db(&dir);
+        let flows = FlowContext::new(db.clone());
+
+        let hourly = slow_flow(&flows, 1).await;
+        let replaced = slow_flow(&flows, 1).await;
+        let finished = slow_flow(&flows, 1).await;
+
+        for flow_id in [hourly, replaced] {
+            let schedule = Schedule::interval(Duration::from_secs(3600), OverlapPolicy::Skip);
+
+            flows
+                .deploy_flow(schedule, flow_id, HashMap::new())
+                .await
+                .unwrap();
+        }
+
+        flows
+            .deploy_flow(Schedule::Once, replaced, HashMap::new())
+            .await
+            .unwrap();
+
+        let once = RecurringSchedule::new(
+            Recurrence::Interval(Duration::from_millis(10)),
+            OverlapPolicy::Skip,
+        )
+        .with_max_runs(1);
+
+        flows
+            .deploy_flow(Schedule::Recurring(once), finished, HashMap::new())
+            .await
+            .unwrap();
+
+        let deployed = || async {
+            flows
+                .list_deployed()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|deployment| deployment.flow_id)
+                .collect::<Vec<_>>()
+        };
+
+        for _ in 0..100 {
+            if deployed().await.len() == 1 {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(deployed().await, vec![hourly]);
+        assert!(matches!(
+            flows.undeploy_flow(finished).await,
+            Err(Error::FlowDoesntExist)
+        ));
+
+        let restarted = FlowContext::new(db);
+
+        assert_eq!(restarted.restore().await.unwrap(), vec![hourly]);
+        assert!(!restarted.resume_flow(replaced).await.unwrap());
+
+        restarted.undeploy_flow(hourly).await.unwrap();
+
+        assert!(restarted.list_deployed().await.unwrap().is_empty());
+    }
+
+    // polls the record of the only node of the run until it's in the state
+    async fn wait_for_state(flows: &FlowContext, run_id: RunId, matches: fn(&RunState) -> bool) {
+        for _ in 0..100 {
+            let records = flows.get_run(run_id).await.unwrap();
+
+            if records
+                .values()
+                .any(|record| record.state().map_or(false, matches))
+            {
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        panic!("node didn't reach the state");
+    }
+
+    #[tokio::test]
+    async fn canceled_runs_cancel_their_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = FlowContext::new(test_db(&dir));
+
+        let flow_id = slow_flow(&flows, 10_000).await;
+
+        let run_id = flows
+            .start_flow(flow_id, HashMap::new(), RunOptions::default())
+            .await
+            .unwrap();
+
+        wait_for_state(&flows, run_id, |state| {
+            matches!(state, RunState::Waiting(_))
+        })
+        .await;
+
+        assert_eq!(flows.list_runs(flow_id), vec![run_id]);
+
+        flows.cancel_run(run_id).unwrap();
+
+        wait_for_state(&flows, run_id, |state| *state == RunState::Canceled).await;
+
+        assert!(flows.list_runs(flow_id).is_empty());
+        assert!(matches!(
+            flows.cancel_run(run_id),
+            Err(Error::RunDoesntExist)
+        ));
+    }
+
+    #[tokio::test]
+    async fn run_events_are_read_back_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = FlowContext::new(test_db(&dir));
+
+        let flow_id = slow_flow(&flows, 0).await;
+
+        let (run_id, _) = flows
+            .run_to_completion(flow_id, HashMap::new(), RunOptions::default())
+            .await
+            .unwrap();
+
+        let events = flows.run_events(run_id).await.unwrap();
+
+        assert!(!flows.is_running(run_id));
+        assert!(events.windows(2).all(|pair| pair[0].at <= pair[1].at));
+        assert!(events
+            .iter()
+            .all(|event| event.flow_id == flow_id && event.name == "slow"));
+        assert!(matches!(
+            events.last().map(|event| &event.state),
+            Some(RunState::Success(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delays_past_the_range_of_timestamps_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = FlowContext::new(test_db(&dir));
+
+        let flow_id = slow_flow(&flows, u64::MAX).await;
+
+        let run_id = flows
+            .start_flow(flow_id, HashMap::new(), RunOptions::default())
+            .await
+            .unwrap();
+
+        wait_for_state(&flows, run_id, |state| {
+            matches!(state, RunState::Failed(_, _))
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn corrupt_flows_fail_to_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = FlowContext::new(test_db(&dir));
+
+        let file = FlowBuilder::new()
+            .start_node(
+                "slow",
+                Delay {
+                    duration_ms: Some(0),
+                },
+            )
+            .node_property("slow", CTX_MARKER, "not a context")
+            .build()
+            .unwrap();
+
+        let flow_id = flows.import_flow(&file).await.unwrap();
+
+        let started = flows
+            .start_flow(flow_id, HashMap::new(), RunOptions::default())
+            .await;
+
+        assert!(matches!(started, Err(Error::JsonError(_))));
+    }
+
+    #[tokio::test]
+    async fn approvals_wait_in_their_node_until_decided() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = FlowContext::new(test_db(&dir));
+
+        let file = FlowBuilder::new()
+            .start_node(
+                "approve",
+                Approval {
+                    message: Some("ship it?".to_owned()),
+                    timeout_secs: None,
+                },
+            )
+            .build()
+            .unwrap();
+
+        let flow_id = flows.import_flow(&file).await.unwrap();
+
+        let run_id = flows
+            .start_flow(flow_id, HashMap::new(), RunOptions::default())
+            .await
+            .unwrap();
+
+        wait_for_state(&flows, run_id, |state| *state == RunState::AwaitingApproval).await;
+
+        let pending = flows.pending_approvals();
+
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].run_id, run_id);
+        assert_eq!(pending[0].name, "approve");
+        assert_eq!(pending[0].message.as_deref(), Some("ship it?"));
+
+        flows.approve(run_id, pending[0].node_id).unwrap();
+
+        wait_for_state(&flows, run_id, |state| {
+            matches!(state, RunState::Success(_))
+        })
+        .await;
+
+        assert!(flows.pending_approvals().is_empty());
     }
 }
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use url::Url;
+
+use crate::commands::{simple, solana, Command};
+
+// limits shared by every flow of a flow context, see FlowContext::with_limits. e.g. as json:
+//
+// {
+//     "global": { "max_concurrent": 64 },
+//     "resources": {
+//         "https://api.devnet.solana.com": { "max_concurrent": 4, "per_second": 10 },
+//         "api.pinata.cloud": { "per_second": 2 }
+//     }
+// }
+//
+// resources are the rpc urls of solana contexts and the hosts of http requests,
+// resources which aren't listed aren't limited
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Limits {
+    #[serde(default)]
+    pub global: Limit,
+    #[serde(default)]
+    pub resources: HashMap<String, Limit>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct Limit {
+    // commands using the resource at the same time, globally commands of every flow
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+    // requests sent to the resource, globally commands started
+    #[serde(default)]
+    pub per_second: Option<f64>,
+}
+
+struct Gate {
+    semaphore: Option<Arc<Semaphore>>,
+    interval: Option<Duration>,
+    next: Mutex<Option<Instant>>,
+}
+
+impl Gate {
+    fn new(limit: &Limit) -> Gate {
+        Gate {
+            semaphore: limit
+                .max_concurrent
+                .map(|permits| Arc::new(Semaphore::new(permits.max(1)))),
+            interval: limit
+                .per_second
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            next: Mutex::new(None),
+        }
+    }
+
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.semaphore {
+            // the semaphore is never closed
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+
+    // reserves the next free slot, requests are spaced evenly instead of sent in bursts
+    fn reserve(&self) -> Option<Instant> {
+        let interval = self.interval?;
+
+        let mut next = self.next.lock().unwrap();
+
+        let now = Instant::now();
+        let at = next.map_or(now, |next| next.max(now));
+
+        *next = Some(at + interval);
+
+        Some(at)
+    }
+}
+
+pub(crate) struct Limiter {
+    global: Gate,
+    resources: HashMap<String, Gate>,
+}
+
+// urls are compared in their normalized form, anything else is a host
+fn resource_key(resource: &str) -> String {
+    match Url::parse(resource) {
+        Ok(url) if resource.contains("://") => url.to_string(),
+        _ => resource.to_owned(),
+    }
+}
+
+// call_flow only waits for the other flow, whose nodes take their own permits, timers
+// only sleep and approvals wait for a decision, so they don't take permits while they wait
+pub(crate) fn waits(cmd: &Command) -> bool {
+    match cmd {
+        Command::Simple(cmd) => matches!(
+            cmd,
+            simple::Command::CallFlow(_)
+                | simple::Command::Approval(_)
+                | simple::Command::Delay(_)
+                | simple::Command::WaitUntil(_)
+        ),
+        Command::Solana(cmd) => matches!(cmd.kind, solana::Kind::WaitForSlot(_)),
+    }
+}
+
+impl Limiter {
+    pub fn new(limits: &Limits) -> Limiter {
+        Limiter {
+            global: Gate::new(&limits.global),
+            resources: limits
+                .resources
+                .iter()
+                .map(|(resource, limit)| (resource_key(resource), Gate::new(limit)))
+                .collect(),
+        }
+    }
+
+    // held while a node runs its command, unless the command only waits
+    pub async fn command(&self, cmd: &Command) -> Option<OwnedSemaphorePermit> {
+        if waits(cmd) {
+            return None;
+        }
+
+        let permit = self.global.acquire().await;
+
+        if let Some(at) = self.global.reserve() {
+            tokio::time::sleep_until(at).await;
+        }
+
+        permit
+    }
+
+    pub async fn concurrency(&self, resource: &str) -> Option<OwnedSemaphorePermit> {
+        match self.resources.get(&resource_key(resource)) {
+            Some(gate) => gate.acquire().await,
+            None => None,
+        }
+    }
+
+    pub async fn throttle(&self, resource: &str) {
+        let at = self
+            .resources
+            .get(&resource_key(resource))
+            .and_then(Gate::reserve);
+
+        if let Some(at) = at {
+            tokio::time::sleep_until(at).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_spaced_by_the_rate() {
+        let limiter = Limiter::new(&Limits {
+            global: Limit::default(),
+            resources: maplit::hashmap! {
+                "https://api.devnet.solana.com".to_owned() => Limit {
+                    max_concurrent: None,
+                    per_second: Some(4.0),
+                },
+            },
+        });
+
+        let gate = &limiter.resources[&resource_key("https://api.devnet.solana.com/")];
+
+        let first = gate.reserve().unwrap();
+        let second = gate.reserve().unwrap();
+        let third = gate.reserve().unwrap();
+
+        assert_eq!(second - first, Duration::from_millis(250));
+        assert_eq!(third - second, Duration::from_millis(250));
+
+        assert!(limiter
+            .resources
+            .get(&resource_key("api.pinata.cloud"))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn timers_dont_take_permits() {
+        let limiter = Limiter::new(&Limits {
+            global: Limit {
+                max_concurrent: Some(1),
+                per_second: None,
+            },
+            resources: HashMap::new(),
+        });
+
+        let delay = Command::Simple(simple::Command::Delay(simple::timer::Delay {
+            duration_ms: Some(1000),
+        }));
+        let print = Command::Simple(simple::Command::Print);
+
+        assert!(limiter.command(&delay).await.is_none());
+
+        let _permit = limiter.command(&print).await;
+        assert!(limiter.command(&delay).await.is_none());
+    }
+}
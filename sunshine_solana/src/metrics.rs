@@ -0,0 +1,231 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use dashmap::DashMap;
+
+use crate::FlowId;
+
+// receives the measurements of the flow context, see FlowContext::with_metrics
+pub trait MetricsExporter: Send + Sync {
+    fn run_started(&self, _flow_id: FlowId) {}
+
+    fn run_finished(&self, _flow_id: FlowId, _failed: bool) {}
+
+    fn node_finished(&self, _kind: &str, _elapsed: Duration, _failed: bool) {}
+
+    fn rpc_call(&self, _method: &str) {}
+
+    // outputs of a node reused from the cache
+    fn cache_hit(&self, _kind: &str) {}
+
+    // fees and rent deposits of sent transactions
+    fn lamports_spent(&self, _flow_id: FlowId, _lamports: u64) {}
+}
+
+pub struct NoopMetrics;
+
+impl MetricsExporter for NoopMetrics {}
+
+// upper bounds of the node latency buckets in milliseconds
+const LATENCY_BUCKETS: [u64; 10] = [10, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, millis: u64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if millis <= bound {
+                *bucket += 1;
+            }
+        }
+
+        self.count += 1;
+        self.sum += millis;
+    }
+}
+
+// keeps the measurements in memory and renders them in the prometheus text format
+#[derive(Default)]
+pub struct PrometheusMetrics {
+    runs_started: DashMap<FlowId, u64>,
+    runs_failed: DashMap<FlowId, u64>,
+    nodes_failed: DashMap<String, u64>,
+    node_latency: DashMap<String, Histogram>,
+    rpc_calls: DashMap<String, u64>,
+    cache_hits: DashMap<String, u64>,
+    lamports_spent: DashMap<FlowId, u64>,
+}
+
+impl PrometheusMetrics {
+    pub fn new() -> PrometheusMetrics {
+        PrometheusMetrics::default()
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        render_counter(
+            &mut out,
+            "sunshine_runs_started_total",
+            "flow runs started",
+            "flow_id",
+            &self.runs_started,
+        );
+        render_counter(
+            &mut out,
+            "sunshine_runs_failed_total",
+            "flow runs with a failed node",
+            "flow_id",
+            &self.runs_failed,
+        );
+        render_counter(
+            &mut out,
+            "sunshine_nodes_failed_total",
+            "commands which returned an error",
+            "kind",
+            &self.nodes_failed,
+        );
+        render_counter(
+            &mut out,
+            "sunshine_rpc_calls_total",
+            "solana rpc calls",
+            "method",
+            &self.rpc_calls,
+        );
+        render_counter(
+            &mut out,
+            "sunshine_cache_hits_total",
+            "commands whose outputs were reused from the cache",
+            "kind",
+            &self.cache_hits,
+        );
+        render_counter(
+            &mut out,
+            "sunshine_lamports_spent_total",
+            "lamports spent on fees and rent",
+            "flow_id",
+            &self.lamports_spent,
+        );
+
+        let name = "sunshine_node_latency_milliseconds";
+
+        writeln!(out, "# HELP {} time taken by commands", name).unwrap();
+        writeln!(out, "# TYPE {} histogram", name).unwrap();
+
+        for entry in self.node_latency.iter() {
+            let (kind, histogram) = entry.pair();
+
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                writeln!(
+                    out,
+                    "{}_bucket{{kind=\"{}\",le=\"{}\"}} {}",
+                    name, kind, bound, count
+                )
+                .unwrap();
+            }
+
+            writeln!(
+                out,
+                "{}_bucket{{kind=\"{}\",le=\"+Inf\"}} {}",
+                name, kind, histogram.count
+            )
+            .unwrap();
+            writeln!(out, "{}_sum{{kind=\"{}\"}} {}", name, kind, histogram.sum).unwrap();
+            writeln!(
+                out,
+                "{}_count{{kind=\"{}\"}} {}",
+                name, kind, histogram.count
+            )
+            .unwrap();
+        }
+
+        out
+    }
+}
+
+fn render_counter<K: std::fmt::Display + Eq + std::hash::Hash>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: &DashMap<K, u64>,
+) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} counter", name).unwrap();
+
+    for entry in values.iter() {
+        writeln!(
+            out,
+            "{}{{{}=\"{}\"}} {}",
+            name,
+            label,
+            entry.key(),
+            entry.value()
+        )
+        .unwrap();
+    }
+}
+
+impl MetricsExporter for PrometheusMetrics {
+    fn run_started(&self, flow_id: FlowId) {
+        *self.runs_started.entry(flow_id).or_default() += 1;
+    }
+
+    fn run_finished(&self, flow_id: FlowId, failed: bool) {
+        if failed {
+            *self.runs_failed.entry(flow_id).or_default() += 1;
+        }
+    }
+
+    fn node_finished(&self, kind: &str, elapsed: Duration, failed: bool) {
+        self.node_latency
+            .entry(kind.to_owned())
+            .or_default()
+            .observe(elapsed.as_millis() as u64);
+
+        if failed {
+            *self.nodes_failed.entry(kind.to_owned()).or_default() += 1;
+        }
+    }
+
+    fn rpc_call(&self, method: &str) {
+        *self.rpc_calls.entry(method.to_owned()).or_default() += 1;
+    }
+
+    fn cache_hit(&self, kind: &str) {
+        *self.cache_hits.entry(kind.to_owned()).or_default() += 1;
+    }
+
+    fn lamports_spent(&self, flow_id: FlowId, lamports: u64) {
+        *self.lamports_spent.entry(flow_id).or_default() += lamports;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_latency_buckets_cumulatively() {
+        let metrics = PrometheusMetrics::new();
+
+        metrics.node_finished("Print", Duration::from_millis(40), false);
+        metrics.node_finished("Print", Duration::from_millis(700), true);
+
+        let rendered = metrics.render();
+
+        assert!(rendered
+            .contains("sunshine_node_latency_milliseconds_bucket{kind=\"Print\",le=\"10\"} 0"));
+        assert!(rendered
+            .contains("sunshine_node_latency_milliseconds_bucket{kind=\"Print\",le=\"50\"} 1"));
+        assert!(rendered
+            .contains("sunshine_node_latency_milliseconds_bucket{kind=\"Print\",le=\"1000\"} 2"));
+        assert!(rendered.contains("sunshine_node_latency_milliseconds_sum{kind=\"Print\"} 740"));
+        assert!(rendered.contains("sunshine_nodes_failed_total{kind=\"Print\"} 1"));
+    }
+}
@@ -0,0 +1,633 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sunshine_core::msg::{NodeId, Properties};
+use sunshine_core::store::Datastore;
+use tokio::sync::mpsc::UnboundedSender as Sender;
+
+use crate::coerce::Conversion;
+use crate::commands::solana::nft::Command as NftCommand;
+use crate::commands::solana::Kind as SolanaKind;
+use crate::commands::{simple, Config};
+use crate::error::Error;
+use crate::{Value, ValueKind, COMMAND_MARKER, INPUT_ARG_NAME_MARKER, OUTPUT_ARG_NAME_MARKER};
+
+// the kind an edge converts values to before they reach the input, set on edges between
+// ports whose kinds convert losslessly
+pub const EDGE_COERCE_MARKER: &str = "EDGE_COERCE_MARKER";
+
+// keypair and pubkey inputs also take the node id of a wallet entry
+const KEYPAIR: Option<ValueKind> = Some(ValueKind::Keypair);
+const PUBKEY: Option<ValueKind> = Some(ValueKind::Pubkey);
+const STRING: Option<ValueKind> = Some(ValueKind::String);
+const BOOL: Option<ValueKind> = Some(ValueKind::Bool);
+const U64: Option<ValueKind> = Some(ValueKind::U64);
+const F64: Option<ValueKind> = Some(ValueKind::F64);
+const ANY: Option<ValueKind> = None;
+
+// the outputs of the command, None when they depend on the values the command gets
+pub fn outputs(cfg: &Config) -> Option<Vec<(&'static str, ValueKind)>> {
+    use ValueKind as K;
+
+    let outputs = match cfg {
+        Config::Simple(cmd) => match cmd {
+            simple::Command::Const(value) => vec![("output", value.kind())],
+            simple::Command::Print => vec![("__print_output", K::String)],
+            simple::Command::IpfsUpload(_) => vec![("file_cid", K::String)],
+            simple::Command::IpfsNftUpload(_) => vec![
+                ("metadata_cid", K::String),
+                ("updated_metadata", K::NftMetadata),
+                ("metadata_url", K::String),
+            ],
+            _ => return None,
+        },
+        Config::Solana(kind) => match kind {
+            SolanaKind::GenerateKeypair(_) => vec![
+                ("pubkey", K::Pubkey),
+                ("keypair", K::Keypair),
+                ("empty", K::Empty),
+            ],
+            SolanaKind::DeleteKeypair(_) => vec![("removed_keypair", K::Keypair)],
+            SolanaKind::AddPubkey(_) => vec![("pubkey", K::Pubkey)],
+            SolanaKind::DeletePubkey(_) => vec![("removed_pubkey", K::Pubkey)],
+            SolanaKind::CreateTokenAccount(_) => vec![
+                ("signature", K::Success),
+                ("mint_account", K::Pubkey),
+                ("owner", K::Pubkey),
+                ("fee_payer", K::Keypair),
+            ],
+            SolanaKind::GetBalance(_) => vec![("balance", K::Balance)],
+            SolanaKind::CreateMintAccount(_) => vec![
+                ("mint_account", K::Keypair),
+                ("signature", K::Success),
+                ("fee_payer", K::Keypair),
+                ("authority", K::Keypair),
+            ],
+            SolanaKind::RequestAirdrop(_) => vec![("signature", K::Success)],
+            SolanaKind::MintToken(_) => vec![
+                ("signature", K::Success),
+                ("mint_account", K::Pubkey),
+                ("fee_payer", K::Keypair),
+                ("recipient", K::Pubkey),
+            ],
+            SolanaKind::TransferToken(_) => vec![
+                ("sender_owner", K::Pubkey),
+                ("recipient_account", K::Pubkey),
+                ("signature", K::Success),
+                ("fee_payer", K::Keypair),
+            ],
+            SolanaKind::TransferSolana(_) => vec![
+                ("sender", K::Keypair),
+                ("recipient", K::Pubkey),
+                ("signature", K::Success),
+            ],
+            // `value` has the kind of the value it got
+            SolanaKind::WaitForSlot(_) => return None,
+            SolanaKind::Nft(nft) => match nft {
+                NftCommand::CreateMetadataAccounts(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                    ("metadata_account", K::Pubkey),
+                    // empty without a collection
+                    ("collection_mint_account", K::Pubkey),
+                ],
+                NftCommand::CreateMasterEdition(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                    ("metadata_account", K::Pubkey),
+                    ("master_edition_account", K::Pubkey),
+                ],
+                NftCommand::UpdateMetadataAccounts(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                    ("metadata_account", K::Pubkey),
+                ],
+                NftCommand::Utilize(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                    ("use_authority", K::Keypair),
+                    ("owner", K::Pubkey),
+                    ("account", K::Pubkey),
+                    ("burner", K::Pubkey),
+                ],
+                NftCommand::ApproveUseAuthority(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                    ("use_authority", K::Pubkey),
+                    ("owner", K::Keypair),
+                    ("token_account", K::Pubkey),
+                    ("burner", K::Pubkey),
+                    ("use_authority_record", K::Pubkey),
+                ],
+                // empty when the nft has no uses
+                NftCommand::GetLeftUses(_) => vec![("left_uses", K::U64)],
+                NftCommand::ArweaveNftUpload(_) => vec![
+                    ("metadata_url", K::String),
+                    ("updated_metadata", K::NftMetadata),
+                    ("fee_payer", K::Keypair),
+                ],
+                NftCommand::ArweaveFileUpload(_) => {
+                    vec![("file_url", K::String), ("fee_payer", K::Keypair)]
+                }
+                NftCommand::VerifyCollection(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                    ("collection_authority", K::Keypair),
+                ],
+                NftCommand::SignMetadata(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                    ("creator", K::Keypair),
+                ],
+                NftCommand::ApproveCollectionAuthority(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("update_authority", K::Keypair),
+                    ("mint_account", K::Pubkey),
+                ],
+                NftCommand::CreateAuctionHouse(_) => vec![
+                    ("treasury_withdrawal_destination", K::Pubkey),
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("auction_house_authority", K::Pubkey),
+                    ("treasury_mint_account", K::Pubkey),
+                ],
+                NftCommand::AuctionHouseSell(_) => vec![
+                    ("signature", K::Success),
+                    ("fee_payer", K::Keypair),
+                    ("auction_house_authority", K::Keypair),
+                    ("treasury_mint_account", K::Pubkey),
+                ],
+            },
+        },
+    };
+
+    Some(outputs)
+}
+
+// the inputs the command reads, None for the kinds it takes any value of. commands ignore
+// inputs they don't read, so inputs which aren't listed aren't checked
+pub fn inputs(cfg: &Config) -> Vec<(&'static str, Option<ValueKind>)> {
+    use ValueKind as K;
+
+    match cfg {
+        Config::Simple(cmd) => match cmd {
+            simple::Command::Const(_) => vec![],
+            simple::Command::Print => vec![("print", ANY)],
+            simple::Command::Wait => vec![("wait", ANY), ("value", ANY)],
+            simple::Command::HttpRequest(_) => vec![
+                ("method", STRING),
+                ("url", STRING),
+                ("auth_token", STRING),
+                ("json_body", ANY),
+                ("headers", ANY),
+            ],
+            simple::Command::JsonExtract(_) => vec![("json", ANY), ("path", STRING)],
+            simple::Command::JsonInsert(_) => {
+                vec![("json", ANY), ("path", STRING), ("value", ANY)]
+            }
+            simple::Command::IpfsUpload(_) => vec![
+                ("pinata_url", STRING),
+                ("pinata_jwt", STRING),
+                ("file_path", STRING),
+            ],
+            simple::Command::IpfsNftUpload(_) => vec![
+                ("pinata_url", STRING),
+                ("pinata_jwt", STRING),
+                ("metadata", Some(K::NftMetadata)),
+            ],
+            simple::Command::Branch(_) => {
+                vec![("operator", Some(K::Operator)), ("a", ANY), ("b", ANY)]
+            }
+            simple::Command::Switch(_) => vec![("value", ANY)],
+            // the other inputs are variables of the expression or inputs of the called flow
+            simple::Command::Expr(_) => vec![("expression", STRING)],
+            simple::Command::CallFlow(_) => vec![("flow_id", Some(K::NodeId))],
+            simple::Command::Approval(_) => vec![("value", ANY)],
+            simple::Command::Delay(_) => vec![("duration_ms", U64), ("value", ANY)],
+            simple::Command::WaitUntil(_) => {
+                vec![("timestamp", Some(K::Integer)), ("value", ANY)]
+            }
+            simple::Command::Variable(_) => vec![("name", STRING)],
+        },
+        Config::Solana(kind) => match kind {
+            SolanaKind::GenerateKeypair(_) => vec![
+                ("seed_phrase", STRING),
+                ("private_key", STRING),
+                ("passphrase", STRING),
+                ("save", STRING),
+            ],
+            SolanaKind::DeleteKeypair(_) | SolanaKind::DeletePubkey(_) => vec![],
+            SolanaKind::AddPubkey(_) => vec![("name", STRING), ("pubkey", PUBKEY)],
+            SolanaKind::CreateTokenAccount(_) => vec![
+                ("owner", PUBKEY),
+                ("fee_payer", KEYPAIR),
+                ("mint_account", PUBKEY),
+                ("token_account", KEYPAIR),
+            ],
+            SolanaKind::GetBalance(_) => vec![("pubkey", PUBKEY)],
+            SolanaKind::CreateMintAccount(_) => vec![
+                ("fee_payer", KEYPAIR),
+                ("decimals", Some(K::U8)),
+                ("authority", KEYPAIR),
+                ("mint_account", KEYPAIR),
+                ("memo", STRING),
+            ],
+            SolanaKind::RequestAirdrop(_) => vec![("pubkey", PUBKEY), ("amount", U64)],
+            SolanaKind::MintToken(_) => vec![
+                ("mint_account", PUBKEY),
+                ("recipient", PUBKEY),
+                ("mint_authority", KEYPAIR),
+                ("amount", F64),
+                ("fee_payer", KEYPAIR),
+            ],
+            SolanaKind::TransferToken(_) => vec![
+                ("fee_payer", KEYPAIR),
+                ("mint_account", PUBKEY),
+                ("amount", F64),
+                ("recipient", PUBKEY),
+                ("sender_token_account", PUBKEY),
+                ("sender_owner", KEYPAIR),
+                ("allow_unfunded", BOOL),
+                ("fund_recipient", BOOL),
+                ("memo", STRING),
+            ],
+            SolanaKind::TransferSolana(_) => {
+                vec![("sender", KEYPAIR), ("recipient", PUBKEY), ("amount", F64)]
+            }
+            SolanaKind::WaitForSlot(_) => vec![("slot", U64), ("value", ANY)],
+            SolanaKind::Nft(nft) => match nft {
+                NftCommand::CreateMetadataAccounts(_) => vec![
+                    ("mint_account", PUBKEY),
+                    ("mint_authority", PUBKEY),
+                    ("fee_payer", KEYPAIR),
+                    ("update_authority", KEYPAIR),
+                    ("metadata", Some(K::NftMetadata)),
+                    ("metadata_uri", STRING),
+                    ("uses", Some(K::NftUses)),
+                    ("creators", Some(K::NftCreators)),
+                    ("is_mutable", BOOL),
+                    ("collection_mint_account", PUBKEY),
+                ],
+                NftCommand::CreateMasterEdition(_) => vec![
+                    ("mint_account", PUBKEY),
+                    ("mint_authority", PUBKEY),
+                    ("fee_payer", KEYPAIR),
+                    ("update_authority", KEYPAIR),
+                    ("max_supply", U64),
+                ],
+                NftCommand::UpdateMetadataAccounts(_) => vec![
+                    ("mint_account", PUBKEY),
+                    ("fee_payer", KEYPAIR),
+                    ("update_authority", KEYPAIR),
+                    ("new_update_authority", PUBKEY),
+                    ("data", Some(K::MetadataAccountData)),
+                    ("primary_sale_happened", BOOL),
+                    ("is_mutable", BOOL),
+                ],
+                NftCommand::Utilize(_) => vec![
+                    ("mint_account", PUBKEY),
+                    ("use_authority", KEYPAIR),
+                    ("fee_payer", KEYPAIR),
+                    ("account", PUBKEY),
+                    ("owner", PUBKEY),
+                    ("burner", PUBKEY),
+                    ("number_of_uses", U64),
+                ],
+                NftCommand::ApproveUseAuthority(_) => vec![
+                    ("use_authority", PUBKEY),
+                    ("owner", KEYPAIR),
+                    ("fee_payer", KEYPAIR),
+                    ("token_account", PUBKEY),
+                    ("mint_account", PUBKEY),
+                    ("burner", PUBKEY),
+                    ("number_of_uses", U64),
+                ],
+                NftCommand::GetLeftUses(_) => vec![("mint_account", PUBKEY)],
+                NftCommand::ArweaveNftUpload(_) => vec![
+                    ("fee_payer", KEYPAIR),
+                    ("metadata", Some(K::NftMetadata)),
+                    ("fund_bundlr", BOOL),
+                ],
+                NftCommand::ArweaveFileUpload(_) => vec![
+                    ("fee_payer", KEYPAIR),
+                    ("file_path", STRING),
+                    ("fund_bundlr", BOOL),
+                ],
+                NftCommand::VerifyCollection(_) => vec![
+                    ("mint_account", PUBKEY),
+                    ("fee_payer", KEYPAIR),
+                    ("collection_authority", KEYPAIR),
+                    ("collection_mint_account", PUBKEY),
+                    ("collection_authority_is_delegated", BOOL),
+                ],
+                NftCommand::SignMetadata(_) => vec![
+                    ("fee_payer", KEYPAIR),
+                    ("mint_account", PUBKEY),
+                    ("creator", KEYPAIR),
+                ],
+                NftCommand::ApproveCollectionAuthority(_) => vec![
+                    ("new_collection_authority", PUBKEY),
+                    ("update_authority", KEYPAIR),
+                    ("fee_payer", KEYPAIR),
+                    ("mint_account", PUBKEY),
+                ],
+                NftCommand::CreateAuctionHouse(_) => vec![
+                    ("treasury_mint_account", PUBKEY),
+                    ("fee_payer", KEYPAIR),
+                    ("fee_withdrawal_destination", PUBKEY),
+                    ("auction_house_authority", PUBKEY),
+                    ("treasury_withdrawal_destination", PUBKEY),
+                    ("treasury_withdrawal_destination_owner", PUBKEY),
+                    ("seller_fee_basis_points", Some(K::U16)),
+                    ("requires_sign_off", BOOL),
+                    ("can_change_sale_price", BOOL),
+                ],
+                NftCommand::AuctionHouseSell(_) => vec![
+                    ("treasury_mint_account", PUBKEY),
+                    ("fee_payer", KEYPAIR),
+                    ("auction_house_authority", KEYPAIR),
+                    ("seller", KEYPAIR),
+                    ("seller_token_account", PUBKEY),
+                    ("seller_token_mint_account", PUBKEY),
+                    ("sale_price", U64),
+                ],
+            },
+        },
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum EdgeCheck {
+    Exact,
+    // converted on the edge, see EDGE_COERCE_MARKER
+    Coerced {
+        from: ValueKind,
+        to: ValueKind,
+    },
+    // converted by the command, the run fails if the value doesn't convert
+    Checked {
+        from: ValueKind,
+        to: ValueKind,
+    },
+    // one of the ports takes or gives any kind of value
+    Unchecked,
+    Incompatible {
+        reason: String,
+        // outputs of the same node which do fit the input
+        suggestions: Vec<String>,
+    },
+}
+
+impl EdgeCheck {
+    // the kind to set as EDGE_COERCE_MARKER
+    pub fn coerce_to(&self) -> Option<ValueKind> {
+        match self {
+            EdgeCheck::Coerced { to, .. } => Some(*to),
+            _ => None,
+        }
+    }
+
+    pub fn error(&self, output: &str, input: &str) -> Option<Error> {
+        match self {
+            EdgeCheck::Incompatible {
+                reason,
+                suggestions,
+            } => Some(Error::IncompatibleEdge {
+                output: output.to_owned(),
+                input: input.to_owned(),
+                reason: match suggestions.is_empty() {
+                    true => reason.clone(),
+                    false => format!("{}, outputs which fit: {}", reason, suggestions.join(", ")),
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+pub fn check_edge(from: &Config, output: &str, to: &Config, input: &str) -> EdgeCheck {
+    let outputs = match outputs(from) {
+        Some(outputs) => outputs,
+        None => return EdgeCheck::Unchecked,
+    };
+
+    let from_kind = match outputs.iter().find(|(name, _)| *name == output) {
+        Some((_, kind)) => *kind,
+        None => {
+            return EdgeCheck::Incompatible {
+                reason: format!("the command has no output {}", output),
+                suggestions: Vec::new(),
+            }
+        }
+    };
+
+    let to_kind = match inputs(to).into_iter().find(|(name, _)| *name == input) {
+        Some((_, Some(kind))) => kind,
+        _ => return EdgeCheck::Unchecked,
+    };
+
+    match check_kinds(from_kind, to_kind) {
+        Conversion::Same => EdgeCheck::Exact,
+        Conversion::Lossless => EdgeCheck::Coerced {
+            from: from_kind,
+            to: to_kind,
+        },
+        Conversion::Checked => EdgeCheck::Checked {
+            from: from_kind,
+            to: to_kind,
+        },
+        Conversion::Incompatible => EdgeCheck::Incompatible {
+            reason: format!("{} doesn't convert to {}", from_kind, to_kind),
+            suggestions: outputs
+                .iter()
+                .filter(|(_, kind)| {
+                    matches!(
+                        check_kinds(*kind, to_kind),
+                        Conversion::Same | Conversion::Lossless
+                    )
+                })
+                .map(|(name, _)| (*name).to_owned())
+                .collect(),
+        },
+    }
+}
+
+// sets EDGE_COERCE_MARKER on the properties of an edge between the ports, an error when
+// the ports don't fit
+pub(crate) fn type_edge(
+    check: &EdgeCheck,
+    output: &str,
+    input: &str,
+    properties: &mut Properties,
+) -> Result<(), Error> {
+    if let Some(e) = check.error(output, input) {
+        return Err(e);
+    }
+
+    match check.coerce_to() {
+        Some(kind) => {
+            properties.insert(EDGE_COERCE_MARKER.to_owned(), serde_json::to_value(kind)?);
+        }
+        None => {
+            properties.remove(EDGE_COERCE_MARKER);
+        }
+    }
+
+    Ok(())
+}
+
+// checks an edge of the datastore by the ports named in its properties, edges which don't
+// connect the ports of two commands, e.g. context edges, aren't checked
+pub(crate) async fn type_stored_edge(
+    db: &Arc<dyn Datastore>,
+    from: NodeId,
+    to: NodeId,
+    properties: &mut Properties,
+) -> Result<Option<EdgeCheck>, Error> {
+    let port = |marker: &str| properties.get(marker).and_then(JsonValue::as_str);
+
+    let (output, input) = match (port(OUTPUT_ARG_NAME_MARKER), port(INPUT_ARG_NAME_MARKER)) {
+        (Some(output), Some(input)) => (output.to_owned(), input.to_owned()),
+        _ => return Ok(None),
+    };
+
+    let (from_cfg, to_cfg) = match (read_command(db, from).await?, read_command(db, to).await?) {
+        (Some(from_cfg), Some(to_cfg)) => (from_cfg, to_cfg),
+        _ => return Ok(None),
+    };
+
+    let check = check_edge(&from_cfg, &output, &to_cfg, &input);
+
+    type_edge(&check, &output, &input, properties)?;
+
+    Ok(Some(check))
+}
+
+pub(crate) async fn read_command(
+    db: &Arc<dyn Datastore>,
+    node_id: NodeId,
+) -> Result<Option<Config>, Error> {
+    let props = db.read_node(node_id).await?.properties;
+
+    match props.get(COMMAND_MARKER) {
+        Some(cfg) => Ok(Some(serde_json::from_value(cfg.clone())?)),
+        None => Ok(None),
+    }
+}
+
+fn check_kinds(from: ValueKind, to: ValueKind) -> Conversion {
+    use ValueKind as K;
+
+    match (from.conversion_to(to), from, to) {
+        // looked up in the wallet when the command runs
+        (Conversion::Incompatible, K::NodeId | K::NodeIdOpt, K::Keypair | K::Pubkey) => {
+            Conversion::Checked
+        }
+        // empty values are skipped by optional inputs
+        (Conversion::Incompatible, K::Empty, _) => Conversion::Checked,
+        (conversion, _, _) => conversion,
+    }
+}
+
+// an edge leaving an output, coercing the values it sends when its ports are typed
+pub(crate) struct OutputEdge {
+    pub tx: Sender<Value>,
+    pub coerce: Option<ValueKind>,
+}
+
+impl OutputEdge {
+    pub fn send(&self, value: Value) {
+        // values of other kinds, e.g. empty outputs or cancellations, are sent as they are
+        let value = match self.coerce {
+            Some(kind) if value.kind().conversion_to(kind) == Conversion::Lossless => {
+                match value.clone().coerce_to(kind) {
+                    Ok(coerced) => coerced,
+                    Err(_) => value,
+                }
+            }
+            _ => value,
+        };
+
+        self.tx.send(value).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::solana::get_balance::GetBalance;
+    use crate::commands::solana::transfer_solana::TransferSolana;
+
+    fn transfer() -> Config {
+        TransferSolana {
+            sender: None,
+            recipient: None,
+            amount: None,
+        }
+        .into()
+    }
+
+    #[test]
+    fn edges_are_checked_by_kind() {
+        let balance: Config = GetBalance { pubkey: None }.into();
+
+        assert_eq!(
+            check_edge(&transfer(), "recipient", &balance, "pubkey"),
+            EdgeCheck::Exact
+        );
+        assert_eq!(
+            check_edge(&transfer(), "sender", &balance, "pubkey"),
+            EdgeCheck::Coerced {
+                from: ValueKind::Keypair,
+                to: ValueKind::Pubkey,
+            }
+        );
+        assert_eq!(
+            check_edge(&balance, "balance", &transfer(), "amount"),
+            EdgeCheck::Checked {
+                from: ValueKind::Balance,
+                to: ValueKind::F64,
+            }
+        );
+
+        match check_edge(&transfer(), "signature", &balance, "pubkey") {
+            EdgeCheck::Incompatible { suggestions, .. } => {
+                assert_eq!(suggestions, vec!["sender", "recipient"])
+            }
+            check => panic!("unexpected check: {:?}", check),
+        }
+    }
+
+    #[test]
+    fn untyped_ports_arent_checked() {
+        let print: Config = simple::Command::Print.into();
+        let json: Config = simple::Command::JsonExtract(simple::json_extract::JsonExtract {
+            path: None,
+            json: None,
+        })
+        .into();
+
+        assert_eq!(
+            check_edge(&transfer(), "signature", &print, "print"),
+            EdgeCheck::Unchecked
+        );
+        assert_eq!(
+            check_edge(&json, "value", &transfer(), "amount"),
+            EdgeCheck::Unchecked
+        );
+        assert!(matches!(
+            check_edge(&transfer(), "missing", &print, "print"),
+            EdgeCheck::Incompatible { .. }
+        ));
+    }
+}
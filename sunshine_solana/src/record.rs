@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Signature;
+use sunshine_core::msg::{NodeId, Properties};
+use sunshine_core::store::Datastore;
+use tokio::sync::broadcast;
+
+use crate::commands::solana::instructions::Simulation;
+use crate::error::Error;
+use crate::{Engine, FlowId, RunId, RunState, Value, CHECKPOINT_MARKER};
+
+// log node property holding the NodeRunRecord of the node
+pub const NODE_RUN_RECORD_MARKER: &str = "NODE_RUN_RECORD_MARKER";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateChange {
+    pub state: RunState,
+    pub at: DateTime<Utc>,
+}
+
+// what happened to a node of a run, the record is rewritten as a whole on every change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeRunRecord {
+    // node of the flow graph
+    pub node_id: NodeId,
+    pub name: String,
+    pub kind: String,
+    pub states: Vec<StateChange>,
+    pub inputs: HashMap<String, Value>,
+    pub outputs: HashMap<String, Value>,
+    pub error: Option<String>,
+    // how many times the command was started, resumed runs start it again
+    pub attempts: u32,
+    pub signatures: Vec<Signature>,
+    pub print_output: Option<String>,
+    pub simulations: Vec<Simulation>,
+    // the outputs were reused from the cache instead of running the command
+    #[serde(default)]
+    pub cache_hit: bool,
+}
+
+// state change of a node, sent to the subscribers of the flow context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunEvent {
+    pub flow_id: FlowId,
+    pub run_id: RunId,
+    pub node_id: NodeId,
+    pub name: String,
+    pub state: RunState,
+    pub at: DateTime<Utc>,
+}
+
+impl NodeRunRecord {
+    pub fn state(&self) -> Option<&RunState> {
+        self.states.last().map(|change| &change.state)
+    }
+}
+
+pub(crate) fn read_record(props: &Properties) -> Result<Option<NodeRunRecord>, serde_json::Error> {
+    props
+        .get(NODE_RUN_RECORD_MARKER)
+        .map(|record| serde_json::from_value(record.clone()))
+        .transpose()
+}
+
+// log node of a command, only the task running the command writes to it
+pub(crate) struct NodeLog {
+    db: Arc<dyn Datastore>,
+    events: broadcast::Sender<RunEvent>,
+    flow_id: FlowId,
+    run_id: RunId,
+    log_node_id: NodeId,
+    props: Properties,
+    record: NodeRunRecord,
+}
+
+impl NodeLog {
+    // a record left by a previous attempt of the run is continued
+    pub async fn open(
+        engine: &Engine,
+        flow_id: FlowId,
+        run_id: RunId,
+        log_node_id: NodeId,
+        node_id: NodeId,
+        name: String,
+        kind: String,
+    ) -> Result<NodeLog, Error> {
+        let db = engine.db.clone();
+
+        let props = db.read_node(log_node_id).await?.properties;
+
+        let record = match read_record(&props)? {
+            Some(record) => NodeRunRecord {
+                name,
+                kind,
+                ..record
+            },
+            None => NodeRunRecord {
+                node_id,
+                name,
+                kind,
+                states: Vec::new(),
+                inputs: HashMap::new(),
+                outputs: HashMap::new(),
+                error: None,
+                attempts: 0,
+                signatures: Vec::new(),
+                print_output: None,
+                simulations: Vec::new(),
+                cache_hit: false,
+            },
+        };
+
+        Ok(NodeLog {
+            db,
+            events: engine.events.clone(),
+            flow_id,
+            run_id,
+            log_node_id,
+            props,
+            record,
+        })
+    }
+
+    pub fn state(&self) -> Option<RunState> {
+        self.record.state().cloned()
+    }
+
+    pub async fn set_state(&mut self, state: RunState) {
+        self.update(|record| {
+            if let RunState::Failed(_, e) = &state {
+                record.error = Some(e.clone());
+            }
+
+            record.states.push(StateChange {
+                state,
+                at: Utc::now(),
+            });
+        })
+        .await
+    }
+
+    // no subscribers isn't an error
+    fn notify(&self) {
+        if let Some(change) = self.record.states.last() {
+            self.events
+                .send(RunEvent {
+                    flow_id: self.flow_id,
+                    run_id: self.run_id,
+                    node_id: self.record.node_id,
+                    name: self.record.name.clone(),
+                    state: change.state.clone(),
+                    at: change.at,
+                })
+                .ok();
+        }
+    }
+
+    // the inputs a command runs with, counted as a new attempt
+    pub async fn start(&mut self, inputs: &HashMap<String, Value>) {
+        self.update(|record| {
+            record.inputs = inputs.clone();
+            record.error = None;
+            record.attempts += 1;
+            record.cache_hit = false;
+            record.states.push(StateChange {
+                state: RunState::Running,
+                at: Utc::now(),
+            });
+        })
+        .await
+    }
+
+    // the command isn't run, its outputs are taken from the cache
+    pub async fn reuse(&mut self, inputs: &HashMap<String, Value>) {
+        self.update(|record| {
+            record.inputs = inputs.clone();
+            record.error = None;
+            record.cache_hit = true;
+            record.states.push(StateChange {
+                state: RunState::Running,
+                at: Utc::now(),
+            });
+        })
+        .await
+    }
+
+    // records the outputs together with the checkpoint used to resume the run
+    pub async fn finish(&mut self, outputs: &HashMap<String, Value>, simulations: Vec<Simulation>) {
+        self.record.signatures = outputs
+            .values()
+            .filter_map(|value| match value {
+                Value::Success(signature) => Some(*signature),
+                _ => None,
+            })
+            .collect();
+
+        self.record.print_output = match outputs.get("__print_output") {
+            Some(Value::String(output)) => Some(output.clone()),
+            _ => None,
+        };
+
+        self.record.outputs = outputs.clone();
+        self.record.simulations = simulations;
+
+        match serde_json::to_value(outputs) {
+            Ok(checkpoint) => {
+                self.props.insert(CHECKPOINT_MARKER.to_owned(), checkpoint);
+            }
+            Err(e) => tracing::error!(error = %e, "failed to checkpoint outputs of command"),
+        }
+
+        self.write().await
+    }
+
+    pub async fn fail(&mut self, simulations: Vec<Simulation>, state: RunState) {
+        self.record.simulations = simulations;
+        self.set_state(state).await
+    }
+
+    async fn update(&mut self, update: impl FnOnce(&mut NodeRunRecord)) {
+        update(&mut self.record);
+        self.write().await;
+        self.notify();
+    }
+
+    async fn write(&self) {
+        let record = match serde_json::to_value(&self.record) {
+            Ok(record) => record,
+            Err(e) => {
+                tracing::error!(error = %e, "failed to serialize log of command");
+                return;
+            }
+        };
+
+        let mut props = self.props.clone();
+        props.insert(NODE_RUN_RECORD_MARKER.to_owned(), record);
+
+        if let Err(e) = self
+            .db
+            .update_node((self.log_node_id, props), self.run_id)
+            .await
+        {
+            tracing::error!(error = %e, "failed to update logs for command");
+        }
+    }
+}
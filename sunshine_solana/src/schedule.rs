@@ -0,0 +1,325 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Schedule {
+    Once,
+    Recurring(RecurringSchedule),
+}
+
+impl Schedule {
+    pub fn interval(period: Duration, overlap: OverlapPolicy) -> Schedule {
+        Schedule::Recurring(RecurringSchedule::new(Recurrence::Interval(period), overlap))
+    }
+
+    // accepts standard 5 field expressions, whose days of the week are 0-7 with Sunday as
+    // 0 or 7, as well as the 6/7 field form with seconds/years of the cron crate, whose days
+    // of the week are 1-7 with Sunday as 1
+    pub fn cron(expression: impl Into<String>, overlap: OverlapPolicy) -> Schedule {
+        Schedule::Recurring(RecurringSchedule::new(
+            Recurrence::Cron(expression.into()),
+            overlap,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecurringSchedule {
+    pub recurrence: Recurrence,
+    // interval ticks are aligned to this, so they don't drift when the flow is redeployed
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub max_runs: Option<u64>,
+    pub overlap: OverlapPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Recurrence {
+    Interval(Duration),
+    Cron(String),
+}
+
+// what to do when a tick fires while the previous run is still in progress
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OverlapPolicy {
+    Skip,
+    Queue,
+    AllowConcurrent,
+}
+
+impl RecurringSchedule {
+    pub fn new(recurrence: Recurrence, overlap: OverlapPolicy) -> RecurringSchedule {
+        RecurringSchedule {
+            recurrence,
+            start: None,
+            end: None,
+            max_runs: None,
+            overlap,
+        }
+    }
+
+    pub fn starting_at(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn ending_at(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn with_max_runs(mut self, max_runs: u64) -> Self {
+        self.max_runs = Some(max_runs);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        match &self.recurrence {
+            Recurrence::Interval(period) => {
+                if period.as_millis() == 0 {
+                    return Err(Error::InvalidSchedule(
+                        "interval must be at least 1ms".to_owned(),
+                    ));
+                }
+            }
+            Recurrence::Cron(expression) => {
+                parse_cron(expression)?;
+            }
+        }
+
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if end <= start {
+                return Err(Error::InvalidSchedule(
+                    "end time is before start time".to_owned(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // returns the first tick strictly after `after`, or None when the schedule is exhausted
+    pub fn next_after(
+        &self,
+        after: DateTime<Utc>,
+        run_count: u64,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        if let Some(max_runs) = self.max_runs {
+            if run_count >= max_runs {
+                return Ok(None);
+            }
+        }
+
+        let next = match &self.recurrence {
+            Recurrence::Interval(period) => {
+                let anchor = self.start.unwrap_or(after);
+                let period = period.as_millis() as i64;
+
+                if period == 0 {
+                    return Err(Error::InvalidSchedule(
+                        "interval must be at least 1ms".to_owned(),
+                    ));
+                }
+
+                if after < anchor {
+                    anchor
+                } else {
+                    let elapsed = (after - anchor).num_milliseconds();
+                    let ticks = elapsed / period + 1;
+
+                    anchor + chrono::Duration::milliseconds(ticks * period)
+                }
+            }
+            Recurrence::Cron(expression) => {
+                let after = match self.start {
+                    Some(start) if start > after => start - chrono::Duration::seconds(1),
+                    _ => after,
+                };
+
+                match parse_cron(expression)?.after(&after).next() {
+                    Some(next) => next,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        match self.end {
+            Some(end) if next > end => Ok(None),
+            _ => Ok(Some(next)),
+        }
+    }
+}
+
+fn parse_cron(expression: &str) -> Result<cron::Schedule, Error> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+
+    // the cron crate expects a leading seconds field and counts days of the week from 1
+    let expression = match fields.as_slice() {
+        [minutes, hours, days, months, weekdays] => format!(
+            "0 {} {} {} {} {}",
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays_from_sunday_one(weekdays)?
+        ),
+        _ => expression.to_owned(),
+    };
+
+    cron::Schedule::from_str(&expression).map_err(|e| Error::InvalidSchedule(e.to_string()))
+}
+
+// translates days of the week from 0-7 with Sunday as 0 or 7 to 1-7 with Sunday as 1.
+// names like MON-FRI mean the same in both
+fn weekdays_from_sunday_one(field: &str) -> Result<String, Error> {
+    let invalid = || Error::InvalidSchedule(format!("invalid day of the week: {}", field));
+
+    let day = |day: &str| -> Result<Option<u32>, Error> {
+        match day.parse::<u32>() {
+            Ok(day) if day <= 7 => Ok(Some(day % 7 + 1)),
+            Ok(_) => Err(invalid()),
+            Err(_) => Ok(None),
+        }
+    };
+
+    let mut translated = Vec::new();
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+
+        let with_step = |range: String| match step {
+            Some(step) => format!("{}/{}", range, step),
+            None => range,
+        };
+
+        // a single day with a step steps through the rest of the week, like a range up to 7
+        let range = match step {
+            Some(_) if !range.contains('-') && range.parse::<u32>().is_ok() => {
+                format!("{}-7", range)
+            }
+            _ => range.to_owned(),
+        };
+
+        match range.split_once('-') {
+            Some((first, last)) => match (day(first)?, last.parse::<u32>()) {
+                // only Sunday
+                (Some(_), Ok(7)) if first == "7" => translated.push("1".to_owned()),
+                // Sunday as 7 ends the range, which becomes the range up to Saturday and
+                // Sunday on its own when the range reaches it
+                (Some(first), Ok(7)) => {
+                    translated.push(with_step(format!("{}-7", first)));
+
+                    let sunday = match step {
+                        Some(step) => step
+                            .parse::<u32>()
+                            .map_or(false, |step| step > 0 && (8 - first) % step == 0),
+                        None => true,
+                    };
+
+                    if sunday && first > 1 {
+                        translated.push("1".to_owned());
+                    }
+                }
+                (Some(first), _) => match day(last)? {
+                    Some(last) => translated.push(with_step(format!("{}-{}", first, last))),
+                    None => return Err(invalid()),
+                },
+                (None, _) => translated.push(part.to_owned()),
+            },
+            None => match day(&range)? {
+                Some(day) => translated.push(with_step(day.to_string())),
+                None => translated.push(part.to_owned()),
+            },
+        }
+    }
+
+    Ok(translated.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn interval_ticks_are_aligned_to_start() {
+        let start = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let schedule = RecurringSchedule::new(
+            Recurrence::Interval(Duration::from_secs(60)),
+            OverlapPolicy::Skip,
+        )
+        .starting_at(start);
+
+        let after = Utc.ymd(2022, 1, 1).and_hms(0, 2, 30);
+
+        assert_eq!(
+            schedule.next_after(after, 0).unwrap(),
+            Some(Utc.ymd(2022, 1, 1).and_hms(0, 3, 0))
+        );
+        assert_eq!(
+            schedule
+                .next_after(start - chrono::Duration::seconds(5), 0)
+                .unwrap(),
+            Some(start)
+        );
+    }
+
+    #[test]
+    fn cron_respects_end_and_max_runs() {
+        let schedule = RecurringSchedule::new(
+            Recurrence::Cron("0 0 * * *".to_owned()),
+            OverlapPolicy::Queue,
+        )
+        .ending_at(Utc.ymd(2022, 1, 3).and_hms(12, 0, 0))
+        .with_max_runs(5);
+
+        let after = Utc.ymd(2022, 1, 1).and_hms(8, 0, 0);
+
+        assert_eq!(
+            schedule.next_after(after, 0).unwrap(),
+            Some(Utc.ymd(2022, 1, 2).and_hms(0, 0, 0))
+        );
+        assert_eq!(
+            schedule
+                .next_after(Utc.ymd(2022, 1, 3).and_hms(0, 0, 0), 0)
+                .unwrap(),
+            None
+        );
+        assert_eq!(schedule.next_after(after, 5).unwrap(), None);
+    }
+
+    #[test]
+    fn five_field_cron_counts_sunday_as_zero_or_seven() {
+        // a saturday
+        let after = Utc.ymd(2022, 1, 1).and_hms(8, 0, 0);
+
+        let next = |expression: &str| {
+            RecurringSchedule::new(Recurrence::Cron(expression.to_owned()), OverlapPolicy::Skip)
+                .next_after(after, 0)
+                .unwrap()
+        };
+
+        let sunday = Some(Utc.ymd(2022, 1, 2).and_hms(0, 0, 0));
+        let monday = Some(Utc.ymd(2022, 1, 3).and_hms(0, 0, 0));
+
+        assert_eq!(next("0 0 * * 0"), sunday);
+        assert_eq!(next("0 0 * * 7"), sunday);
+        assert_eq!(next("0 0 * * 1-5"), monday);
+        assert_eq!(next("0 0 * * 1-7"), sunday);
+        assert_eq!(next("0 0 * * 0-7"), sunday);
+        assert_eq!(next("0 0 * * 1-7/3"), sunday);
+        assert_eq!(next("0 0 * * 3/2"), sunday);
+        assert_eq!(next("0 0 * * 7/2"), sunday);
+        assert_eq!(next("0 0 * * MON"), monday);
+        assert_eq!(next("0 0 0 * * 2 *"), monday);
+        assert!(parse_cron("0 0 * * 8").is_err());
+    }
+}
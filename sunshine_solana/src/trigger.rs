@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use solana_client::pubsub_client::{
+    PubsubAccountClientSubscription, PubsubClient, PubsubLogsClientSubscription,
+};
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use sunshine_core::msg::{Graph, NodeId};
+use tokio::sync::{mpsc, watch};
+
+use crate::commands::solana::SolanaNet;
+use crate::error::Error;
+use crate::{Engine, FlowContext, FlowId, RunOptions, Value};
+
+pub const TRIGGER_MARKER: &str = "TRIGGER_MARKER";
+
+// stored in the properties of a start node under TRIGGER_MARKER
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trigger {
+    pub kind: TriggerKind,
+    // input of the start node which receives the event payload as Value::Json
+    pub input: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TriggerKind {
+    Webhook { path: String },
+    AccountChange { solana_net: SolanaNet, pubkey: String },
+    Logs { solana_net: SolanaNet, mentions: String },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WebhookBinding {
+    pub flow_id: FlowId,
+    pub node_id: NodeId,
+    pub input: String,
+}
+
+pub(crate) fn read_triggers(graph: &Graph) -> Result<Vec<(NodeId, Trigger)>, Error> {
+    let mut triggers = Vec::new();
+
+    for node in graph.nodes.iter() {
+        if let Some(trigger) = node.properties.get(TRIGGER_MARKER) {
+            triggers.push((node.node_id, serde_json::from_value(trigger.clone())?));
+        }
+    }
+
+    Ok(triggers)
+}
+
+// forwards every notification of the subscription as a run of the flow until stop_signal fires
+pub(crate) async fn spawn_subscription(
+    engine: Engine,
+    flow_id: FlowId,
+    node_id: NodeId,
+    trigger: Trigger,
+    mut stop_signal: watch::Receiver<u8>,
+) -> Result<(), Error> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<JsonValue>();
+
+    let kind = trigger.kind.clone();
+
+    let subscription = tokio::task::spawn_blocking(move || match kind {
+        TriggerKind::AccountChange { solana_net, pubkey } => {
+            let pubkey = Pubkey::from_str(&pubkey)?;
+
+            let (subscription, receiver) =
+                PubsubClient::account_subscribe(solana_net.ws_url().as_str(), &pubkey, None)
+                    .map_err(|e| Error::Subscription(e.to_string()))?;
+
+            std::thread::spawn(move || {
+                for notification in receiver.iter() {
+                    let payload = serde_json::to_value(&notification).unwrap_or_default();
+                    if tx.send(payload).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            Ok(Subscription::Account(subscription))
+        }
+        TriggerKind::Logs {
+            solana_net,
+            mentions,
+        } => {
+            let (subscription, receiver) = PubsubClient::logs_subscribe(
+                solana_net.ws_url().as_str(),
+                RpcTransactionLogsFilter::Mentions(vec![mentions]),
+                RpcTransactionLogsConfig { commitment: None },
+            )
+            .map_err(|e| Error::Subscription(e.to_string()))?;
+
+            std::thread::spawn(move || {
+                for notification in receiver.iter() {
+                    let payload = serde_json::to_value(&notification).unwrap_or_default();
+                    if tx.send(payload).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            Ok(Subscription::Logs(subscription))
+        }
+        TriggerKind::Webhook { path } => Err(Error::Subscription(format!(
+            "webhook {} isn't a solana subscription",
+            path
+        ))),
+    })
+    .await
+    .map_err(|e| Error::Subscription(e.to_string()))??;
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                payload = rx.recv() => {
+                    let payload = match payload {
+                        Some(payload) => payload,
+                        None => {
+                            tracing::warn!(%flow_id, "subscription closed");
+                            break;
+                        }
+                    };
+
+                    let start_inputs = start_inputs(node_id, &trigger.input, payload);
+
+                    let run = FlowContext::run_flow(
+                        engine.clone(),
+                        flow_id,
+                        start_inputs,
+                        HashMap::new(),
+                        RunOptions::default(),
+                    );
+
+                    if let Err(e) = run.await {
+                        tracing::error!(%flow_id, error = %e, "failed to run flow");
+                    }
+                }
+                _ = stop_signal.changed() => break,
+            }
+        }
+
+        tokio::task::spawn_blocking(move || {
+            let mut subscription = subscription;
+            subscription.shutdown();
+        })
+        .await
+        .ok();
+    });
+
+    Ok(())
+}
+
+pub(crate) fn start_inputs(
+    node_id: NodeId,
+    input: &str,
+    payload: JsonValue,
+) -> HashMap<NodeId, HashMap<String, Value>> {
+    let mut inputs = HashMap::new();
+
+    inputs.insert(input.to_owned(), Value::Json(payload.into()));
+
+    let mut start_inputs = HashMap::new();
+
+    start_inputs.insert(node_id, inputs);
+
+    start_inputs
+}
+
+enum Subscription {
+    Account(PubsubAccountClientSubscription),
+    Logs(PubsubLogsClientSubscription),
+}
+
+impl Subscription {
+    fn shutdown(&mut self) {
+        let res = match self {
+            Subscription::Account(subscription) => {
+                subscription.send_unsubscribe().ok();
+                subscription.shutdown()
+            }
+            Subscription::Logs(subscription) => {
+                subscription.send_unsubscribe().ok();
+                subscription.shutdown()
+            }
+        };
+
+        if res.is_err() {
+            tracing::warn!("failed to shut down solana subscription");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::simple::timer::Delay;
+    use crate::{FlowBuilder, RunState};
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use serde_json::json;
+    use sunshine_indra::store::{DbConfig, DB};
+
+    fn test_flows(dir: &tempfile::TempDir) -> FlowContext {
+        let db = DB::new(&DbConfig {
+            db_path: dir.path().join("db").to_str().unwrap().to_owned(),
+        })
+        .unwrap();
+
+        FlowContext::new(Arc::new(db))
+    }
+
+    // a start node passing the payload of the trigger on
+    fn triggered_flow(trigger: JsonValue) -> FlowBuilder {
+        FlowBuilder::new()
+            .start_node(
+                "hook",
+                Delay {
+                    duration_ms: Some(0),
+                },
+            )
+            .node_property("hook", TRIGGER_MARKER, trigger)
+    }
+
+    #[test]
+    fn triggers_are_read_from_start_nodes() {
+        let node_id = NodeId::new_v4();
+
+        let mut properties = sunshine_core::msg::Properties::new();
+        properties.insert(
+            TRIGGER_MARKER.to_owned(),
+            json!({ "kind": { "Webhook": { "path": "deploy" } }, "input": "value" }),
+        );
+
+        let graph = Graph {
+            nodes: vec![sunshine_core::msg::Node {
+                node_id,
+                properties,
+                outbound_edges: Vec::new(),
+                inbound_edges: Vec::new(),
+            }],
+            edges: HashMap::new(),
+            state_id: 0,
+        };
+
+        let triggers = read_triggers(&graph).unwrap();
+
+        assert_eq!(triggers.len(), 1);
+        assert_eq!(triggers[0].0, node_id);
+        assert_eq!(triggers[0].1.input, "value");
+        assert!(matches!(
+            &triggers[0].1.kind,
+            TriggerKind::Webhook { path } if path == "deploy"
+        ));
+    }
+
+    #[tokio::test]
+    async fn webhooks_start_runs_with_their_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = test_flows(&dir);
+
+        let file = triggered_flow(
+            json!({ "kind": { "Webhook": { "path": "deploy" } }, "input": "value" }),
+        )
+        .build()
+        .unwrap();
+
+        let flow_id = flows.import_flow(&file).await.unwrap();
+
+        assert_eq!(flows.enable_triggers(flow_id).await.unwrap(), 1);
+
+        let payload = json!({ "ref": "main" });
+
+        let run_id = flows
+            .handle_webhook("deploy", payload.clone())
+            .await
+            .unwrap();
+
+        for _ in 0..100 {
+            let records = flows.get_run(run_id).await.unwrap();
+
+            if let Some(record) = records
+                .values()
+                .find(|record| matches!(record.state(), Some(RunState::Success(_))))
+            {
+                match record.outputs.get("value") {
+                    Some(Value::Json(json)) => assert_eq!(JsonValue::from(json.clone()), payload),
+                    other => panic!("unexpected output: {:?}", other),
+                }
+
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        panic!("run didn't finish");
+    }
+
+    #[tokio::test]
+    async fn failed_triggers_dont_bind_webhooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = test_flows(&dir);
+
+        let file = triggered_flow(
+            json!({ "kind": { "Webhook": { "path": "deploy" } }, "input": "value" }),
+        )
+        .node(
+            "account",
+            Delay {
+                duration_ms: Some(0),
+            },
+        )
+        .node_property(
+            "account",
+            TRIGGER_MARKER,
+            json!({
+                "kind": { "AccountChange": { "solana_net": "Devnet", "pubkey": "not a pubkey" } },
+                "input": "value",
+            }),
+        )
+        .build()
+        .unwrap();
+
+        let flow_id = flows.import_flow(&file).await.unwrap();
+
+        assert!(flows.enable_triggers(flow_id).await.is_err());
+        assert!(matches!(
+            flows.handle_webhook("deploy", JsonValue::Null).await,
+            Err(Error::WebhookNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sunshine_core::msg::{Graph, NodeId};
+use sunshine_core::store::Datastore;
+
+use crate::error::Error;
+use crate::{cache, commands, input, interface, ports, trigger, variables};
+use crate::{
+    FlowId, COMMAND_MARKER, COMMAND_NAME_MARKER, CTX_EDGE_MARKER, CTX_MARKER, FLOW_INPUTS_MARKER,
+    FLOW_OUTPUTS_MARKER, INPUT_ARG_NAME_MARKER, OUTPUT_ARG_NAME_MARKER, START_NODE_MARKER,
+};
+
+// something which would make a run of the flow fail or never finish
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Problem {
+    pub node_id: Option<NodeId>,
+    pub message: String,
+}
+
+impl Problem {
+    fn flow(message: impl Into<String>) -> Problem {
+        Problem {
+            node_id: None,
+            message: message.into(),
+        }
+    }
+
+    fn node(node_id: NodeId, message: impl Into<String>) -> Problem {
+        Problem {
+            node_id: Some(node_id),
+            message: message.into(),
+        }
+    }
+}
+
+// checks what read_flow would otherwise only find out when the flow is run,
+// an empty list means the flow can be run
+pub async fn validate_flow(
+    db: &Arc<dyn Datastore>,
+    flow_id: FlowId,
+) -> Result<Vec<Problem>, Error> {
+    let root = db.read_node(flow_id).await?;
+    let graph = db.read_graph(flow_id).await?;
+
+    let mut problems = Vec::new();
+
+    if let Err(e) = variables::read_defaults(&root.properties) {
+        problems.push(Problem::flow(e.to_string()));
+    }
+
+    if let Err(e) = trigger::read_triggers(&graph) {
+        problems.push(Problem::flow(e.to_string()));
+    }
+
+    let contexts: HashSet<NodeId> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.properties.contains_key(CTX_MARKER))
+        .map(|node| node.node_id)
+        .collect();
+
+    let mut has_start_node = false;
+
+    for node in graph.nodes.iter() {
+        let props = &node.properties;
+
+        if let Some(cfg) = props.get(CTX_MARKER) {
+            if let Err(e) = serde_json::from_value::<commands::solana::Config>(cfg.clone()) {
+                problems.push(Problem::node(
+                    node.node_id,
+                    format!("invalid context: {}", e),
+                ));
+            }
+        }
+
+        let cfg = match props.get(COMMAND_MARKER) {
+            Some(cfg) => cfg,
+            None => continue,
+        };
+
+        has_start_node |= props.contains_key(START_NODE_MARKER);
+
+        match serde_json::from_value::<commands::Config>(cfg.clone()) {
+            Ok(commands::Config::Solana(_)) => {
+                let mut has_ctx = false;
+
+                for edge in node.inbound_edges.iter() {
+                    let edge_props = edge_properties(db, &graph, edge).await?;
+
+                    has_ctx |=
+                        edge_props.contains_key(CTX_EDGE_MARKER) && contexts.contains(&edge.from);
+                }
+
+                if !has_ctx {
+                    problems.push(Problem::node(
+                        node.node_id,
+                        "solana command isn't connected to a context",
+                    ));
+                }
+            }
+            Ok(commands::Config::Simple(_)) => (),
+            Err(e) => {
+                problems.push(Problem::node(
+                    node.node_id,
+                    format!("invalid command: {}", e),
+                ));
+            }
+        }
+
+        if !matches!(props.get(COMMAND_NAME_MARKER), Some(name) if name.is_string()) {
+            problems.push(Problem::node(node.node_id, "command has no name"));
+        }
+
+        if let Err(e) = cache::read_policy(props) {
+            problems.push(Problem::node(
+                node.node_id,
+                format!("invalid cache policy: {}", e),
+            ));
+        }
+
+        if let Err(e) = input::read_merge_strategies(props) {
+            problems.push(Problem::node(
+                node.node_id,
+                format!("invalid merge strategies: {}", e),
+            ));
+        }
+
+        for marker in [FLOW_INPUTS_MARKER, FLOW_OUTPUTS_MARKER] {
+            if let Err(e) = interface::read_bindings(props, marker) {
+                problems.push(Problem::node(
+                    node.node_id,
+                    format!("invalid {}: {}", marker, e),
+                ));
+            }
+        }
+    }
+
+    if !has_start_node {
+        problems.push(Problem::flow("flow has no start node"));
+    }
+
+    let commands: HashSet<NodeId> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.properties.contains_key(COMMAND_MARKER))
+        .map(|node| node.node_id)
+        .collect();
+
+    let configs: HashMap<NodeId, commands::Config> = graph
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let cfg = node.properties.get(COMMAND_MARKER)?;
+            Some((node.node_id, serde_json::from_value(cfg.clone()).ok()?))
+        })
+        .collect();
+
+    let mut successors: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+    let mut connected_inputs: HashMap<(NodeId, String), usize> = HashMap::new();
+
+    for node in graph.nodes.iter() {
+        if !commands.contains(&node.node_id) {
+            continue;
+        }
+
+        for edge in node.outbound_edges.iter() {
+            if !commands.contains(&edge.to) {
+                problems.push(Problem::node(
+                    node.node_id,
+                    "edge leads to a node without a command",
+                ));
+                continue;
+            }
+
+            let edge_props = edge_properties(db, &graph, edge).await?;
+
+            for marker in [INPUT_ARG_NAME_MARKER, OUTPUT_ARG_NAME_MARKER] {
+                if !matches!(edge_props.get(marker), Some(name) if name.is_string()) {
+                    problems.push(Problem::node(
+                        node.node_id,
+                        format!("edge to {} has no {}", edge.to, marker),
+                    ));
+                }
+            }
+
+            let output = edge_props
+                .get(OUTPUT_ARG_NAME_MARKER)
+                .and_then(|n| n.as_str());
+
+            if let Some(input) = edge_props
+                .get(INPUT_ARG_NAME_MARKER)
+                .and_then(|n| n.as_str())
+            {
+                *connected_inputs
+                    .entry((edge.to, input.to_owned()))
+                    .or_default() += 1;
+
+                if let (Some(output), Some(from_cfg), Some(to_cfg)) =
+                    (output, configs.get(&edge.from), configs.get(&edge.to))
+                {
+                    let check = ports::check_edge(from_cfg, output, to_cfg, input);
+
+                    if let Some(e) = check.error(output, input) {
+                        problems.push(Problem::node(
+                            node.node_id,
+                            format!("edge to {}: {}", edge.to, e),
+                        ));
+                    }
+                }
+            }
+
+            successors.entry(edge.from).or_default().push(edge.to);
+        }
+    }
+
+    for node in graph.nodes.iter() {
+        let merge = input::read_merge_strategies(&node.properties).unwrap_or_default();
+
+        for ((node_id, input), count) in connected_inputs.iter() {
+            if *node_id == node.node_id && *count > 1 && !merge.contains_key(input) {
+                problems.push(Problem::node(
+                    node.node_id,
+                    format!("input {} has {} edges but no merge strategy", input, count),
+                ));
+            }
+        }
+    }
+
+    // a node on a cycle waits for its own output and the run never finishes
+    let sorted = pathfinding::directed::topological_sort::topological_sort(
+        &commands.iter().copied().collect::<Vec<_>>(),
+        |node_id| successors.get(node_id).cloned().unwrap_or_default(),
+    );
+
+    if let Err(node_id) = sorted {
+        problems.push(Problem::node(node_id, "node is part of a cycle"));
+    }
+
+    Ok(problems)
+}
+
+async fn edge_properties(
+    db: &Arc<dyn Datastore>,
+    graph: &Graph,
+    edge: &sunshine_core::msg::Edge,
+) -> Result<sunshine_core::msg::Properties, Error> {
+    match graph.edges.get(&edge.id) {
+        Some(properties) => Ok(properties.clone()),
+        None => Ok(db.read_edge_properties(*edge).await?),
+    }
+}
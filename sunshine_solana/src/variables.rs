@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use serde_json::Value as JsonValue;
+use sunshine_core::msg::Properties;
+use sunshine_core::store::Datastore;
+
+use crate::error::Error;
+use crate::{FlowId, Value};
+
+// json object in the flow root properties with the default value of every flow variable,
+// e.g. {"collection_mint": "...", "api_token": "..."}
+pub const FLOW_VARIABLES_MARKER: &str = "FLOW_VARIABLES_MARKER";
+// flow inputs and overrides passed to deploy_flow, persisted next to the schedule
+pub const DEPLOY_INPUTS_MARKER: &str = "DEPLOY_INPUTS_MARKER";
+// variables a run was started with, stored on the log graph so that a resumed run sees them
+pub const RUN_VARIABLES_MARKER: &str = "RUN_VARIABLES_MARKER";
+
+pub(crate) fn read_defaults(props: &Properties) -> Result<HashMap<String, Value>, Error> {
+    match props.get(FLOW_VARIABLES_MARKER) {
+        Some(JsonValue::Object(variables)) => variables
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), Value::try_from(value.clone())?)))
+            .collect(),
+        Some(JsonValue::Null) | None => Ok(HashMap::new()),
+        Some(_) => Err(Error::InvalidFlowVariables),
+    }
+}
+
+// the defaults of the flow with `overrides` applied, only variables the flow defines
+// can be overridden
+pub(crate) async fn resolve(
+    db: &Arc<dyn Datastore>,
+    flow_id: FlowId,
+    overrides: HashMap<String, Value>,
+) -> Result<HashMap<String, Value>, Error> {
+    let props = db.read_node(flow_id).await?.properties;
+
+    let mut variables = read_defaults(&props)?;
+
+    for (name, value) in overrides {
+        if !variables.contains_key(&name) {
+            return Err(Error::UnknownFlowVariable(name));
+        }
+
+        variables.insert(name, value);
+    }
+
+    Ok(variables)
+}
+
+// values persisted with serde keep their variant, unlike the plain json of the defaults
+pub(crate) fn read_persisted(
+    props: &Properties,
+    marker: &str,
+) -> Result<HashMap<String, Value>, Error> {
+    match props.get(marker) {
+        Some(variables) => Ok(serde_json::from_value(variables.clone())?),
+        None => Ok(HashMap::new()),
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sunshine_core::msg::{GraphId, Properties};
+use sunshine_core::store::Datastore;
+
+use crate::error::Error;
+use crate::{flow_file, update_flow_props, FlowId};
+
+// versions published from a flow, kept on the root of the flow
+pub const FLOW_VERSIONS_MARKER: &str = "FLOW_VERSIONS_MARKER";
+// on the root of a snapshot graph, which is never edited
+pub const SNAPSHOT_MARKER: &str = "SNAPSHOT_MARKER";
+// on the log graph of a run of a published version
+pub const FLOW_VERSION_MARKER: &str = "FLOW_VERSION_MARKER";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowVersion {
+    pub version: u64,
+    // the snapshot graph the version is run from
+    pub graph_id: GraphId,
+    pub published_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub flow_id: FlowId,
+    pub version: u64,
+}
+
+pub(crate) fn read_versions(props: &Properties) -> Result<Vec<FlowVersion>, Error> {
+    match props.get(FLOW_VERSIONS_MARKER) {
+        Some(versions) => Ok(serde_json::from_value(versions.clone())?),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub(crate) fn read_snapshot(props: &Properties) -> Result<Option<Snapshot>, Error> {
+    match props.get(SNAPSHOT_MARKER) {
+        Some(snapshot) => Ok(Some(serde_json::from_value(snapshot.clone())?)),
+        None => Ok(None),
+    }
+}
+
+// copies the current graph of the flow into a new snapshot graph, versions count up from 1
+pub(crate) async fn publish(
+    db: &Arc<dyn Datastore>,
+    flow_id: FlowId,
+) -> Result<FlowVersion, Error> {
+    let props = db.read_node(flow_id).await?.properties;
+
+    if read_snapshot(&props)?.is_some() {
+        return Err(Error::SnapshotIsImmutable(flow_id));
+    }
+
+    let mut versions = read_versions(&props)?;
+
+    let version = versions.last().map_or(1, |last| last.version + 1);
+
+    let mut file = flow_file::export_flow(db, flow_id).await?;
+
+    file.properties.insert(
+        SNAPSHOT_MARKER.to_owned(),
+        serde_json::to_value(Snapshot { flow_id, version })?,
+    );
+
+    let graph_id = flow_file::import_flow(db, &file).await?;
+
+    let published = FlowVersion {
+        version,
+        graph_id,
+        published_at: Utc::now(),
+    };
+
+    versions.push(published.clone());
+
+    let persisted = serde_json::to_value(&versions)?;
+
+    update_flow_props(db, flow_id, |props| {
+        props.insert(FLOW_VERSIONS_MARKER.to_owned(), persisted);
+    })
+    .await?;
+
+    Ok(published)
+}
+
+// the graph to run for the version, the live graph of the flow without one
+pub(crate) async fn graph_of(
+    db: &Arc<dyn Datastore>,
+    flow_id: FlowId,
+    version: Option<u64>,
+) -> Result<GraphId, Error> {
+    let version = match version {
+        Some(version) => version,
+        None => return Ok(flow_id),
+    };
+
+    let props = db.read_node(flow_id).await?.properties;
+
+    read_versions(&props)?
+        .into_iter()
+        .find(|published| published.version == version)
+        .map(|published| published.graph_id)
+        .ok_or(Error::VersionDoesntExist(version))
+}
+
+pub(crate) fn read_version(props: &Properties, marker: &str) -> Option<u64> {
+    props.get(marker).and_then(JsonValue::as_u64)
+}